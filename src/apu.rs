@@ -3,6 +3,8 @@
 //! audio plumbing and basic tone generation while leaving room for future
 //! accuracy improvements.
 
+use crate::debug::{AccessKind, AddressSpace, Breakpoints};
+
 const AUDIO_RAM_SIZE: usize = 0x10000; // 64KB
 const DSP_REGISTER_SPACE: usize = 0x80; // $00-$7F
 pub const SAMPLE_RATE: u32 = 32_000;
@@ -45,6 +47,12 @@ impl Apu {
     /// Run the SPC700 for a small number of cycles. This is a placeholder that
     /// keeps the core alive without attempting exact timing.
     pub fn step_spc(&mut self, cycles: u32) {
+        self.step_spc_with(cycles, None);
+    }
+
+    /// Run the SPC700 like `step_spc`, checking ARAM accesses against the
+    /// given breakpoints.
+    pub fn step_spc_with(&mut self, cycles: u32, mut breakpoints: Option<&mut Breakpoints>) {
         for _ in 0..cycles {
             // Split-borrow the APU so the SPC core can access RAM/DSP safely.
             let mut bus = ApuBusView {
@@ -53,6 +61,7 @@ impl Apu {
                 dsp_addr: &mut self.dsp_addr,
                 cpu_ports: &self.cpu_ports,
                 spc_ports: &mut self.spc_ports,
+                breakpoints: breakpoints.as_deref_mut(),
             };
             let _ = self.spc.step(&mut bus);
         }
@@ -81,6 +90,24 @@ impl Apu {
         self.spc_ports[index]
     }
 
+    /// Audio RAM.
+    pub fn ram(&self) -> &[u8] {
+        &self.ram[..]
+    }
+
+    pub fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram[..]
+    }
+
+    /// DSP register file ($00-$7F), bypassing the write side effects of $F3.
+    pub fn dsp_registers(&self) -> &[u8] {
+        &self.dsp.registers
+    }
+
+    pub fn dsp_registers_mut(&mut self) -> &mut [u8] {
+        &mut self.dsp.registers
+    }
+
 }
 
 /// Simple SPC700 bus trait to decouple core from backing memory/DSP.
@@ -95,19 +122,27 @@ struct ApuBusView<'a> {
     dsp_addr: &'a mut u8,
     cpu_ports: &'a [u8; 4],
     spc_ports: &'a mut [u8; 4],
+    breakpoints: Option<&'a mut Breakpoints>,
 }
 
 impl SpcBus for ApuBusView<'_> {
     fn read8(&mut self, addr: u16) -> u8 {
-        match addr {
+        let value = match addr {
             0xF2 => *self.dsp_addr,
             0xF3 => self.dsp.read_register(*self.dsp_addr),
             0xF4..=0xF7 => self.cpu_ports[(addr - 0xF4) as usize],
             _ => self.ram[addr as usize],
+        };
+        if let Some(bps) = self.breakpoints.as_deref_mut() {
+            bps.check(AddressSpace::Aram, addr as u32, AccessKind::Read, value);
         }
+        value
     }
 
     fn write8(&mut self, addr: u16, value: u8) {
+        if let Some(bps) = self.breakpoints.as_deref_mut() {
+            bps.check(AddressSpace::Aram, addr as u32, AccessKind::Write, value);
+        }
         match addr {
             0xF2 => *self.dsp_addr = value & 0x7F,
             0xF3 => self.dsp.write_register(*self.dsp_addr, value, &self.ram[..]),
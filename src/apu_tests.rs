@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
     use crate::apu::{Apu, Spc700, Psw, SpcBus};
+    use crate::debug::{AccessKind, AddressSpace, Breakpoint, Breakpoints};
 
     #[test]
     fn test_apu_initialization() {
@@ -274,6 +275,27 @@ mod tests {
         assert!(apu.spc.cycles > initial_cycles);
     }
 
+    #[test]
+    fn test_aram_write_breakpoint() {
+        let mut apu = Apu::new();
+        let mut bps = Breakpoints::new();
+        bps.add(Breakpoint::write(AddressSpace::Aram, 0x0060, 0x0060));
+        
+        // MOV $60, A
+        apu.ram[0] = 0xC4;
+        apu.ram[1] = 0x60;
+        apu.spc.a = 0x77;
+        apu.spc.pc = 0;
+        
+        apu.step_spc_with(1, Some(&mut bps));
+        
+        let hit = bps.hit().unwrap();
+        assert_eq!(hit.kind, AccessKind::Write);
+        assert_eq!(hit.addr, 0x0060);
+        assert_eq!(hit.value, 0x77);
+        assert_eq!(apu.ram[0x60], 0x77);
+    }
+
     // Helper struct to provide bus access for testing
     struct TestBus<'a> {
         apu: &'a mut Apu,
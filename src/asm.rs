@@ -0,0 +1,776 @@
+//! 65816 Assembler
+//!
+//! A small two-pass assembler for tests and tools. It accepts the same
+//! operand syntax the disassembler prints, plus:
+//!
+//! * labels (`loop:`) and constants (`PORT = $2140`)
+//! * `org`, `db`/`.byte`, `dw`/`.word`, `dl`/`.long`
+//! * `.a8`/`.a16`/`.i8`/`.i16` to size M/X immediates
+//! * `.b`/`.w`/`.l` mnemonic suffixes to force an operand size
+//!
+//! Operand sizes follow the value: hex literals keep their written width
+//! (`$0012` is absolute), labels in the current bank use 16-bit forms, and
+//! forward references default to absolute addressing. An operand that turns
+//! out not to fit its chosen size is an error rather than being truncated.
+
+use std::collections::HashMap;
+
+use crate::debug::symbols::SymbolTable;
+use crate::disasm::{self, AddrMode};
+
+/// Address used when code appears before any `org`
+const DEFAULT_ORIGIN: u32 = 0x008000;
+
+/// A contiguous block of assembled bytes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Segment {
+    /// 24-bit CPU address of the first byte
+    pub addr: u32,
+    pub data: Vec<u8>,
+}
+
+/// Output of the assembler
+#[derive(Clone, Debug, Default)]
+pub struct Program {
+    pub segments: Vec<Segment>,
+    /// Every label defined in the source
+    pub labels: SymbolTable,
+}
+
+impl Program {
+    /// Address of the first segment
+    pub fn origin(&self) -> Option<u32> {
+        self.segments.first().map(|s| s.addr)
+    }
+
+    /// Flatten all segments into one image starting at `origin`
+    ///
+    /// Gaps between segments are zero filled; segments placed before the
+    /// origin are ignored.
+    pub fn bytes(&self) -> Vec<u8> {
+        let Some(origin) = self.origin() else {
+            return Vec::new();
+        };
+        let mut out = Vec::new();
+        for segment in &self.segments {
+            let Some(start) = segment.addr.checked_sub(origin) else {
+                continue;
+            };
+            let start = start as usize;
+            let end = start + segment.data.len();
+            if out.len() < end {
+                out.resize(end, 0);
+            }
+            out[start..end].copy_from_slice(&segment.data);
+        }
+        out
+    }
+}
+
+/// Assemble 65816 source into a program
+pub fn assemble(source: &str) -> Result<Program, String> {
+    let mut assembler = Assembler::new();
+    assembler.run(source, Pass::First)?;
+    assembler.run(source, Pass::Second)?;
+
+    Ok(Program {
+        segments: assembler.segments,
+        labels: assembler.symbols,
+    })
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Pass {
+    First,
+    Second,
+}
+
+/// Evaluated expression: value plus the operand width it asks for
+#[derive(Copy, Clone)]
+struct Value {
+    value: i64,
+    /// Minimum width in bytes implied by the source (0 = from the value)
+    width: u8,
+    /// Whether a label contributed, enabling same-bank 16-bit addressing
+    is_label: bool,
+}
+
+/// Operand syntax, before an addressing mode is chosen
+enum Operand<'a> {
+    None,
+    Accumulator,
+    Immediate(&'a str),
+    Indirect(&'a str),
+    IndexedIndirect(&'a str),
+    IndirectIndexed(&'a str),
+    StackIndirectIndexed(&'a str),
+    IndirectLong(&'a str),
+    IndirectLongIndexed(&'a str),
+    Stack(&'a str),
+    IndexedX(&'a str),
+    IndexedY(&'a str),
+    Pair(&'a str, &'a str),
+    Plain(&'a str),
+}
+
+struct Assembler {
+    pass: Pass,
+    pc: u32,
+    m8: bool,
+    x8: bool,
+    labels: HashMap<String, u32>,
+    /// Labels in source order, so the last of several at one address is shown
+    symbols: SymbolTable,
+    constants: HashMap<String, i64>,
+    /// Addressing mode and opcode chosen for each source line in the first pass
+    modes: HashMap<usize, (AddrMode, u8)>,
+    segments: Vec<Segment>,
+}
+
+impl Assembler {
+    fn new() -> Self {
+        Self {
+            pass: Pass::First,
+            pc: DEFAULT_ORIGIN,
+            m8: true,
+            x8: true,
+            labels: HashMap::new(),
+            symbols: SymbolTable::new(),
+            constants: HashMap::new(),
+            modes: HashMap::new(),
+            segments: Vec::new(),
+        }
+    }
+
+    fn run(&mut self, source: &str, pass: Pass) -> Result<(), String> {
+        self.pass = pass;
+        self.pc = DEFAULT_ORIGIN;
+        self.m8 = true;
+        self.x8 = true;
+        self.segments.clear();
+
+        for (index, line) in source.lines().enumerate() {
+            self.line(index, line).map_err(|e| format!("Line {}: {}", index + 1, e))?;
+        }
+        Ok(())
+    }
+
+    fn line(&mut self, index: usize, line: &str) -> Result<(), String> {
+        let mut line = strip_comment(line).trim();
+
+        // Leading labels
+        while let Some((name, rest)) = line.split_once(':') {
+            if !is_identifier(name.trim()) {
+                break;
+            }
+            self.define_label(name.trim())?;
+            line = rest.trim();
+        }
+        if line.is_empty() {
+            return Ok(());
+        }
+
+        // Constant definitions
+        if let Some((name, expr)) = line.split_once('=') {
+            let name = name.trim();
+            if is_identifier(name) {
+                if let Some(value) = self.eval(expr.trim())? {
+                    self.constants.insert(name.to_string(), value.value);
+                }
+                return Ok(());
+            }
+        }
+
+        let (word, rest) = match line.split_once(char::is_whitespace) {
+            Some((word, rest)) => (word, rest.trim()),
+            None => (line, ""),
+        };
+
+        match word.to_ascii_lowercase().as_str() {
+            "org" | ".org" => {
+                let value = self.eval_required(rest)?;
+                self.pc = (value.value as u32) & 0xFFFFFF;
+                self.segments.push(Segment { addr: self.pc, data: Vec::new() });
+                Ok(())
+            }
+            "db" | ".db" | ".byte" => self.data(rest, 1),
+            "dw" | ".dw" | ".word" => self.data(rest, 2),
+            "dl" | ".dl" | ".long" => self.data(rest, 3),
+            ".a8" => {
+                self.m8 = true;
+                Ok(())
+            }
+            ".a16" => {
+                self.m8 = false;
+                Ok(())
+            }
+            ".i8" => {
+                self.x8 = true;
+                Ok(())
+            }
+            ".i16" => {
+                self.x8 = false;
+                Ok(())
+            }
+            _ => self.instruction(index, word, rest),
+        }
+    }
+
+    fn define_label(&mut self, name: &str) -> Result<(), String> {
+        if self.pass == Pass::First {
+            if self.labels.insert(name.to_string(), self.pc).is_some() {
+                return Err(format!("Duplicate label: {}", name));
+            }
+            self.symbols.insert(self.pc, name);
+        }
+        Ok(())
+    }
+
+    fn emit(&mut self, bytes: &[u8]) {
+        if self.segments.is_empty() {
+            self.segments.push(Segment { addr: self.pc, data: Vec::new() });
+        }
+        if let Some(segment) = self.segments.last_mut() {
+            segment.data.extend_from_slice(bytes);
+        }
+        self.pc = self.pc.wrapping_add(bytes.len() as u32) & 0xFFFFFF;
+    }
+
+    /// db/dw/dl: comma separated expressions (and strings for db)
+    fn data(&mut self, args: &str, width: u8) -> Result<(), String> {
+        for item in split_args(args) {
+            if let Some(text) = item.strip_prefix('"') {
+                let text = text.strip_suffix('"').ok_or("Unterminated string")?;
+                if width != 1 {
+                    return Err("Strings are only allowed in db".to_string());
+                }
+                self.emit(text.as_bytes());
+                continue;
+            }
+            let value = match self.eval(item)? {
+                Some(v) => check_range(v.value, width)?,
+                None => 0,
+            };
+            let bytes = value.to_le_bytes();
+            self.emit(&bytes[..width as usize]);
+        }
+        Ok(())
+    }
+
+    fn instruction(&mut self, index: usize, word: &str, operand: &str) -> Result<(), String> {
+        let (mnemonic, forced) = match word.rsplit_once('.') {
+            Some((m, suffix)) => {
+                let width = match suffix.to_ascii_lowercase().as_str() {
+                    "b" => 1,
+                    "w" => 2,
+                    "l" => 3,
+                    _ => return Err(format!("Unknown size suffix: {}", word)),
+                };
+                (m, Some(width))
+            }
+            None => (word, None),
+        };
+        let mnemonic = mnemonic.to_ascii_uppercase();
+        if !(0..=255u8).any(|op| disasm::opcode_info(op).0 == mnemonic) {
+            return Err(format!("Unknown instruction: {}", word));
+        }
+
+        let operand: String = operand.chars().filter(|c| !c.is_whitespace()).collect();
+        let syntax = parse_operand(&operand);
+
+        let (mode, opcode) = match self.modes.get(&index) {
+            Some(&chosen) if self.pass == Pass::Second => chosen,
+            _ => {
+                let chosen = self.select_mode(&mnemonic, &syntax, forced)?;
+                self.modes.insert(index, chosen);
+                chosen
+            }
+        };
+
+        let len = disasm::instruction_length(mode, self.m8, self.x8);
+        let mut bytes = vec![opcode];
+        let operand_len = len as usize - 1;
+
+        match (&syntax, mode) {
+            (_, AddrMode::Implied | AddrMode::Accumulator) => {}
+            (Operand::Pair(src, dest), AddrMode::BlockMove) => {
+                let dest = self.eval_operand(dest)?;
+                let src = self.eval_operand(src)?;
+                bytes.push(check_range(dest, 1)? as u8);
+                bytes.push(check_range(src, 1)? as u8);
+            }
+            (Operand::Plain(expr), AddrMode::Relative | AddrMode::RelativeLong) => {
+                let next = (self.pc as u16).wrapping_add(len as u16);
+                let disp = match self.eval(expr)? {
+                    Some(target) => (target.value as u16).wrapping_sub(next) as i16 as i64,
+                    None => 0,
+                };
+                if mode == AddrMode::Relative && !(-128..=127).contains(&disp) {
+                    return Err(format!("Branch out of range: {}", expr));
+                }
+                bytes.extend_from_slice(&disp.to_le_bytes()[..operand_len]);
+            }
+            (_, AddrMode::Immediate8 | AddrMode::ImmediateM | AddrMode::ImmediateX | AddrMode::Immediate16) => {
+                let expr = operand_expr(&syntax).ok_or("Missing operand")?;
+                let value = check_range(self.eval_operand(expr)?, operand_len as u8)?;
+                bytes.extend_from_slice(&value.to_le_bytes()[..operand_len]);
+            }
+            _ => {
+                let expr = operand_expr(&syntax).ok_or("Missing operand")?;
+                let value = self.check_address(self.eval_operand(expr)?, operand_len, expr)?;
+                bytes.extend_from_slice(&value.to_le_bytes()[..operand_len]);
+            }
+        }
+
+        self.emit(&bytes);
+        Ok(())
+    }
+
+    /// Pick the addressing mode and opcode for an instruction
+    fn select_mode(&self, mnemonic: &str, syntax: &Operand, forced: Option<u8>) -> Result<(AddrMode, u8), String> {
+        use AddrMode::*;
+
+        let fixed: &[AddrMode] = match syntax {
+            Operand::None => &[Implied, Accumulator],
+            Operand::Accumulator => &[Accumulator],
+            Operand::Immediate(_) => &[Immediate8, ImmediateM, ImmediateX, Immediate16],
+            Operand::Indirect(_) => &[DirectIndirect, AbsoluteIndirect],
+            Operand::IndexedIndirect(_) => &[DirectIndexedIndirect, AbsoluteIndexedIndirect],
+            Operand::IndirectIndexed(_) => &[DirectIndirectIndexed],
+            Operand::StackIndirectIndexed(_) => &[StackRelativeIndirectIndexed],
+            Operand::IndirectLong(_) => &[DirectIndirectLong, AbsoluteIndirectLong],
+            Operand::IndirectLongIndexed(_) => &[DirectIndirectLongIndexed],
+            Operand::Stack(_) => &[StackRelative],
+            Operand::Pair(..) => &[BlockMove],
+            Operand::Plain(_) if lookup(mnemonic, Relative).is_some() => &[Relative],
+            Operand::Plain(_) if lookup(mnemonic, RelativeLong).is_some() => &[RelativeLong],
+            // PEA takes its operand without '#'
+            Operand::Plain(_) if lookup(mnemonic, Immediate16).is_some() => &[Immediate16],
+            Operand::IndexedX(_) | Operand::IndexedY(_) | Operand::Plain(_) => &[],
+        };
+        if !fixed.is_empty() {
+            return fixed
+                .iter()
+                .find_map(|&mode| lookup(mnemonic, mode).map(|op| (mode, op)))
+                .ok_or_else(|| format!("Invalid addressing mode for {}", mnemonic));
+        }
+
+        // Operand size decides between direct page, absolute and long forms
+        let (sized, expr): ([Option<AddrMode>; 3], &str) = match syntax {
+            Operand::IndexedX(e) => ([Some(DirectX), Some(AbsoluteX), Some(AbsoluteLongX)], e),
+            Operand::IndexedY(e) => ([Some(DirectY), Some(AbsoluteY), None], e),
+            Operand::Plain(e) => ([Some(Direct), Some(Absolute), Some(AbsoluteLong)], e),
+            _ => unreachable!(),
+        };
+        let width = match forced {
+            Some(width) => width,
+            None => match self.eval(expr)? {
+                Some(value) => self.operand_width(value),
+                None => 2,
+            },
+        };
+
+        for (i, mode) in sized.iter().enumerate().skip(width as usize - 1) {
+            let Some(mode) = *mode else {
+                continue;
+            };
+            if let Some(op) = lookup(mnemonic, mode) {
+                return Ok((mode, op));
+            }
+            // JMP/JSR to another bank become JML/JSL
+            if i == 2 {
+                let long = match mnemonic {
+                    "JMP" => lookup("JML", mode),
+                    "JSR" => lookup("JSL", mode),
+                    _ => None,
+                };
+                if let Some(op) = long {
+                    return Ok((mode, op));
+                }
+            }
+        }
+        Err(format!("Invalid addressing mode or operand size for {}", mnemonic))
+    }
+
+    /// Check an address operand is reachable with `len` bytes
+    ///
+    /// 16-bit operands may name any address in the current bank. Forward
+    /// references and forced sizes are only known to fit in the second pass.
+    fn check_address(&self, value: i64, len: usize, expr: &str) -> Result<i64, String> {
+        let fits = match len {
+            1 => (0..=0xFF).contains(&value),
+            2 => (0..=0xFFFF).contains(&value) || (value as u32 >> 16) == (self.pc >> 16),
+            _ => (0..=0xFFFFFF).contains(&value),
+        };
+        if !fits {
+            return Err(format!("Address ${:X} out of range for a {}-byte operand: {}", value, len, expr));
+        }
+        Ok(value)
+    }
+
+    /// Operand width in bytes implied by a value
+    fn operand_width(&self, value: Value) -> u8 {
+        let natural = if (0..=0xFF).contains(&value.value) {
+            1
+        } else if (0..=0xFFFF).contains(&value.value)
+            || (value.is_label && (value.value as u32 >> 16) == (self.pc >> 16))
+        {
+            2
+        } else {
+            3
+        };
+        natural.max(value.width)
+    }
+
+    fn eval_operand(&self, expr: &str) -> Result<i64, String> {
+        Ok(self.eval(expr)?.map_or(0, |v| v.value))
+    }
+
+    fn eval_required(&self, expr: &str) -> Result<Value, String> {
+        self.eval(expr)?.ok_or_else(|| format!("Value must be known in the first pass: {}", expr))
+    }
+
+    /// Evaluate `term (+|- term)*`
+    ///
+    /// Returns None in the first pass when a label is not yet defined.
+    fn eval(&self, expr: &str) -> Result<Option<Value>, String> {
+        let expr = expr.trim();
+        if expr.is_empty() {
+            return Err("Missing expression".to_string());
+        }
+
+        let mut result = Value { value: 0, width: 0, is_label: false };
+        let mut known = true;
+        let mut sign = 1;
+        let mut start = 0;
+        let bytes = expr.as_bytes();
+
+        for i in 0..=bytes.len() {
+            let at_operator = i < bytes.len() && (bytes[i] == b'+' || bytes[i] == b'-') && i > start;
+            if i < bytes.len() && !at_operator {
+                continue;
+            }
+            let term = &expr[start..i];
+            let (term, negate) = match term.strip_prefix('-') {
+                Some(t) => (t, true),
+                None => (term, false),
+            };
+            match self.term(term)? {
+                Some(v) => {
+                    let v_sign = if negate { -sign } else { sign };
+                    result.value += v_sign * v.value;
+                    result.width = result.width.max(v.width);
+                    result.is_label |= v.is_label;
+                }
+                None => known = false,
+            }
+            if i < bytes.len() {
+                sign = if bytes[i] == b'-' { -1 } else { 1 };
+            }
+            start = i + 1;
+        }
+
+        Ok(if known { Some(result) } else { None })
+    }
+
+    fn term(&self, term: &str) -> Result<Option<Value>, String> {
+        let shift = match term.chars().next() {
+            Some('<') => Some(0),
+            Some('>') => Some(8),
+            Some('^') => Some(16),
+            _ => None,
+        };
+        if let Some(shift) = shift {
+            return Ok(self.term(&term[1..])?.map(|v| Value {
+                value: (v.value >> shift) & 0xFF,
+                width: 1,
+                is_label: false,
+            }));
+        }
+
+        let number = |text: &str, radix: u32| {
+            i64::from_str_radix(text, radix).map_err(|_| format!("Invalid number: {}", term))
+        };
+        let value = if let Some(hex) = term.strip_prefix('$') {
+            let width = hex.len().div_ceil(2).min(3) as u8;
+            Value { value: number(hex, 16)?, width, is_label: false }
+        } else if let Some(bin) = term.strip_prefix('%') {
+            Value { value: number(bin, 2)?, width: 0, is_label: false }
+        } else if term == "*" {
+            Value { value: self.pc as i64, width: 0, is_label: true }
+        } else if term.starts_with(|c: char| c.is_ascii_digit()) {
+            Value { value: number(term, 10)?, width: 0, is_label: false }
+        } else if let Some(&value) = self.constants.get(term) {
+            Value { value, width: 0, is_label: false }
+        } else if let Some(&addr) = self.labels.get(term) {
+            Value { value: addr as i64, width: 0, is_label: true }
+        } else if self.pass == Pass::First && is_identifier(term) {
+            return Ok(None);
+        } else {
+            return Err(format!("Undefined symbol: {}", term));
+        };
+        Ok(Some(value))
+    }
+}
+
+fn lookup(mnemonic: &str, mode: AddrMode) -> Option<u8> {
+    disasm::find_opcode(mnemonic, mode)
+}
+
+/// Check a value fits in `width` bytes (signed or unsigned) and return it
+fn check_range(value: i64, width: u8) -> Result<i64, String> {
+    let bits = width as u32 * 8;
+    if value < -(1 << (bits - 1)) || value >= (1 << bits) {
+        return Err(format!("Value ${:X} does not fit in {} byte(s)", value, width));
+    }
+    Ok(value & ((1 << bits) - 1))
+}
+
+fn parse_operand(text: &str) -> Operand<'_> {
+    let upper = text.to_ascii_uppercase();
+    let inner = |prefix: usize, suffix: usize| &text[prefix..text.len() - suffix];
+
+    if text.is_empty() {
+        Operand::None
+    } else if upper == "A" {
+        Operand::Accumulator
+    } else if let Some(rest) = text.strip_prefix('#') {
+        Operand::Immediate(rest)
+    } else if text.starts_with('(') {
+        if upper.ends_with(",S),Y") {
+            Operand::StackIndirectIndexed(inner(1, 5))
+        } else if upper.ends_with("),Y") {
+            Operand::IndirectIndexed(inner(1, 3))
+        } else if upper.ends_with(",X)") {
+            Operand::IndexedIndirect(inner(1, 3))
+        } else {
+            Operand::Indirect(inner(1, 1))
+        }
+    } else if text.starts_with('[') {
+        if upper.ends_with("],Y") {
+            Operand::IndirectLongIndexed(inner(1, 3))
+        } else {
+            Operand::IndirectLong(inner(1, 1))
+        }
+    } else if upper.ends_with(",X") {
+        Operand::IndexedX(inner(0, 2))
+    } else if upper.ends_with(",Y") {
+        Operand::IndexedY(inner(0, 2))
+    } else if upper.ends_with(",S") {
+        Operand::Stack(inner(0, 2))
+    } else if let Some((a, b)) = text.split_once(',') {
+        Operand::Pair(a, b)
+    } else {
+        Operand::Plain(text)
+    }
+}
+
+/// The single expression of an operand, if it has one
+fn operand_expr<'a>(operand: &Operand<'a>) -> Option<&'a str> {
+    match *operand {
+        Operand::Immediate(e)
+        | Operand::Indirect(e)
+        | Operand::IndexedIndirect(e)
+        | Operand::IndirectIndexed(e)
+        | Operand::StackIndirectIndexed(e)
+        | Operand::IndirectLong(e)
+        | Operand::IndirectLongIndexed(e)
+        | Operand::Stack(e)
+        | Operand::IndexedX(e)
+        | Operand::IndexedY(e)
+        | Operand::Plain(e) => Some(e),
+        Operand::None | Operand::Accumulator | Operand::Pair(..) => None,
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '@')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@')
+}
+
+/// Remove a `;` comment, ignoring semicolons inside strings
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            ';' if !in_string => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Split directive arguments on commas outside strings
+fn split_args(args: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut in_string = false;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            ',' if !in_string => {
+                items.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    let last = args[start..].trim();
+    if !last.is_empty() {
+        items.push(last);
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assemble_bytes(source: &str) -> Vec<u8> {
+        assemble(source).unwrap().bytes()
+    }
+
+    #[test]
+    fn test_basic_program() {
+        let program = assemble(
+            "        org $8000\n\
+             reset:  sei\n\
+                     clc\n\
+                     xce             ; native mode\n\
+                     rep #$30\n\
+                     .a16\n\
+                     .i16\n\
+                     lda #$1234\n\
+                     ldx #$0000\n\
+             loop:   sta $7E0000,x\n\
+                     inx\n\
+                     inx\n\
+                     cpx #$0100\n\
+                     bne loop\n\
+                     stp\n",
+        )
+        .unwrap();
+
+        assert_eq!(program.origin(), Some(0x8000));
+        assert_eq!(
+            program.bytes(),
+            vec![
+                0x78, 0x18, 0xFB, 0xC2, 0x30, 0xA9, 0x34, 0x12, 0xA2, 0x00, 0x00,
+                0x9F, 0x00, 0x00, 0x7E, 0xE8, 0xE8, 0xE0, 0x00, 0x01, 0xD0, 0xF5, 0xDB,
+            ]
+        );
+        assert_eq!(program.labels.address("loop"), Some(0x00800B));
+    }
+
+    #[test]
+    fn test_label_aliases() {
+        let program = assemble("start:\nmain:   nop\n").unwrap();
+        assert_eq!(program.labels.address("start"), Some(0x008000));
+        assert_eq!(program.labels.address("main"), Some(0x008000));
+        assert_eq!(program.labels.label(0x008000), Some("main"));
+    }
+
+    #[test]
+    fn test_operand_sizes() {
+        // Direct, absolute (written width), long, forward reference
+        assert_eq!(assemble_bytes("lda $12"), vec![0xA5, 0x12]);
+        assert_eq!(assemble_bytes("lda $0012"), vec![0xAD, 0x12, 0x00]);
+        assert_eq!(assemble_bytes("lda $7E0012"), vec![0xAF, 0x12, 0x00, 0x7E]);
+        assert_eq!(assemble_bytes("lda.l $12"), vec![0xAF, 0x12, 0x00, 0x00]);
+        assert_eq!(assemble_bytes("lda.w 18"), vec![0xAD, 0x12, 0x00]);
+        assert_eq!(assemble_bytes("jsr sub\nsub: rts"), vec![0x20, 0x03, 0x80, 0x60]);
+
+        // No direct page form: falls back to absolute
+        assert_eq!(assemble_bytes("ldx $12,y"), vec![0xB6, 0x12]);
+        assert_eq!(assemble_bytes("lda $12,y"), vec![0xB9, 0x12, 0x00]);
+
+        // JMP/JSR to another bank become JML/JSL
+        assert_eq!(assemble_bytes("jmp $C08000"), vec![0x5C, 0x00, 0x80, 0xC0]);
+    }
+
+    #[test]
+    fn test_directives_and_expressions() {
+        let bytes = assemble_bytes(
+            "PORT = $2140\n\
+             org $8000\n\
+             table: db 1, $02, \"AB\", <table, >table, ^table\n\
+             dw table+2, PORT\n\
+             dl $123456\n\
+             sta PORT-$40\n",
+        );
+        assert_eq!(
+            bytes,
+            vec![
+                0x01, 0x02, b'A', b'B', 0x00, 0x80, 0x00,
+                0x02, 0x80, 0x40, 0x21,
+                0x56, 0x34, 0x12,
+                0x8D, 0x00, 0x21,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(assemble("foo").unwrap_err().contains("Unknown instruction"));
+        assert!(assemble("lda missing").unwrap_err().starts_with("Line 1"));
+        assert!(assemble("x: nop\nx: nop").unwrap_err().contains("Duplicate label"));
+        assert!(assemble("lda #$123").is_err());
+        assert!(assemble("start: nop\norg $9000\nbra start").unwrap_err().contains("Branch out of range"));
+        assert!(assemble("jsr ($12),y").is_err());
+    }
+
+    #[test]
+    fn test_operand_out_of_range() {
+        // Forward references to another bank don't fit a 16-bit operand
+        let err = assemble("jmp far
+org $018000
+far: rts").unwrap_err();
+        assert!(err.starts_with("Line 1"), "{}", err);
+        assert!(err.contains("out of range"), "{}", err);
+        assert!(assemble("lda later
+org $028000
+later: nop").is_err());
+        assert_eq!(
+            assemble_bytes("jml far
+org $018000
+far: rts")[..4],
+            [0x5C, 0x00, 0x80, 0x01]
+        );
+        assert_eq!(
+            assemble_bytes("org $018000
+jmp near
+near: rts"),
+            vec![0x4C, 0x03, 0x80, 0x60]
+        );
+
+        // Forced sizes are checked too
+        assert!(assemble("lda.b $1234").unwrap_err().contains("out of range"));
+        assert!(assemble("lda.w $7E0010").unwrap_err().contains("out of range"));
+        assert!(assemble("lda.b later
+org $9000
+later: nop").is_err());
+        assert_eq!(assemble_bytes("lda.b $12"), vec![0xA5, 0x12]);
+    }
+
+    #[test]
+    fn test_round_trip_all_opcodes() {
+        for opcode in 0..=255u8 {
+            for (m8, x8) in [(true, true), (false, false)] {
+                let code = [opcode, 0x12, 0x34, 0x56];
+                let inst = disasm::decode(0x018000, m8, x8, |a| code[(a - 0x018000) as usize]);
+                let source = format!(
+                    "org $018000\n{}\n{}\n{}",
+                    if m8 { ".a8" } else { ".a16" },
+                    if x8 { ".i8" } else { ".i16" },
+                    inst
+                );
+                let bytes = assemble(&source)
+                    .unwrap_or_else(|e| panic!("{:02X} `{}`: {}", opcode, inst, e))
+                    .bytes();
+                assert_eq!(bytes, inst.bytes(), "{:02X} `{}`", opcode, inst);
+            }
+        }
+    }
+}
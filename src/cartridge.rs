@@ -129,9 +129,11 @@ impl Cartridge {
     fn detect_mapping_mode(rom_data: &[u8]) -> Result<(MappingMode, usize), String> {
         // LoROM header is at $7FC0-$7FFF (offset $7FC0)
         // HiROM header is at $FFC0-$FFFF (offset $FFC0)
+        // ExHiROM header is at $40FFC0 (bank $00 maps to the upper 4MB)
         
         let lorom_offset = 0x7FC0;
         let hirom_offset = 0xFFC0;
+        let exhirom_offset = 0x40FFC0;
         
         // Score each potential header location
         let lorom_score = if rom_data.len() > lorom_offset + 0x30 {
@@ -146,8 +148,18 @@ impl Cartridge {
             0
         };
         
+        let exhirom_score = if rom_data.len() > exhirom_offset + 0x30 {
+            Self::score_header(rom_data, exhirom_offset)
+        } else {
+            0
+        };
+        
         // Choose the header with the highest score
-        if lorom_score > hirom_score && lorom_score > 0 {
+        if exhirom_score > lorom_score && exhirom_score > hirom_score {
+            let map_mode_byte = rom_data[exhirom_offset + 0x15];
+            let mapping = Self::parse_mapping_mode(map_mode_byte);
+            Ok((mapping, exhirom_offset))
+        } else if lorom_score > hirom_score && lorom_score > 0 {
             let map_mode_byte = rom_data[lorom_offset + 0x15];
             let mapping = Self::parse_mapping_mode(map_mode_byte);
             Ok((mapping, lorom_offset))
@@ -426,6 +426,14 @@ impl CoProcessor for Dsp1 {
         }
     }
 
+    fn peek(&self, addr: u32) -> u8 {
+        match addr & 0xFFFF {
+            0x6000..=0x6FFF => self.output_buffer.get(self.buffer_position).copied().unwrap_or(0x00),
+            0x7000..=0x7FFF => (self.busy as u8) << 7,
+            _ => 0x00,
+        }
+    }
+
     fn write(&mut self, addr: u32, val: u8) {
         let addr = addr & 0xFFFF;
         
@@ -503,6 +511,19 @@ mod tests {
         assert_eq!(result, 20000);
     }
 
+    #[test]
+    fn test_peek_leaves_output_queued() {
+        let mut dsp = Dsp1::new();
+        for byte in [0x00, 100, 0, 200, 0] {
+            dsp.write(0x6000, byte);
+        }
+        
+        assert_eq!(dsp.peek(0x6000), 0x20);
+        assert_eq!(dsp.peek(0x6000), 0x20);
+        assert_eq!(dsp.read(0x6000), 0x20);
+        assert_eq!(dsp.peek(0x6000), 0x4E);
+    }
+
     #[test]
     fn test_square_root() {
         let mut dsp = Dsp1::new();
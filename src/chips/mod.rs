@@ -28,6 +28,20 @@ pub trait CoProcessor: Send {
     /// The byte value at the given address, or 0 for unmapped regions
     fn read(&mut self, addr: u32) -> u8;
 
+    /// Read a byte without side effects, for debuggers and tracing
+    ///
+    /// # Arguments
+    /// * `addr` - 24-bit SNES address
+    ///
+    /// # Returns
+    /// The byte `read` would return, leaving FIFOs, IRQ flags and other
+    /// read-sensitive state untouched. Chips that don't override this
+    /// report 0 rather than risk a side effect.
+    fn peek(&self, addr: u32) -> u8 {
+        let _ = addr;
+        0
+    }
+
     /// Write a byte to the coprocessor's address space
     ///
     /// # Arguments
@@ -52,6 +66,59 @@ pub trait CoProcessor: Send {
     /// # Returns
     /// true if this coprocessor should handle reads/writes to this address
     fn handles_address(&self, addr: u32) -> bool;
+
+    /// Level of the coprocessor's IRQ output line to the main CPU
+    ///
+    /// # Returns
+    /// true while the chip is asserting /IRQ on the cartridge bus
+    fn irq_line(&self) -> bool {
+        false
+    }
+
+    /// Level of the coprocessor's NMI output line to the main CPU
+    ///
+    /// # Returns
+    /// true while the chip is asserting NMI towards the main CPU
+    fn nmi_line(&self) -> bool {
+        false
+    }
+
+    /// Replacement for the native-mode IRQ vector ($00FFEE)
+    ///
+    /// Memory answers reads of the vector with it, so it applies to every
+    /// IRQ the main CPU takes, not only those raised by the coprocessor.
+    ///
+    /// # Returns
+    /// The vector the main CPU should jump to instead of the one in ROM,
+    /// or None to use the ROM vector
+    fn irq_vector(&self) -> Option<u16> {
+        None
+    }
+
+    /// Replacement for the native-mode NMI vector ($00FFEA)
+    ///
+    /// Memory answers reads of the vector with it, so it applies to every
+    /// NMI the main CPU takes, not only those raised by the coprocessor.
+    ///
+    /// # Returns
+    /// The vector the main CPU should jump to instead of the one in ROM,
+    /// or None to use the ROM vector
+    fn nmi_vector(&self) -> Option<u16> {
+        None
+    }
+
+    /// Internal RAM exposed to debugging tools (SA-1 I-RAM, GSU cache)
+    ///
+    /// # Returns
+    /// The chip's RAM, or an empty slice for chips without one
+    fn ram(&self) -> &[u8] {
+        &[]
+    }
+
+    /// Mutable access to the RAM returned by `ram`
+    fn ram_mut(&mut self) -> &mut [u8] {
+        &mut []
+    }
 }
 
 /// Types of SNES coprocessors that can be detected from cartridge headers
@@ -105,7 +105,6 @@ pub struct Sa1 {
     sa1_irq_pending: bool,
     sa1_nmi_pending: bool,
     snes_irq_pending: bool,
-    snes_nmi_pending: bool,
     
     /// Math operation results
     math_result: u64,
@@ -129,7 +128,6 @@ impl Sa1 {
             sa1_irq_pending: false,
             sa1_nmi_pending: false,
             snes_irq_pending: false,
-            snes_nmi_pending: false,
             math_result: 0,
             vbit_buffer: 0,
             vbit_count: 0,
@@ -138,6 +136,17 @@ impl Sa1 {
 
     /// Read from SA-1 register space
     fn read_register(&mut self, addr: u16) -> u8 {
+        let value = self.peek_register(addr);
+        // Variable-length bit reads shift the next bit in
+        if addr == 0x2231 && self.vbit_count > 0 {
+            self.vbit_buffer <<= 1;
+            self.vbit_count -= 1;
+        }
+        value
+    }
+
+    /// Read from SA-1 register space without side effects
+    fn peek_register(&self, addr: u16) -> u8 {
         match addr {
             // Status flags
             0x2300 => {
@@ -172,17 +181,7 @@ impl Sa1 {
             0x230F => ((self.math_result >> 24) & 0xFF) as u8,
             
             // Variable-length bit read
-            0x2231 => {
-                let bit = if self.vbit_count > 0 {
-                    let result = (self.vbit_buffer >> 7) & 1;
-                    self.vbit_buffer <<= 1;
-                    self.vbit_count -= 1;
-                    result
-                } else {
-                    0
-                };
-                bit
-            }
+            0x2231 if self.vbit_count > 0 => (self.vbit_buffer >> 7) & 1,
             
             _ => 0,
         }
@@ -213,7 +212,8 @@ impl Sa1 {
             
             // SNES Interrupt Clear
             0x2202 => {
-                if val & 0x80 != 0 { self.snes_nmi_pending = false; }
+                // Bit 7: Acknowledge the IRQ raised through SCNT
+                if val & 0x80 != 0 { self.snes_irq_pending = false; }
                 if val & 0x40 != 0 { self.sa1_nmi_pending = false; }
             }
             
@@ -232,6 +232,9 @@ impl Sa1 {
             // SNES Control
             0x2209 => {
                 self.registers.scnt = val;
+                // Bit 7: IRQ to S-CPU
+                // Bit 6: Use SIV instead of the ROM IRQ vector
+                // Bit 4: Use SNV instead of the ROM NMI vector
                 if val & 0x80 != 0 {
                     self.snes_irq_pending = true;
                 }
@@ -423,6 +426,14 @@ impl Sa1 {
 }
 
 impl CoProcessor for Sa1 {
+    fn ram(&self) -> &[u8] {
+        &self.iram[..]
+    }
+
+    fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.iram[..]
+    }
+
     fn reset(&mut self) {
         self.sa1_cycles = 0;
         self.sa1_running = false;
@@ -435,7 +446,6 @@ impl CoProcessor for Sa1 {
         self.sa1_irq_pending = false;
         self.sa1_nmi_pending = false;
         self.snes_irq_pending = false;
-        self.snes_nmi_pending = false;
         self.math_result = 0;
         self.vbit_buffer = 0;
         self.vbit_count = 0;
@@ -460,6 +470,14 @@ impl CoProcessor for Sa1 {
         }
     }
 
+    fn peek(&self, addr: u32) -> u8 {
+        match addr & 0xFFFFFF {
+            0x003000..=0x0037FF => self.iram[(addr & 0xFFFF) as usize - 0x3000],
+            0x002200..=0x0023FF => self.peek_register((addr & 0xFFFF) as u16),
+            _ => 0,
+        }
+    }
+
     fn write(&mut self, addr: u32, val: u8) {
         let addr = addr & 0xFFFFFF;
         
@@ -493,6 +511,27 @@ impl CoProcessor for Sa1 {
         let addr = addr & 0xFFFFFF;
         matches!(addr, 0x002200..=0x0023FF | 0x003000..=0x0037FF)
     }
+
+    fn irq_line(&self) -> bool {
+        // SIE bit 7 gates the SA-1 -> S-CPU IRQ
+        self.snes_irq_pending && self.registers.sie & 0x80 != 0
+    }
+
+    fn irq_vector(&self) -> Option<u16> {
+        if self.registers.scnt & 0x40 != 0 {
+            Some(self.registers.siv)
+        } else {
+            None
+        }
+    }
+
+    fn nmi_vector(&self) -> Option<u16> {
+        if self.registers.scnt & 0x10 != 0 {
+            Some(self.registers.snv)
+        } else {
+            None
+        }
+    }
 }
 
 impl Default for Sa1 {
@@ -505,6 +544,16 @@ impl Default for Sa1 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_ram_exposes_iram() {
+        let mut sa1 = Sa1::new();
+        sa1.write(0x003010, 0x42);
+        assert_eq!(sa1.ram().len(), 0x800);
+        assert_eq!(sa1.ram()[0x10], 0x42);
+        sa1.ram_mut()[0x11] = 0x24;
+        assert_eq!(sa1.read(0x003011), 0x24);
+    }
+
     #[test]
     fn test_iram_access() {
         let mut sa1 = Sa1::new();
@@ -582,4 +631,38 @@ mod tests {
         assert!(sa1.sa1_running);
         assert_eq!(sa1.sa1_pc, 0x8000);
     }
+
+    #[test]
+    fn test_snes_irq_line() {
+        let mut sa1 = Sa1::new();
+        
+        // IRQ is masked until the S-CPU enables it in SIE
+        sa1.write(0x2209, 0x80);
+        assert!(!sa1.irq_line());
+        
+        sa1.write(0x2201, 0x80);
+        assert!(sa1.irq_line());
+        
+        // Acknowledge through SIC
+        sa1.write(0x2202, 0x80);
+        assert!(!sa1.irq_line());
+    }
+
+    #[test]
+    fn test_snes_vector_overrides() {
+        let mut sa1 = Sa1::new();
+        
+        sa1.write(0x220C, 0x34);
+        sa1.write(0x220D, 0x12);
+        sa1.write(0x220E, 0x78);
+        sa1.write(0x220F, 0x56);
+        
+        // ROM vectors are used until SCNT selects the overrides
+        assert_eq!(sa1.nmi_vector(), None);
+        assert_eq!(sa1.irq_vector(), None);
+        
+        sa1.write(0x2209, 0x50);
+        assert_eq!(sa1.nmi_vector(), Some(0x1234));
+        assert_eq!(sa1.irq_vector(), Some(0x5678));
+    }
 }
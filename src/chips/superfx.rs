@@ -185,6 +185,9 @@ impl SuperFx {
             // Stop
             0x00 if opcode == 0x00 => {
                 self.status.go = false;
+                // STOP always latches the IRQ flag; CFGR decides whether it
+                // reaches the S-CPU
+                self.status.irq = true;
                 return 1;
             }
             
@@ -438,7 +441,7 @@ impl SuperFx {
             // Configuration register (CFGR)
             0x3034 => {
                 self.cfgr = val;
-                // Bit 0: IRQ enable
+                // Bit 7: IRQ mask (1 = IRQ disabled)
                 // Bit 5: High speed mode (21.4 MHz vs 10.7 MHz)
                 self.clock_multiplier = if val & 0x20 != 0 { 2 } else { 1 };
             }
@@ -495,6 +498,14 @@ impl SuperFx {
 }
 
 impl CoProcessor for SuperFx {
+    fn ram(&self) -> &[u8] {
+        &self.cache[..]
+    }
+
+    fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.cache[..]
+    }
+
     fn reset(&mut self) {
         self.r = [0; 16];
         self.cache.fill(0);
@@ -520,6 +531,13 @@ impl CoProcessor for SuperFx {
         let addr = addr & 0xFFFF;
         
         match addr {
+            // SFR high byte - reading it acknowledges the IRQ
+            0x3031 => {
+                let value = if self.status.irq { 0x80 } else { 0x00 };
+                self.status.irq = false;
+                value
+            }
+            
             // SuperFX Registers
             0x3000..=0x303F => self.read_register(addr as u16),
             
@@ -533,6 +551,15 @@ impl CoProcessor for SuperFx {
         }
     }
 
+    fn peek(&self, addr: u32) -> u8 {
+        match addr & 0xFFFF {
+            0x3031 => (self.status.irq as u8) << 7,
+            0x3000..=0x303F => self.read_register((addr & 0xFFFF) as u16),
+            0x3100..=0x32FF => self.cache[(addr & 0xFFFF) as usize - 0x3100],
+            _ => 0,
+        }
+    }
+
     fn write(&mut self, addr: u32, val: u8) {
         let addr = addr & 0xFFFF;
         
@@ -572,6 +599,10 @@ impl CoProcessor for SuperFx {
         let addr = addr & 0xFFFF;
         matches!(addr, 0x3000..=0x303F | 0x3100..=0x32FF)
     }
+
+    fn irq_line(&self) -> bool {
+        self.status.irq && self.cfgr & 0x80 == 0
+    }
 }
 
 impl Default for SuperFx {
@@ -598,6 +629,18 @@ mod tests {
         assert_eq!(sfx.r[0], 0x1234);
     }
 
+    #[test]
+    fn test_peek_keeps_irq() {
+        let mut sfx = SuperFx::new();
+        sfx.status.irq = true;
+        
+        assert_eq!(sfx.peek(0x3031), 0x80);
+        assert!(sfx.status.irq);
+        assert_eq!(sfx.read(0x3031), 0x80);
+        assert!(!sfx.status.irq);
+        assert_eq!(sfx.peek(0x3031), 0x00);
+    }
+    
     #[test]
     fn test_cache_ram() {
         let mut sfx = SuperFx::new();
@@ -670,6 +713,34 @@ mod tests {
         assert_eq!(sfx.clock_multiplier, 2);
     }
 
+    #[test]
+    fn test_stop_raises_irq() {
+        let mut sfx = SuperFx::new();
+        
+        // Program in cache: NOP, STOP
+        sfx.cache[0] = 0x01;
+        sfx.cache[1] = 0x00;
+        sfx.write(0x3030, 0x20);
+        sfx.step(16);
+        
+        assert!(!sfx.status.go);
+        assert!(sfx.irq_line());
+        
+        // Reading SFR high acknowledges the interrupt
+        assert_eq!(sfx.read(0x3031) & 0x80, 0x80);
+        assert!(!sfx.irq_line());
+    }
+
+    #[test]
+    fn test_irq_mask() {
+        let mut sfx = SuperFx::new();
+        
+        sfx.write(0x3034, 0x80);
+        sfx.execute_instruction(0x00);
+        assert!(sfx.status.irq);
+        assert!(!sfx.irq_line());
+    }
+
     #[test]
     fn test_handles_address() {
         let sfx = SuperFx::new();
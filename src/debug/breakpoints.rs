@@ -0,0 +1,389 @@
+// Breakpoints and Watchpoints
+// Execute/read/write breakpoints on the CPU bus and the video/audio memories
+
+use crate::debug::symbols::SymbolTable;
+
+/// Memory a breakpoint address refers to
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AddressSpace {
+    /// 24-bit S-CPU bus (WRAM, ROM, SRAM and I/O registers)
+    Bus,
+    /// PPU video RAM (64KB, byte addressed)
+    Vram,
+    /// PPU palette RAM (512 bytes, byte addressed)
+    Cgram,
+    /// PPU sprite attribute memory (544 bytes)
+    Oam,
+    /// SPC700 audio RAM (64KB)
+    Aram,
+}
+
+/// Kind of access that triggered a breakpoint
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AccessKind {
+    /// Instruction fetch at the start of an instruction
+    Execute,
+    /// Data read (on the CPU bus this includes operand fetches)
+    Read,
+    /// Data write
+    Write,
+}
+
+/// Optional condition on the value read or written
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Condition {
+    Equals(u8),
+    NotEquals(u8),
+    Less(u8),
+    Greater(u8),
+    /// Matches when `value & mask == expected`
+    Mask { mask: u8, expected: u8 },
+}
+
+impl Condition {
+    /// Check whether the accessed value satisfies this condition
+    pub fn matches(&self, value: u8) -> bool {
+        match *self {
+            Condition::Equals(v) => value == v,
+            Condition::NotEquals(v) => value != v,
+            Condition::Less(v) => value < v,
+            Condition::Greater(v) => value > v,
+            Condition::Mask { mask, expected } => value & mask == expected,
+        }
+    }
+}
+
+/// A single breakpoint covering an inclusive address range
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub space: AddressSpace,
+    pub start: u32,
+    pub end: u32,
+    pub execute: bool,
+    pub read: bool,
+    pub write: bool,
+    /// Value condition for read/write accesses (ignored for execute)
+    pub condition: Option<Condition>,
+    pub enabled: bool,
+}
+
+impl Breakpoint {
+    /// Break when the CPU starts executing an instruction at `addr`
+    pub fn execute(addr: u32) -> Self {
+        Self::new(AddressSpace::Bus, addr, addr, true, false, false)
+    }
+
+    /// Break on reads from `start..=end` in the given space
+    pub fn read(space: AddressSpace, start: u32, end: u32) -> Self {
+        Self::new(space, start, end, false, true, false)
+    }
+
+    /// Break on writes to `start..=end` in the given space
+    pub fn write(space: AddressSpace, start: u32, end: u32) -> Self {
+        Self::new(space, start, end, false, false, true)
+    }
+
+    /// Break on reads or writes to `start..=end` in the given space
+    pub fn access(space: AddressSpace, start: u32, end: u32) -> Self {
+        Self::new(space, start, end, false, true, true)
+    }
+
+    /// Break when the CPU starts executing at a label (or `$`-prefixed address)
+    pub fn execute_at(symbols: &SymbolTable, label: &str) -> Result<Self, String> {
+        Ok(Self::execute(symbols.resolve(label)?))
+    }
+
+    fn new(space: AddressSpace, start: u32, end: u32, execute: bool, read: bool, write: bool) -> Self {
+        Self {
+            space,
+            start: start.min(end),
+            end: start.max(end),
+            execute,
+            read,
+            write,
+            condition: None,
+            enabled: true,
+        }
+    }
+
+    /// Attach a value condition
+    pub fn with_condition(mut self, condition: Condition) -> Self {
+        self.condition = Some(condition);
+        self
+    }
+
+    /// Check whether this breakpoint triggers on the given access
+    ///
+    /// On the CPU bus, mirrors of low WRAM and the I/O area count as the
+    /// same address: a watch on $7E0010 catches $000010 and $800010, and
+    /// one on $002100 catches $802100.
+    pub fn matches(&self, space: AddressSpace, addr: u32, kind: AccessKind, value: u8) -> bool {
+        if !self.enabled || self.space != space {
+            return false;
+        }
+        let in_range = (self.start..=self.end).contains(&addr)
+            || (space == AddressSpace::Bus && {
+                let (start, end) = self.canonical_range();
+                (start..=end).contains(&canonical_bus_address(addr))
+            });
+        if !in_range {
+            return false;
+        }
+
+        match kind {
+            AccessKind::Execute => self.execute,
+            AccessKind::Read | AccessKind::Write => {
+                let wanted = if kind == AccessKind::Read { self.read } else { self.write };
+                wanted && self.condition.is_none_or(|c| c.matches(value))
+            }
+        }
+    }
+
+    /// The range in canonical bus addresses, when it lies within a single
+    /// mirrored area (otherwise the range as given)
+    fn canonical_range(&self) -> (u32, u32) {
+        let (start, end) = (canonical_bus_address(self.start), canonical_bus_address(self.end));
+        if end.wrapping_sub(start) == self.end - self.start {
+            (start, end)
+        } else {
+            (self.start, self.end)
+        }
+    }
+}
+
+/// Canonical form of a CPU bus address: banks $00-$3F and $80-$BF mirror
+/// the first 8K of WRAM ($7E0000-$7E1FFF) and the I/O area ($2000-$5FFF of
+/// bank $00)
+fn canonical_bus_address(addr: u32) -> u32 {
+    let bank = (addr >> 16) & 0xFF;
+    let offset = addr & 0xFFFF;
+    if bank < 0x40 || (0x80..0xC0).contains(&bank) {
+        match offset {
+            0x0000..=0x1FFF => return 0x7E0000 | offset,
+            0x2000..=0x5FFF => return offset,
+            _ => {}
+        }
+    }
+    addr & 0xFFFFFF
+}
+
+/// Description of the access that stopped emulation
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BreakpointHit {
+    /// Id of the breakpoint that triggered
+    pub id: u32,
+    pub space: AddressSpace,
+    pub addr: u32,
+    pub kind: AccessKind,
+    /// Value read or written (0 for execute)
+    pub value: u8,
+    /// 24-bit address of the CPU instruction that was executing
+    pub pc: u32,
+}
+
+/// Breakpoint list plus the pending hit, shared by all bus hooks
+#[derive(Default)]
+pub struct Breakpoints {
+    entries: Vec<(u32, Breakpoint)>,
+    next_id: u32,
+    pc: u32,
+    hit: Option<BreakpointHit>,
+}
+
+impl Breakpoints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a breakpoint, returning its id
+    pub fn add(&mut self, breakpoint: Breakpoint) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entries.push((id, breakpoint));
+        id
+    }
+
+    /// Remove a breakpoint by id
+    pub fn remove(&mut self, id: u32) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|(i, _)| *i != id);
+        self.entries.len() != len
+    }
+
+    /// Enable or disable a breakpoint by id
+    pub fn set_enabled(&mut self, id: u32, enabled: bool) -> bool {
+        match self.entries.iter_mut().find(|(i, _)| *i == id) {
+            Some((_, bp)) => {
+                bp.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Look up a breakpoint by id
+    pub fn get(&self, id: u32) -> Option<&Breakpoint> {
+        self.entries.iter().find(|(i, _)| *i == id).map(|(_, bp)| bp)
+    }
+
+    /// Iterate over all breakpoints with their ids
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &Breakpoint)> {
+        self.entries.iter().map(|(i, bp)| (*i, bp))
+    }
+
+    /// Remove all breakpoints and any pending hit
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.hit = None;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Record the address of the instruction about to execute
+    pub fn set_pc(&mut self, pc: u32) {
+        self.pc = pc;
+    }
+
+    /// Check an access against all breakpoints, recording the first hit
+    ///
+    /// # Returns
+    /// true if a breakpoint triggered on this access
+    pub fn check(&mut self, space: AddressSpace, addr: u32, kind: AccessKind, value: u8) -> bool {
+        if self.entries.is_empty() {
+            return false;
+        }
+
+        let Some((id, _)) = self.entries.iter().find(|(_, bp)| bp.matches(space, addr, kind, value)) else {
+            return false;
+        };
+
+        if self.hit.is_none() {
+            self.hit = Some(BreakpointHit {
+                id: *id,
+                space,
+                addr,
+                kind,
+                value,
+                pc: self.pc,
+            });
+        }
+        true
+    }
+
+    /// Check for an execute breakpoint at `pc`
+    pub fn check_execute(&mut self, pc: u32) -> bool {
+        self.set_pc(pc);
+        self.check(AddressSpace::Bus, pc, AccessKind::Execute, 0)
+    }
+
+    /// The hit recorded since the last `clear_hit`, if any
+    pub fn hit(&self) -> Option<&BreakpointHit> {
+        self.hit.as_ref()
+    }
+
+    /// Take the recorded hit, leaving none pending
+    pub fn take_hit(&mut self) -> Option<BreakpointHit> {
+        self.hit.take()
+    }
+
+    pub fn clear_hit(&mut self) {
+        self.hit = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_and_kind() {
+        let mut bps = Breakpoints::new();
+        let id = bps.add(Breakpoint::write(AddressSpace::Bus, 0x7E0100, 0x7E01FF));
+
+        assert!(!bps.check(AddressSpace::Bus, 0x7E0150, AccessKind::Read, 0));
+        assert!(!bps.check(AddressSpace::Vram, 0x7E0150, AccessKind::Write, 0));
+        assert!(!bps.check(AddressSpace::Bus, 0x7E0200, AccessKind::Write, 0));
+        assert!(bps.hit().is_none());
+
+        bps.set_pc(0x008123);
+        assert!(bps.check(AddressSpace::Bus, 0x7E01FF, AccessKind::Write, 0x42));
+        let hit = bps.take_hit().unwrap();
+        assert_eq!(hit.id, id);
+        assert_eq!(hit.addr, 0x7E01FF);
+        assert_eq!(hit.value, 0x42);
+        assert_eq!(hit.pc, 0x008123);
+        assert!(bps.hit().is_none());
+    }
+
+    #[test]
+    fn test_bus_mirrors() {
+        let wram = Breakpoint::write(AddressSpace::Bus, 0x7E0000, 0x7E1FFF);
+        assert!(wram.matches(AddressSpace::Bus, 0x000010, AccessKind::Write, 0));
+        assert!(wram.matches(AddressSpace::Bus, 0xBF1FFF, AccessKind::Write, 0));
+        assert!(!wram.matches(AddressSpace::Bus, 0x002000, AccessKind::Write, 0));
+        assert!(!wram.matches(AddressSpace::Bus, 0x400010, AccessKind::Write, 0));
+
+        // Mirrored breakpoint addresses are normalised too
+        let low = Breakpoint::read(AddressSpace::Bus, 0x800100, 0x8001FF);
+        assert!(low.matches(AddressSpace::Bus, 0x7E0150, AccessKind::Read, 0));
+        assert!(!low.matches(AddressSpace::Bus, 0x7E0200, AccessKind::Read, 0));
+
+        let io = Breakpoint::write(AddressSpace::Bus, 0x002100, 0x002100);
+        assert!(io.matches(AddressSpace::Bus, 0x802100, AccessKind::Write, 0));
+        assert!(io.matches(AddressSpace::Bus, 0x3F2100, AccessKind::Write, 0));
+        assert!(!io.matches(AddressSpace::Bus, 0xC02100, AccessKind::Write, 0));
+        // Other memories have no mirrors
+        assert!(!io.matches(AddressSpace::Vram, 0x802100, AccessKind::Write, 0));
+    }
+
+    #[test]
+    fn test_first_hit_is_kept() {
+        let mut bps = Breakpoints::new();
+        let first = bps.add(Breakpoint::read(AddressSpace::Aram, 0x00F4, 0x00F7));
+        bps.add(Breakpoint::read(AddressSpace::Aram, 0x0200, 0x0200));
+
+        assert!(bps.check(AddressSpace::Aram, 0x00F5, AccessKind::Read, 1));
+        assert!(bps.check(AddressSpace::Aram, 0x0200, AccessKind::Read, 2));
+        assert_eq!(bps.hit().unwrap().id, first);
+    }
+
+    #[test]
+    fn test_conditions() {
+        let mut bps = Breakpoints::new();
+        bps.add(Breakpoint::write(AddressSpace::Cgram, 0, 0x1FF).with_condition(Condition::Greater(0x10)));
+        bps.add(Breakpoint::read(AddressSpace::Oam, 0, 0).with_condition(Condition::Mask { mask: 0xF0, expected: 0x80 }));
+
+        assert!(!bps.check(AddressSpace::Cgram, 4, AccessKind::Write, 0x10));
+        assert!(bps.check(AddressSpace::Cgram, 4, AccessKind::Write, 0x11));
+        assert!(!bps.check(AddressSpace::Oam, 0, AccessKind::Read, 0x7F));
+        assert!(bps.check(AddressSpace::Oam, 0, AccessKind::Read, 0x8F));
+    }
+
+    #[test]
+    fn test_execute_at_label() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert(0x019234, "UpdatePlayer");
+
+        let bp = Breakpoint::execute_at(&symbols, "UpdatePlayer").unwrap();
+        assert_eq!((bp.start, bp.end), (0x019234, 0x019234));
+        assert!(Breakpoint::execute_at(&symbols, "Missing").is_err());
+    }
+
+    #[test]
+    fn test_enable_remove() {
+        let mut bps = Breakpoints::new();
+        let id = bps.add(Breakpoint::execute(0x008000));
+
+        assert!(bps.set_enabled(id, false));
+        assert!(!bps.check_execute(0x008000));
+        assert!(bps.set_enabled(id, true));
+        assert!(bps.check_execute(0x008000));
+        assert_eq!(bps.hit().unwrap().kind, AccessKind::Execute);
+
+        assert!(bps.remove(id));
+        assert!(!bps.remove(id));
+        assert!(bps.is_empty());
+    }
+}
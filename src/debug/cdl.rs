@@ -0,0 +1,442 @@
+// Code/Data Logger
+// Marks each ROM byte as code (with its M/X widths), data, DMA source or
+// pointer access while the game runs, and turns the log into a listing
+
+use std::fmt::Write;
+
+use crate::cartridge::MappingMode;
+use crate::cpu::Cpu65816;
+use crate::debug::symbols::{rom_offset_to_address, SymbolTable};
+use crate::disasm::{self, AddrMode, Instruction};
+use crate::memory::Memory;
+
+/// Executed as part of an instruction
+pub const CODE: u8 = 0x01;
+/// Read as an instruction operand
+pub const DATA: u8 = 0x02;
+/// Reached by a branch or jump
+pub const JUMP_TARGET: u8 = 0x04;
+/// Reached by JSR/JSL or as an interrupt handler
+pub const SUB_ENTRY: u8 = 0x08;
+/// Code executed with 8-bit index registers
+pub const INDEX_8: u8 = 0x10;
+/// Code executed with an 8-bit accumulator
+pub const MEMORY_8: u8 = 0x20;
+/// Read through an indirect addressing mode, or read as a jump pointer
+pub const INDIRECT: u8 = 0x40;
+/// Read by a DMA transfer
+pub const DMA: u8 = 0x80;
+
+const MAGIC: &[u8; 4] = b"ZCDL";
+const HEADER_LEN: usize = 8;
+
+/// One flag byte per ROM byte
+///
+/// Install with `Emulator::set_code_data_log`. The flag layout of the first
+/// six bits matches Mesen's SNES CDL files.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodeDataLog {
+    flags: Vec<u8>,
+}
+
+impl CodeDataLog {
+    /// Create an empty log for a ROM of `rom_len` bytes
+    pub fn new(rom_len: usize) -> Self {
+        Self { flags: vec![0; rom_len] }
+    }
+
+    /// File name of the log stored next to `rom_path` (`game.sfc` -> `game.cdl`)
+    pub fn file_name(rom_path: &str) -> String {
+        let stem_start = rom_path.rfind(['/', '\\']).map_or(0, |i| i + 1);
+        match rom_path[stem_start..].rfind('.') {
+            Some(dot) => format!("{}.cdl", &rom_path[..stem_start + dot]),
+            None => format!("{}.cdl", rom_path),
+        }
+    }
+
+    /// Serialize as `ZCDL`, the ROM length (u32 LE) and the flag bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(HEADER_LEN + self.flags.len());
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&(self.flags.len() as u32).to_le_bytes());
+        data.extend_from_slice(&self.flags);
+        data
+    }
+
+    /// Load a log saved by `to_bytes`, checking it belongs to a ROM of `rom_len` bytes
+    pub fn from_bytes(data: &[u8], rom_len: usize) -> Result<Self, String> {
+        if data.len() < HEADER_LEN || &data[..4] != MAGIC {
+            return Err("Not a CDL file".to_string());
+        }
+        let len = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        if len != rom_len {
+            return Err(format!("CDL file is for a {} byte ROM, loaded ROM is {} bytes", len, rom_len));
+        }
+        if data.len() - HEADER_LEN != len {
+            return Err("CDL file is truncated".to_string());
+        }
+        Ok(Self { flags: data[HEADER_LEN..].to_vec() })
+    }
+
+    /// Flags of every ROM byte
+    pub fn flags(&self) -> &[u8] {
+        &self.flags
+    }
+
+    /// Flags of the ROM byte at `offset`
+    pub fn get(&self, offset: usize) -> u8 {
+        self.flags.get(offset).copied().unwrap_or(0)
+    }
+
+    /// Set flags on the ROM byte at `offset`
+    pub fn mark(&mut self, offset: usize, flags: u8) {
+        if let Some(byte) = self.flags.get_mut(offset) {
+            *byte |= flags;
+        }
+    }
+
+    /// Mark `len` ROM bytes starting at `offset` as read by DMA
+    pub fn mark_dma(&mut self, offset: usize, len: usize) {
+        for offset in offset..offset.saturating_add(len) {
+            self.mark(offset, DMA);
+        }
+    }
+
+    /// Forget everything logged so far
+    pub fn clear(&mut self) {
+        self.flags.fill(0);
+    }
+
+    /// Number of ROM bytes with any of `flags` set
+    pub fn count(&self, flags: u8) -> usize {
+        self.flags.iter().filter(|&&f| f & flags != 0).count()
+    }
+
+    /// Log the instruction at the CPU's PC, which is about to execute
+    ///
+    /// Marks its bytes as code with the current register widths and the ROM
+    /// bytes it reads as data. Returns the decoded instruction for
+    /// `record_flow`.
+    pub fn record_instruction(&mut self, cpu: &Cpu65816, memory: &Memory) -> Instruction {
+        let insn = disasm::decode_at_pc(cpu, |a| memory.peek(a));
+        let m8 = cpu.p.e || cpu.p.m;
+        let x8 = cpu.p.e || cpu.p.x;
+
+        let mut code = CODE;
+        if m8 {
+            code |= MEMORY_8;
+        }
+        if x8 {
+            code |= INDEX_8;
+        }
+        for i in 0..insn.len as u16 {
+            let addr = (insn.addr & 0xFF0000) | (insn.addr as u16).wrapping_add(i) as u32;
+            if let Some(offset) = memory.rom_offset(addr) {
+                // Widths reflect the most recent execution
+                self.flags[offset] = (self.flags[offset] & !(MEMORY_8 | INDEX_8)) | code;
+            }
+        }
+
+        let pbr = insn.addr & 0xFF0000;
+        let operand = insn.operand();
+        let (addr, len, flags) = match insn.mode {
+            // Jump tables: the pointer is the data
+            AddrMode::AbsoluteIndirect => (operand, 2, DATA | INDIRECT),
+            AddrMode::AbsoluteIndexedIndirect => (pbr | ((operand + cpu.x as u32) & 0xFFFF), 2, DATA | INDIRECT),
+            AddrMode::AbsoluteIndirectLong => (operand, 3, DATA | INDIRECT),
+            // MVN/MVP move one byte per execution from srcbank:X
+            AddrMode::BlockMove => (((insn.bytes[2] as u32) << 16) | cpu.x as u32, 1, DATA),
+            mode => {
+                let Some(len) = read_width(&insn, m8, x8) else {
+                    return insn;
+                };
+                let Some(addr) = insn.effective_address(cpu, |a| memory.peek(a)) else {
+                    return insn;
+                };
+                let indirect = matches!(
+                    mode,
+                    AddrMode::DirectIndirect
+                        | AddrMode::DirectIndexedIndirect
+                        | AddrMode::DirectIndirectIndexed
+                        | AddrMode::DirectIndirectLong
+                        | AddrMode::DirectIndirectLongIndexed
+                        | AddrMode::StackRelativeIndirectIndexed
+                );
+                (addr, len, if indirect { DATA | INDIRECT } else { DATA })
+            }
+        };
+        for i in 0..len {
+            if let Some(offset) = memory.rom_offset(addr.wrapping_add(i) & 0xFFFFFF) {
+                self.mark(offset, flags);
+            }
+        }
+        insn
+    }
+
+    /// Log where execution continued after `insn`
+    ///
+    /// Non-sequential destinations become jump targets, or subroutine
+    /// entries for JSR/JSL/BRK/COP. Returns are not followed.
+    pub fn record_flow(&mut self, insn: &Instruction, next_pc: u32, memory: &Memory) {
+        let sequential = (insn.addr & 0xFF0000) | (insn.addr as u16).wrapping_add(insn.len as u16) as u32;
+        if next_pc == sequential || insn.is_return() || insn.mode == AddrMode::BlockMove {
+            return;
+        }
+        let flag = if insn.is_call() || matches!(insn.opcode, 0x00 | 0x02) {
+            SUB_ENTRY
+        } else {
+            JUMP_TARGET
+        };
+        if let Some(offset) = memory.rom_offset(next_pc) {
+            self.mark(offset, flag);
+        }
+    }
+
+    /// Log entry into an interrupt handler
+    pub fn record_interrupt(&mut self, handler: u32, memory: &Memory) {
+        if let Some(offset) = memory.rom_offset(handler) {
+            self.mark(offset, SUB_ENTRY);
+        }
+    }
+
+    /// Disassemble the whole ROM using the log
+    ///
+    /// Logged code is decoded with the widths it ran with, everything else is
+    /// emitted as `.db` lines annotated with how it was accessed. Jump targets
+    /// and subroutine entries without a symbol get `loc_`/`sub_` labels. The
+    /// output uses the assembler's syntax (`org`, `.a8`/`.a16`, `.i8`/`.i16`).
+    pub fn listing(&self, rom: &[u8], mapping: MappingMode, symbols: Option<&SymbolTable>) -> String {
+        let len = rom.len().min(self.flags.len());
+        let address = |offset: usize| rom_offset_to_address(offset as u32, mapping);
+
+        let mut labels = symbols.cloned().unwrap_or_default();
+        for (offset, &flags) in self.flags[..len].iter().enumerate() {
+            let addr = address(offset);
+            if flags & (SUB_ENTRY | JUMP_TARGET) != 0 && labels.label(addr).is_none() {
+                let prefix = if flags & SUB_ENTRY != 0 { "sub" } else { "loc" };
+                labels.insert(addr, &format!("{}_{:06X}", prefix, addr));
+            }
+        }
+
+        let mut out = String::new();
+        let mut widths = None;
+        let mut expected = None;
+        let mut offset = 0;
+        while offset < len {
+            let addr = address(offset);
+            if expected != Some(addr) {
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+                let _ = writeln!(out, "org ${:06X}", addr);
+            }
+            if let Some(label) = labels.label(addr) {
+                let _ = writeln!(out, "{}:", label);
+            }
+
+            let flags = self.flags[offset];
+            let consumed = match self.code_at(rom, len, offset, mapping) {
+                Some(insn) => {
+                    let m8 = flags & MEMORY_8 != 0;
+                    let x8 = flags & INDEX_8 != 0;
+                    if widths != Some((m8, x8)) {
+                        let (old_m8, old_x8) = widths.unwrap_or((!m8, !x8));
+                        if old_m8 != m8 {
+                            let _ = writeln!(out, "    {}", if m8 { ".a8" } else { ".a16" });
+                        }
+                        if old_x8 != x8 {
+                            let _ = writeln!(out, "    {}", if x8 { ".i8" } else { ".i16" });
+                        }
+                        widths = Some((m8, x8));
+                    }
+                    let text = insn.format_with_symbols(&labels);
+                    let bytes: Vec<String> = insn.bytes().iter().map(|b| format!("{:02X}", b)).collect();
+                    let _ = writeln!(out, "    {:<36}; ${:06X}  {}", text, addr, bytes.join(" "));
+                    insn.len as usize
+                }
+                None => {
+                    // Up to 8 bytes with the same flags, stopping at labels and bank edges
+                    let mut count = 1;
+                    while count < 8
+                        && offset + count < len
+                        && self.flags[offset + count] == flags
+                        && self.flags[offset + count] & CODE == 0
+                        && address(offset + count) == addr + count as u32
+                        && labels.label(addr + count as u32).is_none()
+                    {
+                        count += 1;
+                    }
+                    let bytes: Vec<String> = rom[offset..offset + count].iter().map(|b| format!("${:02X}", b)).collect();
+                    let line = format!(".db {}", bytes.join(","));
+                    let _ = writeln!(out, "    {:<36}; ${:06X}  {}", line, addr, describe(flags));
+                    count
+                }
+            };
+            offset += consumed;
+            expected = Some(address(offset - 1) + 1);
+        }
+        out
+    }
+
+    /// Decode the logged instruction at `offset`, if all of its bytes are code
+    fn code_at(&self, rom: &[u8], len: usize, offset: usize, mapping: MappingMode) -> Option<Instruction> {
+        let flags = self.flags[offset];
+        if flags & CODE == 0 {
+            return None;
+        }
+        let addr = rom_offset_to_address(offset as u32, mapping);
+        let insn = disasm::decode(addr, flags & MEMORY_8 != 0, flags & INDEX_8 != 0, |a| {
+            rom.get(offset + a.wrapping_sub(addr) as u16 as usize).copied().unwrap_or(0)
+        });
+        let end = offset + insn.len as usize;
+        // Instructions crossing a bank edge of the mapping are left as data
+        let contiguous = rom_offset_to_address((end - 1) as u32, mapping) == addr + insn.len as u32 - 1;
+        (end <= len && contiguous && self.flags[offset + 1..end].iter().all(|f| f & CODE != 0)).then_some(insn)
+    }
+}
+
+/// Bytes an instruction reads from its effective address, if it reads at all
+fn read_width(insn: &Instruction, m8: bool, x8: bool) -> Option<u32> {
+    let accumulator = if m8 { 1 } else { 2 };
+    let index = if x8 { 1 } else { 2 };
+    match insn.mnemonic {
+        "LDA" | "ADC" | "SBC" | "AND" | "ORA" | "EOR" | "CMP" | "BIT" => Some(accumulator),
+        "ASL" | "LSR" | "ROL" | "ROR" | "INC" | "DEC" | "TSB" | "TRB" if insn.mode != AddrMode::Accumulator => {
+            Some(accumulator)
+        }
+        "LDX" | "LDY" | "CPX" | "CPY" => Some(index),
+        _ => None,
+    }
+}
+
+/// Listing comment for a byte that is not decoded as code
+fn describe(flags: u8) -> String {
+    let kinds: Vec<&str> = [(DATA, "data"), (INDIRECT, "indirect"), (DMA, "dma"), (CODE, "code, misaligned")]
+        .iter()
+        .filter(|&&(flag, _)| flags & flag != 0)
+        .map(|&(_, name)| name)
+        .collect();
+    if kinds.is_empty() {
+        "unknown".to_string()
+    } else {
+        kinds.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RomBuilder;
+
+    /// Run `steps` instructions of a LoROM program, logging each one
+    fn run_logged(code: &[u8], data: &[(u32, &[u8])], steps: usize) -> (CodeDataLog, Vec<u8>) {
+        let mut builder = RomBuilder::lorom(0x8000).with_code(0x008000, code);
+        for &(addr, bytes) in data {
+            builder.write(addr, bytes);
+        }
+        let rom = builder.build();
+        let cartridge = crate::cartridge::Cartridge::from_rom(rom.clone()).unwrap();
+        let mut memory = Memory::new(&cartridge);
+        let mut cpu = Cpu65816::new();
+        cpu.reset(&mut memory);
+
+        let mut cdl = CodeDataLog::new(rom.len());
+        for _ in 0..steps {
+            let insn = cdl.record_instruction(&cpu, &memory);
+            cpu.step(&mut memory);
+            let next_pc = ((cpu.pbr as u32) << 16) | cpu.pc as u32;
+            cdl.record_flow(&insn, next_pc, &memory);
+        }
+        (cdl, rom)
+    }
+
+    fn test_program() -> (CodeDataLog, Vec<u8>) {
+        let code = [
+            0xAD, 0x00, 0x90,   // $8000: LDA $9000
+            0x20, 0x00, 0x81,   // $8003: JSR $8100
+            0x80, 0xFE,         // $8006: BRA $8006
+        ];
+        let sub = [
+            0x18,               // $8100: CLC
+            0xFB,               // $8101: XCE
+            0xC2, 0x30,         // $8102: REP #$30
+            0xA9, 0x10, 0x90,   // $8104: LDA #$9010
+            0x85, 0x10,         // $8107: STA $10
+            0xA0, 0x02, 0x00,   // $8109: LDY #$0002
+            0xB1, 0x10,         // $810C: LDA ($10),Y
+            0x60,               // $810E: RTS
+        ];
+        run_logged(&code, &[(0x008100, &sub), (0x009000, &[0x42])], 11)
+    }
+
+    #[test]
+    fn test_code_and_widths() {
+        let (cdl, _) = test_program();
+        assert_eq!(cdl.get(0x0000), CODE | MEMORY_8 | INDEX_8);
+        assert_eq!(cdl.get(0x0002), CODE | MEMORY_8 | INDEX_8);
+        assert_eq!(cdl.get(0x0100), CODE | SUB_ENTRY | MEMORY_8 | INDEX_8);
+        assert_eq!(cdl.get(0x0104), CODE);
+        assert_eq!(cdl.get(0x0106), CODE);
+        // The RTS returned in native mode with 16-bit registers
+        assert_eq!(cdl.get(0x0006), CODE | JUMP_TARGET);
+        assert_eq!(cdl.get(0x0008), 0);
+    }
+
+    #[test]
+    fn test_data_and_indirect() {
+        let (cdl, _) = test_program();
+        assert_eq!(cdl.get(0x1000), DATA);
+        assert_eq!(cdl.get(0x1001), 0);
+        // 16-bit read through the pointer at $10
+        assert_eq!(cdl.get(0x1012), DATA | INDIRECT);
+        assert_eq!(cdl.get(0x1013), DATA | INDIRECT);
+        assert_eq!(cdl.count(DATA), 3);
+    }
+
+    #[test]
+    fn test_jump_table_pointer() {
+        let code = [
+            0xA2, 0x02,         // $8000: LDX #$02
+            0x7C, 0x00, 0x90,   // $8002: JMP ($9000,X)
+        ];
+        let (cdl, _) = run_logged(&code, &[(0x009000, &[0x00, 0x00, 0x00, 0x80])], 2);
+        assert_eq!(cdl.get(0x1000), 0);
+        assert_eq!(cdl.get(0x1002), DATA | INDIRECT);
+        assert_eq!(cdl.get(0x1003), DATA | INDIRECT);
+        assert_eq!(cdl.get(0x0000) & JUMP_TARGET, JUMP_TARGET);
+    }
+
+    #[test]
+    fn test_file_round_trip() {
+        let (mut cdl, rom) = test_program();
+        cdl.mark_dma(0x2000, 4);
+        let bytes = cdl.to_bytes();
+        assert_eq!(&bytes[..4], b"ZCDL");
+        assert_eq!(CodeDataLog::from_bytes(&bytes, rom.len()).unwrap(), cdl);
+        assert!(CodeDataLog::from_bytes(&bytes, rom.len() * 2).unwrap_err().contains("byte ROM"));
+        assert!(CodeDataLog::from_bytes(&bytes[..100], rom.len()).is_err());
+        assert!(CodeDataLog::from_bytes(b"NES\x1a", rom.len()).is_err());
+
+        assert_eq!(CodeDataLog::file_name("roms/game.sfc"), "roms/game.cdl");
+        assert_eq!(CodeDataLog::file_name("dir.v2/game"), "dir.v2/game.cdl");
+    }
+
+    #[test]
+    fn test_listing() {
+        let (mut cdl, rom) = test_program();
+        cdl.mark_dma(0x2000, 2);
+        let mut symbols = SymbolTable::new();
+        symbols.insert(0x008100, "Init");
+        let listing = cdl.listing(&rom, MappingMode::LoRom, Some(&symbols));
+
+        assert!(listing.starts_with("org $008000\n    .a8\n    .i8\n    LDA $9000"));
+        assert!(listing.contains("JSR Init"));
+        assert!(listing.contains("loc_008006:\n    .a16\n    .i16\n    BRA loc_008006"));
+        assert!(listing.contains("Init:\n    .a8\n    .i8\n    CLC"));
+        assert!(listing.contains("REP #$30                            ; $008102  C2 30\n    .a16\n    .i16\n    LDA #$9010"));
+        assert!(listing.contains(".db $42"));
+        assert!(listing.lines().any(|l| l.contains("; $009012") && l.ends_with("data, indirect")));
+        assert!(listing.lines().any(|l| l.contains("; $00A000") && l.ends_with("dma")));
+        assert!(listing.lines().any(|l| l.contains("; $008008") && l.ends_with("unknown")));
+    }
+}
@@ -0,0 +1,321 @@
+// Debugger Controller
+// Step into/over/out, run-to-address/scanline and call stack tracking on top
+// of Emulator::step
+
+use crate::debug::BreakpointHit;
+use crate::emulator::Emulator;
+
+/// Default instruction budget for a single run command
+const DEFAULT_MAX_INSTRUCTIONS: u64 = 10_000_000;
+
+/// How a call stack frame was entered
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FrameKind {
+    /// JSR abs / JSR (abs,X) - returns with RTS
+    Jsr,
+    /// JSL long - returns with RTL
+    Jsl,
+    /// Hardware interrupt, BRK or COP - returns with RTI
+    Interrupt,
+}
+
+/// One entry of the tracked call stack
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StackFrame {
+    pub kind: FrameKind,
+    /// 24-bit address of the call instruction (or the interrupted instruction)
+    pub call_site: u32,
+    /// 24-bit address the frame started executing at
+    pub target: u32,
+    /// 24-bit address execution resumes at when the frame returns
+    pub return_addr: u32,
+}
+
+/// Why a debugger command returned
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The command completed normally
+    Completed,
+    /// A breakpoint triggered before the command completed
+    Breakpoint(BreakpointHit),
+    /// The instruction budget ran out (or no ROM is loaded)
+    LimitReached,
+}
+
+/// Drives an `Emulator` one instruction at a time like a debugger
+pub struct Debugger {
+    call_stack: Vec<StackFrame>,
+    max_instructions: u64,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self {
+            call_stack: Vec::new(),
+            max_instructions: DEFAULT_MAX_INSTRUCTIONS,
+        }
+    }
+
+    /// Limit the number of instructions a single run command may execute
+    pub fn with_max_instructions(mut self, max_instructions: u64) -> Self {
+        self.max_instructions = max_instructions;
+        self
+    }
+
+    /// Tracked call stack, outermost frame first
+    pub fn call_stack(&self) -> &[StackFrame] {
+        &self.call_stack
+    }
+
+    /// Forget all tracked frames (e.g. after a reset or loading a state)
+    pub fn clear_call_stack(&mut self) {
+        self.call_stack.clear();
+    }
+
+    /// Execute exactly one instruction
+    pub fn step_into(&mut self, emulator: &mut Emulator) -> StopReason {
+        match self.step_instruction(emulator) {
+            Some(step) => step.hit.map_or(StopReason::Completed, StopReason::Breakpoint),
+            None => StopReason::LimitReached,
+        }
+    }
+
+    /// Execute one instruction, running JSR/JSL subroutines to completion
+    pub fn step_over(&mut self, emulator: &mut Emulator) -> StopReason {
+        let depth = self.call_stack.len();
+        let is_call = emulator
+            .disassemble(current_pc(emulator))
+            .is_some_and(|inst| inst.is_call());
+
+        let first = self.step_into(emulator);
+        if !is_call || first != StopReason::Completed || self.call_stack.len() <= depth {
+            return first;
+        }
+        self.run_until(emulator, |debugger, _, _| debugger.call_stack.len() <= depth)
+    }
+
+    /// Run until the current frame returns via RTS/RTL/RTI
+    pub fn step_out(&mut self, emulator: &mut Emulator) -> StopReason {
+        let depth = self.call_stack.len();
+        self.run_until(emulator, |debugger, _, returned| {
+            if depth == 0 {
+                returned
+            } else {
+                debugger.call_stack.len() < depth
+            }
+        })
+    }
+
+    /// Run until the CPU is about to execute the instruction at `addr`
+    pub fn run_to_address(&mut self, emulator: &mut Emulator, addr: u32) -> StopReason {
+        let addr = addr & 0xFFFFFF;
+        self.run_until(emulator, |_, emulator, _| current_pc(emulator) == addr)
+    }
+
+    /// Run until the PPU starts the given scanline
+    pub fn run_to_scanline(&mut self, emulator: &mut Emulator, scanline: u16) -> StopReason {
+        let mut left = emulator.get_scanline() != scanline;
+        self.run_until(emulator, |_, emulator, _| {
+            let current = emulator.get_scanline();
+            if current != scanline {
+                left = true;
+                false
+            } else {
+                left
+            }
+        })
+    }
+
+    /// Step until `done` returns true, a breakpoint hits or the budget runs out
+    ///
+    /// `done` receives the debugger, the emulator and whether the instruction
+    /// just executed was a return.
+    fn run_until<F>(&mut self, emulator: &mut Emulator, mut done: F) -> StopReason
+    where
+        F: FnMut(&Debugger, &Emulator, bool) -> bool,
+    {
+        for i in 0..self.max_instructions {
+            // The instruction we start on never triggers its own execute breakpoint
+            let pc = current_pc(emulator);
+            if i > 0 && emulator.breakpoints_mut().check_execute(pc) {
+                let hit = *emulator.break_reason().expect("execute breakpoint recorded");
+                return StopReason::Breakpoint(hit);
+            }
+
+            let Some(step) = self.step_instruction(emulator) else {
+                return StopReason::LimitReached;
+            };
+            if let Some(hit) = step.hit {
+                return StopReason::Breakpoint(hit);
+            }
+            if done(self, emulator, step.returned) {
+                return StopReason::Completed;
+            }
+        }
+        StopReason::LimitReached
+    }
+
+    /// Run one `Emulator::step`, updating the call stack
+    ///
+    /// Returns None when no ROM is loaded.
+    fn step_instruction(&mut self, emulator: &mut Emulator) -> Option<Step> {
+        let pc = current_pc(emulator);
+        let inst = emulator.disassemble(pc)?;
+
+        emulator.step();
+        let target = current_pc(emulator);
+        let mut returned = false;
+
+        if emulator.interrupt_taken() {
+            self.call_stack.push(StackFrame {
+                kind: FrameKind::Interrupt,
+                call_site: pc,
+                target,
+                return_addr: pc,
+            });
+        } else if inst.is_call() || matches!(inst.opcode, 0x00 | 0x02) {
+            let kind = match inst.opcode {
+                0x22 => FrameKind::Jsl,
+                0x00 | 0x02 => FrameKind::Interrupt,
+                _ => FrameKind::Jsr,
+            };
+            let return_pc = (pc as u16).wrapping_add(inst.len as u16);
+            self.call_stack.push(StackFrame {
+                kind,
+                call_site: pc,
+                target,
+                return_addr: (pc & 0xFF0000) | return_pc as u32,
+            });
+        } else if inst.is_return() {
+            self.call_stack.pop();
+            returned = true;
+        }
+
+        Some(Step {
+            returned,
+            hit: emulator.break_reason().copied(),
+        })
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Outcome of a single tracked instruction
+struct Step {
+    returned: bool,
+    hit: Option<BreakpointHit>,
+}
+
+fn current_pc(emulator: &Emulator) -> u32 {
+    let cpu = emulator.cpu();
+    ((cpu.pbr as u32) << 16) | cpu.pc as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debug::{AddressSpace, Breakpoint};
+    use crate::testing::RomBuilder;
+
+    /// Build a LoROM image running `code` from $008000
+    fn create_test_emulator(code: &[u8]) -> Emulator {
+        let rom = RomBuilder::lorom(0x8000).with_code(0x008000, code).build();
+        let mut emulator = Emulator::new();
+        emulator.load_rom(&rom).unwrap();
+        emulator
+    }
+
+    fn create_call_emulator() -> Emulator {
+        create_test_emulator(&[
+            0x20, 0x08, 0x80,   // $8000: JSR $8008
+            0xEA,               // $8003: NOP
+            0x80, 0xFE,         // $8004: BRA $8004
+            0xEA, 0xEA,         // $8006: (padding)
+            0x22, 0x10, 0x80, 0x00, // $8008: JSL $008010
+            0xE8,               // $800C: INX
+            0x60,               // $800D: RTS
+            0xEA, 0xEA,         // $800E: (padding)
+            0xC8,               // $8010: INY
+            0xC8,               // $8011: INY
+            0x6B,               // $8012: RTL
+        ])
+    }
+
+    #[test]
+    fn test_step_into_tracks_calls() {
+        let mut emulator = create_call_emulator();
+        let mut debugger = Debugger::new();
+
+        assert_eq!(debugger.step_into(&mut emulator), StopReason::Completed);
+        assert_eq!(emulator.cpu().pc, 0x8008);
+        assert_eq!(debugger.step_into(&mut emulator), StopReason::Completed);
+        assert_eq!(emulator.cpu().pc, 0x8010);
+
+        let stack = debugger.call_stack();
+        assert_eq!(stack.len(), 2);
+        assert_eq!(stack[0].kind, FrameKind::Jsr);
+        assert_eq!(stack[0].return_addr, 0x008003);
+        assert_eq!(stack[1].kind, FrameKind::Jsl);
+        assert_eq!(stack[1].call_site, 0x008008);
+        assert_eq!(stack[1].target, 0x008010);
+    }
+
+    #[test]
+    fn test_step_over_and_out() {
+        let mut emulator = create_call_emulator();
+        let mut debugger = Debugger::new();
+
+        // Step over the whole JSR
+        assert_eq!(debugger.step_over(&mut emulator), StopReason::Completed);
+        assert_eq!(emulator.cpu().pc, 0x8003);
+        assert_eq!(emulator.cpu().y & 0xFF, 2);
+        assert!(debugger.call_stack().is_empty());
+
+        // Step out of the JSL from inside it
+        let mut emulator = create_call_emulator();
+        debugger.clear_call_stack();
+        debugger.step_into(&mut emulator);
+        debugger.step_into(&mut emulator);
+        assert_eq!(debugger.step_out(&mut emulator), StopReason::Completed);
+        assert_eq!(emulator.cpu().pc, 0x800C);
+        assert_eq!(debugger.call_stack().len(), 1);
+    }
+
+    #[test]
+    fn test_run_to_address_and_breakpoint() {
+        let mut emulator = create_call_emulator();
+        let mut debugger = Debugger::new();
+
+        assert_eq!(debugger.run_to_address(&mut emulator, 0x008011), StopReason::Completed);
+        assert_eq!(emulator.cpu().pc, 0x8011);
+
+        let id = emulator.breakpoints_mut().add(Breakpoint::execute(0x00800D));
+        match debugger.run_to_address(&mut emulator, 0x008004) {
+            StopReason::Breakpoint(hit) => {
+                assert_eq!(hit.id, id);
+                assert_eq!(hit.pc, 0x00800D);
+            }
+            other => panic!("unexpected stop: {:?}", other),
+        }
+        assert_eq!(emulator.cpu().pc, 0x800D);
+
+        emulator.breakpoints_mut().add(Breakpoint::read(AddressSpace::Bus, 0xFFFFFF, 0xFFFFFF));
+        assert_eq!(debugger.run_to_address(&mut emulator, 0x008004), StopReason::Completed);
+    }
+
+    #[test]
+    fn test_run_to_scanline_and_limit() {
+        let mut emulator = create_call_emulator();
+        let mut debugger = Debugger::new();
+
+        assert_eq!(debugger.run_to_scanline(&mut emulator, 3), StopReason::Completed);
+        assert_eq!(emulator.get_scanline(), 3);
+
+        let mut debugger = Debugger::new().with_max_instructions(10);
+        assert_eq!(debugger.run_to_address(&mut emulator, 0x00C000), StopReason::LimitReached);
+    }
+}
@@ -0,0 +1,216 @@
+// Event Viewer
+// Per-frame log of I/O register writes, DMA transfers and interrupts with
+// their beam position, rendered as an overlay image of the frame
+
+/// Dots per scanline, the overlay width
+pub const OVERLAY_WIDTH: usize = 341;
+/// Scanlines per (NTSC) frame, the overlay height
+pub const OVERLAY_HEIGHT: usize = 262;
+
+/// What happened
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    /// CPU write to a PPU register ($2100-$213F)
+    PpuWrite,
+    /// CPU write to an APU port ($2140-$217F)
+    ApuWrite,
+    /// CPU write to a CPU I/O register ($4200-$437F), including MDMAEN and HDMAEN
+    CpuWrite,
+    /// General purpose DMA transfer on one channel
+    ///
+    /// `register` is the B-bus register ($21xx), `value` the channel and
+    /// `length` the number of bytes moved. A write to MDMAEN ($420B) records
+    /// one event per enabled channel, lowest channel first.
+    Dma,
+    /// HDMA transfer; not recorded until HDMA is emulated
+    Hdma,
+    /// IRQ serviced
+    ///
+    /// Only coprocessors raise IRQs; the H/V timer IRQ is not emulated.
+    Irq,
+    /// NMI serviced
+    ///
+    /// Only coprocessors raise NMIs; the PPU's vblank NMI is not delivered.
+    Nmi,
+}
+
+impl EventKind {
+    /// Overlay marker color (RGBA8888 like the framebuffer)
+    pub fn color(self) -> u32 {
+        match self {
+            EventKind::PpuWrite => 0xFFFFA040,
+            EventKind::ApuWrite => 0xFF40D0FF,
+            EventKind::CpuWrite => 0xFF40FF40,
+            EventKind::Dma => 0xFFFF40FF,
+            EventKind::Hdma => 0xFF0080FF,
+            EventKind::Irq => 0xFF4040FF,
+            EventKind::Nmi => 0xFFFFFFFF,
+        }
+    }
+}
+
+/// One recorded event
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub kind: EventKind,
+    pub scanline: u16,
+    pub dot: u16,
+    /// 24-bit address of the instruction that caused the event (for
+    /// interrupts, the instruction that was interrupted)
+    pub pc: u32,
+    /// Register address (16-bit), or the handler address for interrupts
+    pub register: u32,
+    /// Value written, the channel for DMA, or 0 for interrupts
+    pub value: u8,
+    /// Bytes moved by a DMA transfer (0 for other events)
+    pub length: u32,
+}
+
+/// Collects events frame by frame
+///
+/// Install with `Emulator::set_event_log`. The emulator records events as
+/// they happen and calls `end_frame` when the PPU finishes a frame, keeping
+/// the completed frame available through `last_frame`.
+#[derive(Clone, Debug, Default)]
+pub struct EventLog {
+    frame: Vec<Event>,
+    last: Vec<Event>,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, event: Event) {
+        self.frame.push(event);
+    }
+
+    /// Finish the current frame; its events become `last_frame`
+    pub fn end_frame(&mut self) {
+        self.last = std::mem::take(&mut self.frame);
+    }
+
+    /// Events of the frame in progress, in order
+    pub fn events(&self) -> &[Event] {
+        &self.frame
+    }
+
+    /// Events of the last completed frame, in order
+    pub fn last_frame(&self) -> &[Event] {
+        &self.last
+    }
+
+    pub fn clear(&mut self) {
+        self.frame.clear();
+        self.last.clear();
+    }
+
+    /// Events of the last completed frame within `radius` dots/lines of a
+    /// position, e.g. for a tooltip under the mouse
+    pub fn events_near(&self, scanline: u16, dot: u16, radius: u16) -> Vec<&Event> {
+        self.last
+            .iter()
+            .filter(|e| e.scanline.abs_diff(scanline) <= radius && e.dot.abs_diff(dot) <= radius)
+            .collect()
+    }
+
+    /// Render the last completed frame as an overlay image
+    pub fn render_overlay(&self) -> Vec<u32> {
+        render_overlay(&self.last)
+    }
+}
+
+/// Render events as an `OVERLAY_WIDTH` x `OVERLAY_HEIGHT` RGBA image
+///
+/// One pixel per dot and scanline; each event is drawn as a 3x3 marker in
+/// its kind's color on a transparent background.
+pub fn render_overlay(events: &[Event]) -> Vec<u32> {
+    let mut image = vec![0; OVERLAY_WIDTH * OVERLAY_HEIGHT];
+    for event in events {
+        let (x, y) = (event.dot as i32, event.scanline as i32);
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (px, py) = (x + dx, y + dy);
+                if (0..OVERLAY_WIDTH as i32).contains(&px) && (0..OVERLAY_HEIGHT as i32).contains(&py) {
+                    image[py as usize * OVERLAY_WIDTH + px as usize] = event.kind.color();
+                }
+            }
+        }
+    }
+    image
+}
+
+/// Classify a CPU write, returning None for addresses that are not I/O registers
+pub fn classify_write(addr: u32) -> Option<EventKind> {
+    let bank = (addr >> 16) as u8;
+    if !(bank < 0x40 || (0x80..0xC0).contains(&bank)) {
+        return None;
+    }
+    match addr as u16 {
+        0x2100..=0x213F => Some(EventKind::PpuWrite),
+        0x2140..=0x217F => Some(EventKind::ApuWrite),
+        0x4200..=0x421F | 0x4300..=0x437F => Some(EventKind::CpuWrite),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: EventKind, scanline: u16, dot: u16) -> Event {
+        Event {
+            kind,
+            scanline,
+            dot,
+            pc: 0x008000,
+            register: 0x2100,
+            value: 0x0F,
+            length: 0,
+        }
+    }
+
+    #[test]
+    fn test_classify_write() {
+        assert_eq!(classify_write(0x002100), Some(EventKind::PpuWrite));
+        assert_eq!(classify_write(0x802142), Some(EventKind::ApuWrite));
+        assert_eq!(classify_write(0x00420B), Some(EventKind::CpuWrite));
+        assert_eq!(classify_write(0x00420C), Some(EventKind::CpuWrite));
+        assert_eq!(classify_write(0x004302), Some(EventKind::CpuWrite));
+        assert_eq!(classify_write(0x7E2100), None);
+        assert_eq!(classify_write(0x000010), None);
+    }
+
+    #[test]
+    fn test_frames() {
+        let mut log = EventLog::new();
+        log.record(event(EventKind::PpuWrite, 10, 20));
+        assert_eq!(log.events().len(), 1);
+        assert!(log.last_frame().is_empty());
+
+        log.end_frame();
+        assert!(log.events().is_empty());
+        assert_eq!(log.last_frame().len(), 1);
+        assert_eq!(log.events_near(11, 22, 2).len(), 1);
+        assert!(log.events_near(11, 30, 2).is_empty());
+    }
+
+    #[test]
+    fn test_render_overlay() {
+        let mut log = EventLog::new();
+        log.record(event(EventKind::Nmi, 225, 0));
+        log.record(event(EventKind::PpuWrite, 261, 340));
+        log.end_frame();
+
+        let image = log.render_overlay();
+        assert_eq!(image.len(), OVERLAY_WIDTH * OVERLAY_HEIGHT);
+        let pixel = |x: usize, y: usize| image[y * OVERLAY_WIDTH + x];
+        assert_eq!(pixel(0, 225), EventKind::Nmi.color());
+        assert_eq!(pixel(1, 224), EventKind::Nmi.color());
+        assert_eq!(pixel(2, 225), 0);
+        // Markers at the edges are clipped
+        assert_eq!(pixel(340, 261), EventKind::PpuWrite.color());
+        assert_eq!(image.iter().filter(|&&p| p != 0).count(), 6 + 4);
+    }
+}
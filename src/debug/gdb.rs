@@ -0,0 +1,623 @@
+// GDB Remote Serial Protocol Stub
+// Register and memory access, breakpoints, watchpoints, single-step and
+// continue for a GDB client connected over TCP
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use crate::debug::{AddressSpace, Breakpoint, BreakpointHit};
+use crate::emulator::Emulator;
+
+/// Register layout reported through `qXfer:features:read:target.xml`
+///
+/// The `g`/`G` packets use the same order: A, X, Y, S, D (16 bits each), PC
+/// as a 24-bit address PBR:PC in 32 bits, then P, DBR and the E flag (8 bits
+/// each). All values are little-endian.
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.znes.w65c816">
+    <reg name="a" bitsize="16" type="int" regnum="0"/>
+    <reg name="x" bitsize="16" type="int"/>
+    <reg name="y" bitsize="16" type="int"/>
+    <reg name="s" bitsize="16" type="data_ptr"/>
+    <reg name="d" bitsize="16" type="data_ptr"/>
+    <reg name="pc" bitsize="32" type="code_ptr"/>
+    <reg name="p" bitsize="8" type="int"/>
+    <reg name="dbr" bitsize="8" type="int"/>
+    <reg name="e" bitsize="8" type="int"/>
+  </feature>
+</target>
+"#;
+
+/// Byte sizes of the registers, in register number order
+const REGISTER_SIZES: [usize; 9] = [2, 2, 2, 2, 2, 4, 1, 1, 1];
+
+/// Largest packet the client may send
+const PACKET_SIZE: usize = 0x4000;
+
+/// Signal reported for breakpoints, steps and watchpoints
+const SIGTRAP: u8 = 5;
+/// Signal reported when the client interrupts a continue
+const SIGINT: u8 = 2;
+
+/// What the connection loop does after a packet
+enum Action {
+    Reply(String),
+    Step,
+    Continue,
+    Detach,
+}
+
+/// Serves one GDB client
+///
+/// `listen` waits for a connection and runs the session until the client
+/// detaches or disconnects. Breakpoints created by the client are added to
+/// the emulator's breakpoint list and removed again when the session ends.
+pub struct GdbStub {
+    /// Client breakpoints by (Z type, address, length), mapped to breakpoint ids
+    breakpoints: HashMap<(u8, u32, u32), u32>,
+    no_ack: bool,
+    last_stop: String,
+}
+
+impl GdbStub {
+    pub fn new() -> Self {
+        Self {
+            breakpoints: HashMap::new(),
+            no_ack: false,
+            last_stop: format!("S{:02x}", SIGTRAP),
+        }
+    }
+
+    /// Accept one client on `addr` (e.g. `127.0.0.1:2345`) and serve it
+    pub fn listen(&mut self, addr: &str, emulator: &mut Emulator) -> Result<(), String> {
+        let listener = TcpListener::bind(addr).map_err(|e| format!("Failed to bind {}: {}", addr, e))?;
+        let (stream, _) = listener.accept().map_err(|e| format!("Failed to accept client: {}", e))?;
+        self.serve(stream, emulator)
+    }
+
+    /// Run a session on an accepted connection
+    pub fn serve(&mut self, mut stream: TcpStream, emulator: &mut Emulator) -> Result<(), String> {
+        let _ = stream.set_nodelay(true);
+        let result = self.session(&mut stream, emulator);
+        for (_, id) in self.breakpoints.drain() {
+            emulator.breakpoints_mut().remove(id);
+        }
+        result
+    }
+
+    fn session(&mut self, stream: &mut TcpStream, emulator: &mut Emulator) -> Result<(), String> {
+        while let Some(packet) = self.read_packet(stream)? {
+            let reply = match self.handle_packet(emulator, &packet) {
+                Action::Reply(reply) => reply,
+                Action::Step => {
+                    emulator.step();
+                    self.stop_reply(emulator.break_reason().copied(), SIGTRAP)
+                }
+                Action::Continue => match self.resume(stream, emulator)? {
+                    Some(reply) => reply,
+                    None => return Ok(()),
+                },
+                Action::Detach => {
+                    self.write_packet(stream, "OK")?;
+                    return Ok(());
+                }
+            };
+            self.write_packet(stream, &reply)?;
+        }
+        Ok(())
+    }
+
+    /// Handle one packet payload
+    fn handle_packet(&mut self, emulator: &mut Emulator, packet: &str) -> Action {
+        let reply = match packet.as_bytes().first() {
+            Some(b'?') => self.last_stop.clone(),
+            Some(b'g') => read_registers(emulator),
+            Some(b'G') => reply_result(write_registers(emulator, &packet[1..])),
+            Some(b'p') => match parse_hex(&packet[1..]).and_then(|n| read_register(emulator, n as usize)) {
+                Ok(value) => value,
+                Err(_) => "E01".to_string(),
+            },
+            Some(b'P') => reply_result(packet[1..].split_once('=').ok_or_else(|| "Malformed P".to_string()).and_then(
+                |(reg, value)| write_register(emulator, parse_hex(reg)? as usize, value),
+            )),
+            Some(b'm') => read_memory(emulator, &packet[1..]).unwrap_or_else(|_| "E01".to_string()),
+            Some(b'M') => reply_result(write_memory(emulator, &packet[1..])),
+            Some(b'Z') => reply_result(self.insert_breakpoint(emulator, &packet[1..])),
+            Some(b'z') => reply_result(self.remove_breakpoint(emulator, &packet[1..])),
+            // Resuming at a different address is not supported
+            Some(b's') => return Action::Step,
+            Some(b'c') => return Action::Continue,
+            Some(b'D') | Some(b'k') => return Action::Detach,
+            Some(b'H') => "OK".to_string(),
+            Some(b'T') => "OK".to_string(),
+            _ => self.handle_query(packet),
+        };
+        Action::Reply(reply)
+    }
+
+    fn handle_query(&mut self, packet: &str) -> String {
+        if packet.starts_with("qSupported") {
+            format!("PacketSize={:x};qXfer:features:read+;QStartNoAckMode+;swbreak+;hwbreak+", PACKET_SIZE)
+        } else if packet == "QStartNoAckMode" {
+            self.no_ack = true;
+            "OK".to_string()
+        } else if let Some(range) = packet.strip_prefix("qXfer:features:read:target.xml:") {
+            read_xfer(TARGET_XML, range).unwrap_or_else(|_| "E01".to_string())
+        } else if packet == "qAttached" {
+            "1".to_string()
+        } else if packet == "qC" {
+            "QC1".to_string()
+        } else if packet == "qfThreadInfo" {
+            "m1".to_string()
+        } else if packet == "qsThreadInfo" {
+            "l".to_string()
+        } else {
+            // Empty reply: not supported
+            String::new()
+        }
+    }
+
+    /// Continue until a breakpoint hits, the client sends ^C or disconnects
+    ///
+    /// Returns the stop reply, or None when the client went away.
+    fn resume(&mut self, stream: &mut TcpStream, emulator: &mut Emulator) -> Result<Option<String>, String> {
+        loop {
+            emulator.run_frame();
+            if let Some(hit) = emulator.break_reason().copied() {
+                return Ok(Some(self.stop_reply(Some(hit), SIGTRAP)));
+            }
+
+            stream.set_nonblocking(true).map_err(|e| e.to_string())?;
+            let mut byte = [0u8];
+            let polled = stream.read(&mut byte);
+            stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+            match polled {
+                Ok(0) => return Ok(None),
+                Ok(_) if byte[0] == 0x03 => return Ok(Some(self.stop_reply(None, SIGINT))),
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+    }
+
+    /// Build and remember the stop reply for a breakpoint hit (or a plain stop)
+    fn stop_reply(&mut self, hit: Option<BreakpointHit>, signal: u8) -> String {
+        let kind = hit.and_then(|hit| {
+            let (&(z_type, _, _), _) = self.breakpoints.iter().find(|&(_, &id)| id == hit.id)?;
+            Some(match z_type {
+                0 => "swbreak:".to_string(),
+                1 => "hwbreak:".to_string(),
+                2 => format!("watch:{:x}", hit.addr),
+                3 => format!("rwatch:{:x}", hit.addr),
+                _ => format!("awatch:{:x}", hit.addr),
+            })
+        });
+        self.last_stop = match kind {
+            Some(kind) => format!("T{:02x}{};", signal, kind),
+            None => format!("S{:02x}", signal),
+        };
+        self.last_stop.clone()
+    }
+
+    /// `Z type,addr,kind`: add a breakpoint (0/1) or watchpoint (2 write, 3 read, 4 access)
+    fn insert_breakpoint(&mut self, emulator: &mut Emulator, args: &str) -> Result<(), String> {
+        let (z_type, addr, len) = parse_breakpoint(args)?;
+        if self.breakpoints.contains_key(&(z_type, addr, len)) {
+            return Ok(());
+        }
+        let end = addr.saturating_add(len.max(1) - 1).min(0xFFFFFF);
+        let breakpoint = match z_type {
+            0 | 1 => Breakpoint::execute(addr),
+            2 => Breakpoint::write(AddressSpace::Bus, addr, end),
+            3 => Breakpoint::read(AddressSpace::Bus, addr, end),
+            4 => Breakpoint::access(AddressSpace::Bus, addr, end),
+            _ => return Err(format!("Unsupported breakpoint type {}", z_type)),
+        };
+        let id = emulator.breakpoints_mut().add(breakpoint);
+        self.breakpoints.insert((z_type, addr, len), id);
+        Ok(())
+    }
+
+    fn remove_breakpoint(&mut self, emulator: &mut Emulator, args: &str) -> Result<(), String> {
+        let key = parse_breakpoint(args)?;
+        if let Some(id) = self.breakpoints.remove(&key) {
+            emulator.breakpoints_mut().remove(id);
+        }
+        Ok(())
+    }
+
+    /// Read the next packet payload, acknowledging it
+    ///
+    /// Returns None when the client disconnects.
+    fn read_packet(&mut self, stream: &mut TcpStream) -> Result<Option<String>, String> {
+        loop {
+            let Some(byte) = read_byte(stream)? else {
+                return Ok(None);
+            };
+            match byte {
+                b'$' => {}
+                // ^C while already stopped
+                0x03 => {
+                    let reply = self.last_stop.clone();
+                    self.write_packet(stream, &reply)?;
+                    continue;
+                }
+                // Acks and noise between packets
+                _ => continue,
+            }
+
+            let mut payload = Vec::new();
+            loop {
+                match read_byte(stream)? {
+                    Some(b'#') => break,
+                    Some(byte) if payload.len() < PACKET_SIZE => payload.push(byte),
+                    Some(_) => return Err("Packet too large".to_string()),
+                    None => return Ok(None),
+                }
+            }
+            let mut checksum = [0u8; 2];
+            for digit in checksum.iter_mut() {
+                match read_byte(stream)? {
+                    Some(byte) => *digit = byte,
+                    None => return Ok(None),
+                }
+            }
+
+            let expected = std::str::from_utf8(&checksum).ok().and_then(|s| u8::from_str_radix(s, 16).ok());
+            if !self.no_ack {
+                let ack: &[u8] = if expected == Some(checksum_of(&payload)) { b"+" } else { b"-" };
+                stream.write_all(ack).map_err(|e| e.to_string())?;
+                if ack == b"-" {
+                    continue;
+                }
+            }
+            return Ok(Some(unescape(&payload)));
+        }
+    }
+
+    /// Send a packet, resending until the client acknowledges it
+    fn write_packet(&mut self, stream: &mut TcpStream, payload: &str) -> Result<(), String> {
+        let packet = format!("${}#{:02x}", payload, checksum_of(payload.as_bytes()));
+        loop {
+            stream.write_all(packet.as_bytes()).map_err(|e| e.to_string())?;
+            if self.no_ack {
+                return Ok(());
+            }
+            match read_byte(stream)? {
+                Some(b'-') => continue,
+                _ => return Ok(()),
+            }
+        }
+    }
+}
+
+impl Default for GdbStub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn read_byte(stream: &mut TcpStream) -> Result<Option<u8>, String> {
+    let mut byte = [0u8];
+    match stream.read(&mut byte) {
+        Ok(0) => Ok(None),
+        Ok(_) => Ok(Some(byte[0])),
+        Err(e) if e.kind() == ErrorKind::ConnectionReset => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn checksum_of(payload: &[u8]) -> u8 {
+    payload.iter().fold(0u8, |sum, &b| sum.wrapping_add(b))
+}
+
+/// Undo `}` escaping (the next byte XOR 0x20)
+fn unescape(payload: &[u8]) -> String {
+    let mut out = Vec::with_capacity(payload.len());
+    let mut bytes = payload.iter();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            b'}' => out.extend(bytes.next().map(|b| b ^ 0x20)),
+            _ => out.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn reply_result(result: Result<(), String>) -> String {
+    match result {
+        Ok(()) => "OK".to_string(),
+        Err(_) => "E01".to_string(),
+    }
+}
+
+fn parse_hex(text: &str) -> Result<u32, String> {
+    u32::from_str_radix(text, 16).map_err(|_| format!("Invalid hex number: {}", text))
+}
+
+fn decode_hex(text: &str) -> Result<Vec<u8>, String> {
+    if !text.len().is_multiple_of(2) {
+        return Err("Odd length hex data".to_string());
+    }
+    // Decode bytes, not chars: unescaped payloads may hold U+FFFD
+    text.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| format!("Invalid hex data: {}", text))
+        })
+        .collect()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{:02x}", b);
+        out
+    })
+}
+
+/// Parse `addr,len`
+fn parse_range(text: &str) -> Result<(u32, u32), String> {
+    let (addr, len) = text.split_once(',').ok_or_else(|| format!("Malformed range: {}", text))?;
+    Ok((parse_hex(addr)? & 0xFFFFFF, parse_hex(len)?))
+}
+
+/// Parse `type,addr,kind` from a Z/z packet
+fn parse_breakpoint(args: &str) -> Result<(u8, u32, u32), String> {
+    let (z_type, range) = args.split_once(',').ok_or_else(|| format!("Malformed breakpoint: {}", args))?;
+    let z_type = z_type.parse().map_err(|_| format!("Invalid breakpoint type: {}", z_type))?;
+    let (addr, len) = parse_range(range.split(';').next().unwrap_or(range))?;
+    Ok((z_type, addr, len))
+}
+
+/// Register `n` as little-endian bytes
+fn register_bytes(emulator: &Emulator, n: usize) -> Option<Vec<u8>> {
+    let cpu = emulator.cpu();
+    let value = match n {
+        0 => cpu.a as u32,
+        1 => cpu.x as u32,
+        2 => cpu.y as u32,
+        3 => cpu.s as u32,
+        4 => cpu.d as u32,
+        5 => ((cpu.pbr as u32) << 16) | cpu.pc as u32,
+        6 => cpu.p.to_byte() as u32,
+        7 => cpu.dbr as u32,
+        8 => cpu.p.e as u32,
+        _ => return None,
+    };
+    Some(value.to_le_bytes()[..REGISTER_SIZES[n]].to_vec())
+}
+
+fn read_register(emulator: &Emulator, n: usize) -> Result<String, String> {
+    register_bytes(emulator, n).map(|bytes| encode_hex(&bytes)).ok_or_else(|| format!("No register {}", n))
+}
+
+fn read_registers(emulator: &Emulator) -> String {
+    (0..REGISTER_SIZES.len()).filter_map(|n| register_bytes(emulator, n)).map(|b| encode_hex(&b)).collect()
+}
+
+fn write_register(emulator: &mut Emulator, n: usize, hex: &str) -> Result<(), String> {
+    let bytes = decode_hex(hex)?;
+    let size = *REGISTER_SIZES.get(n).ok_or_else(|| format!("No register {}", n))?;
+    if bytes.len() != size {
+        return Err(format!("Register {} is {} bytes", n, size));
+    }
+    let value = bytes.iter().rev().fold(0u32, |acc, &b| (acc << 8) | b as u32);
+    let cpu = emulator.cpu_mut();
+    match n {
+        0 => cpu.a = value as u16,
+        1 => cpu.x = value as u16,
+        2 => cpu.y = value as u16,
+        3 => cpu.s = value as u16,
+        4 => cpu.d = value as u16,
+        5 => {
+            cpu.pbr = (value >> 16) as u8;
+            cpu.pc = value as u16;
+        }
+        6 => cpu.p.from_byte(value as u8),
+        7 => cpu.dbr = value as u8,
+        _ => cpu.p.e = value != 0,
+    }
+    Ok(())
+}
+
+fn write_registers(emulator: &mut Emulator, hex: &str) -> Result<(), String> {
+    let mut pos = 0;
+    for (n, size) in REGISTER_SIZES.iter().enumerate() {
+        let end = pos + size * 2;
+        let Some(value) = hex.get(pos..end) else {
+            // Short packets leave the remaining registers unchanged
+            break;
+        };
+        write_register(emulator, n, value)?;
+        pos = end;
+    }
+    Ok(())
+}
+
+/// `addr,len`: read from the CPU bus without side effects (I/O registers
+/// are not visible)
+fn read_memory(emulator: &mut Emulator, args: &str) -> Result<String, String> {
+    let (addr, len) = parse_range(args)?;
+    let memory = emulator.memory().ok_or_else(|| "No ROM loaded".to_string())?;
+    let bytes: Vec<u8> = (0..len.min(PACKET_SIZE as u32 / 2))
+        .map(|i| memory.peek(addr.wrapping_add(i) & 0xFFFFFF))
+        .collect();
+    Ok(encode_hex(&bytes))
+}
+
+/// `addr,len:data`: write to the CPU bus (ROM is not writable)
+fn write_memory(emulator: &mut Emulator, args: &str) -> Result<(), String> {
+    let (range, data) = args.split_once(':').ok_or_else(|| format!("Malformed write: {}", args))?;
+    let (addr, len) = parse_range(range)?;
+    let bytes = decode_hex(data)?;
+    if bytes.len() != len as usize {
+        return Err("Write length mismatch".to_string());
+    }
+    let memory = emulator.memory_mut().ok_or_else(|| "No ROM loaded".to_string())?;
+    for (i, &byte) in bytes.iter().enumerate() {
+        memory.write(addr.wrapping_add(i as u32) & 0xFFFFFF, byte);
+    }
+    Ok(())
+}
+
+/// `offset,length` of a qXfer read
+fn read_xfer(document: &str, range: &str) -> Result<String, String> {
+    let (offset, len) = parse_range(range)?;
+    let start = (offset as usize).min(document.len());
+    let end = (start + len as usize).min(document.len());
+    let prefix = if end == document.len() { 'l' } else { 'm' };
+    Ok(format!("{}{}", prefix, &document[start..end]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::RomBuilder;
+    use std::thread;
+
+    /// Minimal client: sends a packet and returns the reply payload
+    struct Client {
+        stream: TcpStream,
+    }
+
+    impl Client {
+        fn send_raw(&mut self, packet: &[u8]) {
+            self.stream.write_all(packet).unwrap();
+        }
+
+        fn request(&mut self, payload: &str) -> String {
+            let packet = format!("${}#{:02x}", payload, checksum_of(payload.as_bytes()));
+            self.send_raw(packet.as_bytes());
+            assert_eq!(read_byte(&mut self.stream).unwrap(), Some(b'+'), "ack for {}", payload);
+            self.reply()
+        }
+
+        fn reply(&mut self) -> String {
+            while read_byte(&mut self.stream).unwrap() != Some(b'$') {}
+            let mut payload = Vec::new();
+            loop {
+                match read_byte(&mut self.stream).unwrap().unwrap() {
+                    b'#' => break,
+                    byte => payload.push(byte),
+                }
+            }
+            let mut checksum = [0u8; 2];
+            self.stream.read_exact(&mut checksum).unwrap();
+            let checksum = u8::from_str_radix(std::str::from_utf8(&checksum).unwrap(), 16).unwrap();
+            assert_eq!(checksum, checksum_of(&payload));
+            self.send_raw(b"+");
+            String::from_utf8(payload).unwrap()
+        }
+    }
+
+    fn create_test_emulator() -> Emulator {
+        let code = [
+            0xA9, 0x12,         // $8000: LDA #$12
+            0x8D, 0x10, 0x00,   // $8002: STA $0010
+            0xEA,               // $8005: NOP
+            0xAD, 0x20, 0x00,   // $8006: LDA $0020
+            0x80, 0xFE,         // $8009: BRA $8009
+        ];
+        let rom = RomBuilder::lorom(0x8000).with_code(0x008000, &code).build();
+        let mut emulator = Emulator::new();
+        emulator.load_rom(&rom).unwrap();
+        emulator
+    }
+
+    /// Run `session` as a client against a stub serving a fresh emulator
+    fn run_session<F>(session: F) -> Emulator
+    where
+        F: FnOnce(&mut Client) + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            let mut client = Client { stream: TcpStream::connect(addr).unwrap() };
+            session(&mut client);
+        });
+
+        let mut emulator = create_test_emulator();
+        let (stream, _) = listener.accept().unwrap();
+        GdbStub::new().serve(stream, &mut emulator).unwrap();
+        client.join().unwrap();
+        emulator
+    }
+
+    #[test]
+    fn test_registers_and_memory() {
+        let emulator = run_session(|client| {
+            assert!(client.request("qSupported:swbreak+").contains("qXfer:features:read+"));
+            assert_eq!(client.request("?"), "S05");
+            // A X Y S D (2 bytes), PC (4), P DBR E (1)
+            let registers = client.request("g");
+            assert_eq!(registers.len(), 34);
+            assert_eq!(&registers[20..28], "00800000");
+            assert_eq!(&registers[32..], "01");
+            assert_eq!(client.request("p5"), "00800000");
+            assert_eq!(client.request("P0=3412"), "OK");
+            assert_eq!(client.request("p0"), "3412");
+
+            assert_eq!(client.request("m8000,3"), "a9128d");
+            assert_eq!(client.request("M7e0100,2:beef"), "OK");
+            assert_eq!(client.request("m7e0100,2"), "beef");
+            assert_eq!(client.request("mzz,2"), "E01");
+
+            let xml = client.request("qXfer:features:read:target.xml:0,2000");
+            assert!(xml.starts_with('l') && xml.contains("name=\"dbr\""));
+            assert_eq!(client.request("vMustReplyEmpty"), "");
+            assert_eq!(client.request("D"), "OK");
+        });
+        assert_eq!(emulator.cpu().a, 0x1234);
+    }
+
+    #[test]
+    fn test_decode_hex() {
+        assert_eq!(decode_hex("beef"), Ok(vec![0xBE, 0xEF]));
+        assert!(decode_hex("bee").is_err());
+        // A non-UTF-8 payload byte unescapes to U+FFFD (three bytes)
+        assert!(decode_hex("\u{FFFD}0").is_err());
+        assert!(decode_hex(&unescape(b"\xff\xfe00")).is_err());
+    }
+
+    #[test]
+    fn test_breakpoints_and_stepping() {
+        let emulator = run_session(|client| {
+            assert_eq!(client.request("QStartNoAckMode"), "OK");
+            // Acks are off from here on
+            let mut request = |payload: &str| {
+                let packet = format!("${}#{:02x}", payload, checksum_of(payload.as_bytes()));
+                client.send_raw(packet.as_bytes());
+                client.reply()
+            };
+
+            assert_eq!(request("s"), "S05");
+            assert_eq!(request("p5"), "02800000");
+
+            assert_eq!(request("Z0,8006,1"), "OK");
+            assert_eq!(request("c"), "T05swbreak:;");
+            assert_eq!(request("p5"), "06800000");
+            assert_eq!(request("m0010,1"), "12");
+            assert_eq!(request("z0,8006,1"), "OK");
+
+            assert_eq!(request("Z3,20,1"), "OK");
+            assert_eq!(request("c"), "T05rwatch:20;");
+            assert_eq!(request("p5"), "09800000");
+            assert_eq!(request("?"), "T05rwatch:20;");
+            assert_eq!(request("Z9,0,1"), "E01");
+            // Lengths past the end of the address space are clipped
+            assert_eq!(request("Z2,10,ffffffff"), "OK");
+            assert_eq!(request("z2,10,ffffffff"), "OK");
+            assert_eq!(request("k"), "OK");
+        });
+        // Breakpoints left by the client are removed when it goes away
+        assert!(emulator.breakpoints().is_empty());
+    }
+}
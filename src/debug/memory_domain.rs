@@ -0,0 +1,60 @@
+// Memory Domains
+// One name per memory of the system, for hex editors, cheat searches and
+// scripts; see `Emulator::peek`/`poke`
+
+/// A memory of the emulated system, addressed from 0
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MemoryDomain {
+    /// 128KB work RAM ($7E0000-$7FFFFF)
+    Wram,
+    /// Cartridge save RAM (also SA-1 BW-RAM and GSU RAM)
+    Sram,
+    /// Cartridge ROM; pokes patch the loaded image
+    Rom,
+    /// 64KB video RAM, byte addressed
+    Vram,
+    /// 544 bytes of sprite attributes
+    Oam,
+    /// 512 bytes of palette, two bytes per BGR555 color (low byte first)
+    Cgram,
+    /// 64KB SPC700 audio RAM
+    Aram,
+    /// 128 DSP registers
+    DspRegisters,
+    /// Coprocessor internal RAM (SA-1 I-RAM, GSU cache)
+    CoprocessorRam,
+}
+
+impl MemoryDomain {
+    /// Every domain, in display order
+    pub const ALL: [MemoryDomain; 9] = [
+        MemoryDomain::Wram,
+        MemoryDomain::Sram,
+        MemoryDomain::Rom,
+        MemoryDomain::Vram,
+        MemoryDomain::Oam,
+        MemoryDomain::Cgram,
+        MemoryDomain::Aram,
+        MemoryDomain::DspRegisters,
+        MemoryDomain::CoprocessorRam,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MemoryDomain::Wram => "WRAM",
+            MemoryDomain::Sram => "SRAM",
+            MemoryDomain::Rom => "ROM",
+            MemoryDomain::Vram => "VRAM",
+            MemoryDomain::Oam => "OAM",
+            MemoryDomain::Cgram => "CGRAM",
+            MemoryDomain::Aram => "ARAM",
+            MemoryDomain::DspRegisters => "DSP",
+            MemoryDomain::CoprocessorRam => "Coprocessor RAM",
+        }
+    }
+
+    /// Look up a domain by `name` (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|d| d.name().eq_ignore_ascii_case(name))
+    }
+}
@@ -0,0 +1,27 @@
+//! Debugging Support
+//!
+//! This module provides the tooling used to inspect a running system:
+//! breakpoints and watchpoints on the CPU bus and on the PPU/APU memories,
+//! instruction trace logging, symbol files, a stepping debugger controller, a
+//! GDB remote protocol stub, an execution profiler, a code/data logger for ROM
+//! disassembly, a per-frame event viewer and uniform access to every memory.
+
+pub mod breakpoints;
+pub mod cdl;
+pub mod debugger;
+pub mod events;
+pub mod gdb;
+pub mod memory_domain;
+pub mod profiler;
+pub mod symbols;
+pub mod trace;
+
+pub use breakpoints::{AccessKind, AddressSpace, Breakpoint, BreakpointHit, Breakpoints, Condition};
+pub use cdl::CodeDataLog;
+pub use debugger::{Debugger, FrameKind, StackFrame, StopReason};
+pub use events::{Event, EventKind, EventLog};
+pub use gdb::GdbStub;
+pub use memory_domain::MemoryDomain;
+pub use profiler::{FrameStats, PcStats, Profiler, SubroutineStats};
+pub use symbols::SymbolTable;
+pub use trace::{TraceEntry, TraceFormat, TraceLogger, TraceSink};
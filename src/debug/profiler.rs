@@ -0,0 +1,354 @@
+// Execution Profiler
+// Cycles per PC, per subroutine (via JSR/JSL tracking) and per frame, with
+// hotspot reports and collapsed-stack output for flamegraph tools
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::debug::symbols::SymbolTable;
+
+/// Cycles and executions attributed to one instruction address
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PcStats {
+    pub cycles: u64,
+    pub count: u64,
+}
+
+/// Cycles attributed to one subroutine, keyed by its entry address
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubroutineStats {
+    pub calls: u64,
+    /// Cycles spent in the subroutine itself
+    pub exclusive_cycles: u64,
+    /// Cycles spent in the subroutine and everything it called
+    pub inclusive_cycles: u64,
+}
+
+/// Totals for one video frame
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    pub cycles: u64,
+    /// Cycles spent halted in WAI
+    pub wait_cycles: u64,
+    /// Cycles the CPU was stalled by DMA
+    pub dma_cycles: u64,
+}
+
+/// Collects CPU cycle counts while the emulator runs
+///
+/// Install with `Emulator::set_profiler`; the emulator reports every
+/// instruction, WAI cycle, DMA stall and frame boundary.
+#[derive(Clone, Debug, Default)]
+pub struct Profiler {
+    pcs: HashMap<u32, PcStats>,
+    subroutines: HashMap<u32, SubroutineStats>,
+    /// Cycles per call stack (entry addresses, outermost first)
+    stacks: HashMap<Vec<u32>, u64>,
+    /// Entry addresses of the active subroutines, outermost first
+    stack: Vec<u32>,
+    /// Stack pointer just after each active subroutine was entered (None
+    /// for the outermost frame, which is never left)
+    stack_pointers: Vec<Option<u16>>,
+    frames: Vec<FrameStats>,
+    frame: FrameStats,
+    total: FrameStats,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one executed instruction
+    ///
+    /// `next_pc` and `s` are the PC and stack pointer after the instruction.
+    /// JSR/JSL enter the subroutine at `next_pc`. A subroutine is left once
+    /// S rises above its value on entry, which covers RTS/RTL/RTI as well as
+    /// code that drops its return address and jumps away.
+    pub fn record_instruction(&mut self, pc: u32, opcode: u8, next_pc: u32, s: u16, cycles: u64) {
+        if self.stack.is_empty() {
+            self.enter(pc, None);
+        }
+        let stats = self.pcs.entry(pc).or_default();
+        stats.cycles += cycles;
+        stats.count += 1;
+        self.charge(cycles);
+        self.leave(s);
+
+        // JSR abs, JSL long, JSR (abs,X)
+        if matches!(opcode, 0x20 | 0x22 | 0xFC) {
+            self.enter(next_pc, Some(s));
+        }
+    }
+
+    /// Record an interrupt entry; the handler is profiled as a subroutine
+    ///
+    /// `cycles` covers pushing the return state and reading the vector, and
+    /// `s` is the stack pointer after the pushes.
+    pub fn record_interrupt(&mut self, handler: u32, s: u16, cycles: u64) {
+        if self.stack.is_empty() {
+            self.enter(handler, None);
+        } else {
+            self.enter(handler, Some(s));
+        }
+        self.charge(cycles);
+    }
+
+    /// Record cycles spent halted in WAI
+    pub fn record_wait(&mut self, cycles: u64) {
+        self.frame.wait_cycles += cycles;
+        self.total.wait_cycles += cycles;
+        self.frame.cycles += cycles;
+        self.total.cycles += cycles;
+    }
+
+    /// Record cycles the CPU was stalled by a DMA transfer
+    pub fn record_dma(&mut self, cycles: u64) {
+        self.frame.dma_cycles += cycles;
+        self.total.dma_cycles += cycles;
+        self.frame.cycles += cycles;
+        self.total.cycles += cycles;
+    }
+
+    /// Close the current frame
+    pub fn end_frame(&mut self) {
+        self.frames.push(std::mem::take(&mut self.frame));
+    }
+
+    /// Forget everything recorded so far
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn pc_stats(&self, pc: u32) -> Option<&PcStats> {
+        self.pcs.get(&pc)
+    }
+
+    pub fn subroutine_stats(&self, entry: u32) -> Option<&SubroutineStats> {
+        self.subroutines.get(&entry)
+    }
+
+    /// Completed frames, oldest first
+    pub fn frames(&self) -> &[FrameStats] {
+        &self.frames
+    }
+
+    /// Totals over everything recorded, including the unfinished frame
+    pub fn total(&self) -> FrameStats {
+        self.total
+    }
+
+    /// Instruction addresses sorted by cycles spent, highest first
+    pub fn hotspots(&self) -> Vec<(u32, PcStats)> {
+        let mut hotspots: Vec<_> = self.pcs.iter().map(|(&pc, &stats)| (pc, stats)).collect();
+        hotspots.sort_by(|a, b| b.1.cycles.cmp(&a.1.cycles).then(a.0.cmp(&b.0)));
+        hotspots
+    }
+
+    /// Subroutines sorted by inclusive cycles, highest first
+    pub fn subroutines(&self) -> Vec<(u32, SubroutineStats)> {
+        let mut subroutines: Vec<_> = self.subroutines.iter().map(|(&pc, &stats)| (pc, stats)).collect();
+        subroutines.sort_by(|a, b| b.1.inclusive_cycles.cmp(&a.1.inclusive_cycles).then(a.0.cmp(&b.0)));
+        subroutines
+    }
+
+    /// Text report of the top `limit` hotspots and subroutines
+    pub fn report(&self, symbols: Option<&SymbolTable>, limit: usize) -> String {
+        let total = self.total.cycles.max(1);
+        let percent = |cycles: u64| cycles as f64 * 100.0 / total as f64;
+        let mut out = String::new();
+
+        let _ = writeln!(out, "Total cycles: {}", self.total.cycles);
+        let _ = writeln!(out, "WAI cycles:   {} ({:.2}%)", self.total.wait_cycles, percent(self.total.wait_cycles));
+        let _ = writeln!(out, "DMA cycles:   {} ({:.2}%)", self.total.dma_cycles, percent(self.total.dma_cycles));
+        if !self.frames.is_empty() {
+            let cycles: u64 = self.frames.iter().map(|f| f.cycles).sum();
+            let max = self.frames.iter().map(|f| f.cycles).max().unwrap_or(0);
+            let _ = writeln!(
+                out,
+                "Frames:       {} (avg {} cycles, max {})",
+                self.frames.len(),
+                cycles / self.frames.len() as u64,
+                max
+            );
+        }
+
+        let _ = writeln!(out, "\nHotspots:");
+        let _ = writeln!(out, "{:>12} {:>7} {:>10}  Address", "Cycles", "%", "Count");
+        for (pc, stats) in self.hotspots().into_iter().take(limit) {
+            let _ = writeln!(
+                out,
+                "{:>12} {:>6.2}% {:>10}  {}",
+                stats.cycles,
+                percent(stats.cycles),
+                stats.count,
+                format_addr(pc, symbols)
+            );
+        }
+
+        let _ = writeln!(out, "\nSubroutines:");
+        let _ = writeln!(out, "{:>12} {:>7} {:>12} {:>8}  Entry", "Inclusive", "%", "Exclusive", "Calls");
+        for (entry, stats) in self.subroutines().into_iter().take(limit) {
+            let _ = writeln!(
+                out,
+                "{:>12} {:>6.2}% {:>12} {:>8}  {}",
+                stats.inclusive_cycles,
+                percent(stats.inclusive_cycles),
+                stats.exclusive_cycles,
+                stats.calls,
+                format_addr(entry, symbols)
+            );
+        }
+        out
+    }
+
+    /// Collapsed stacks (`outer;inner cycles` per line) for flamegraph tools
+    pub fn collapsed_stacks(&self, symbols: Option<&SymbolTable>) -> String {
+        let mut lines: Vec<String> = self
+            .stacks
+            .iter()
+            .map(|(stack, cycles)| {
+                let names: Vec<String> = stack.iter().map(|&addr| format_addr(addr, symbols)).collect();
+                format!("{} {}", names.join(";"), cycles)
+            })
+            .collect();
+        lines.sort();
+        let mut out = lines.join("\n");
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out
+    }
+
+    fn enter(&mut self, entry: u32, s: Option<u16>) {
+        self.subroutines.entry(entry).or_default().calls += 1;
+        self.stack.push(entry);
+        self.stack_pointers.push(s);
+    }
+
+    /// Pop the subroutines whose return address is no longer on the stack
+    fn leave(&mut self, s: u16) {
+        while matches!(self.stack_pointers.last(), Some(Some(entry_s)) if s > *entry_s) {
+            self.stack.pop();
+            self.stack_pointers.pop();
+        }
+    }
+
+    /// Attribute cycles to the active call stack and the current frame
+    fn charge(&mut self, cycles: u64) {
+        self.frame.cycles += cycles;
+        self.total.cycles += cycles;
+
+        if let Some(&top) = self.stack.last() {
+            self.subroutines.entry(top).or_default().exclusive_cycles += cycles;
+        }
+        // Recursive frames count once toward inclusive time
+        for (i, &entry) in self.stack.iter().enumerate() {
+            if !self.stack[..i].contains(&entry) {
+                self.subroutines.entry(entry).or_default().inclusive_cycles += cycles;
+            }
+        }
+        match self.stacks.get_mut(self.stack.as_slice()) {
+            Some(total) => *total += cycles,
+            None => {
+                self.stacks.insert(self.stack.clone(), cycles);
+            }
+        }
+    }
+}
+
+/// Label for an address, or `$BBAAAA` when it has none
+fn format_addr(addr: u32, symbols: Option<&SymbolTable>) -> String {
+    symbols
+        .and_then(|s| s.label(addr))
+        .map_or_else(|| format!("${:06X}", addr), str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// main: JSR sub twice; sub: two 2-cycle instructions and RTS
+    fn create_profile() -> Profiler {
+        let mut profiler = Profiler::new();
+        for _ in 0..2 {
+            profiler.record_instruction(0x008000, 0x20, 0x008100, 0x01FB, 6); // JSR $8100
+            profiler.record_instruction(0x008100, 0xEA, 0x008101, 0x01FB, 2); // NOP
+            profiler.record_instruction(0x008101, 0xEA, 0x008102, 0x01FB, 2); // NOP
+            profiler.record_instruction(0x008102, 0x60, 0x008003, 0x01FD, 6); // RTS
+        }
+        profiler.end_frame();
+        profiler.record_wait(10);
+        profiler
+    }
+
+    #[test]
+    fn test_pc_and_subroutine_stats() {
+        let profiler = create_profile();
+        assert_eq!(profiler.pc_stats(0x008100), Some(&PcStats { cycles: 4, count: 2 }));
+        assert_eq!(profiler.hotspots()[0].0, 0x008000);
+
+        let main = profiler.subroutine_stats(0x008000).unwrap();
+        assert_eq!(main.inclusive_cycles, 32);
+        assert_eq!(main.exclusive_cycles, 12);
+        let sub = profiler.subroutine_stats(0x008100).unwrap();
+        assert_eq!(sub.calls, 2);
+        assert_eq!(sub.inclusive_cycles, 20);
+        assert_eq!(sub.exclusive_cycles, 20);
+    }
+
+    #[test]
+    fn test_frames_and_wait() {
+        let profiler = create_profile();
+        assert_eq!(profiler.frames(), &[FrameStats { cycles: 32, wait_cycles: 0, dma_cycles: 0 }]);
+        assert_eq!(profiler.total().cycles, 42);
+        assert_eq!(profiler.total().wait_cycles, 10);
+    }
+
+    #[test]
+    fn test_report_and_collapsed_stacks() {
+        let profiler = create_profile();
+        let mut symbols = SymbolTable::new();
+        symbols.insert(0x008100, "UpdatePlayer");
+
+        let collapsed = profiler.collapsed_stacks(Some(&symbols));
+        assert_eq!(collapsed, "$008000 12\n$008000;UpdatePlayer 20\n");
+
+        let report = profiler.report(Some(&symbols), 10);
+        assert!(report.contains("Total cycles: 42"));
+        assert!(report.contains("WAI cycles:   10"));
+        assert!(report.lines().any(|l| l.contains("UpdatePlayer") && l.trim_start().starts_with("20")));
+    }
+
+    #[test]
+    fn test_interrupt_frames() {
+        let mut profiler = Profiler::new();
+        profiler.record_instruction(0x008000, 0xEA, 0x008001, 0x1FFF, 2);
+        profiler.record_interrupt(0x009000, 0x1FFB, 8);
+        profiler.record_instruction(0x009000, 0x40, 0x008001, 0x1FFF, 7); // RTI
+        profiler.record_instruction(0x008001, 0xEA, 0x008002, 0x1FFF, 2);
+
+        assert_eq!(profiler.subroutine_stats(0x009000).unwrap().inclusive_cycles, 15);
+        assert_eq!(profiler.subroutine_stats(0x008000).unwrap().exclusive_cycles, 4);
+    }
+
+    #[test]
+    fn test_subroutine_left_without_return() {
+        let mut profiler = Profiler::new();
+        for _ in 0..100 {
+            profiler.record_instruction(0x008000, 0x20, 0x008100, 0x01FB, 6); // JSR $8100
+            profiler.record_instruction(0x008100, 0x68, 0x008101, 0x01FC, 4); // PLA
+            profiler.record_instruction(0x008101, 0x68, 0x008102, 0x01FD, 4); // PLA
+            profiler.record_instruction(0x008102, 0x4C, 0x008000, 0x01FD, 3); // JMP $8000
+        }
+        assert_eq!(profiler.stack, [0x008000]);
+        assert_eq!(profiler.collapsed_stacks(None), "$008000 1300\n$008000;$008100 400\n");
+
+        // Pushing a return address and using RTS as a jump stays in the caller
+        profiler.record_instruction(0x008000, 0x20, 0x008100, 0x01FB, 6); // JSR $8100
+        profiler.record_instruction(0x008100, 0x48, 0x008101, 0x01FA, 3); // PHA
+        profiler.record_instruction(0x008101, 0x48, 0x008102, 0x01F9, 3); // PHA
+        profiler.record_instruction(0x008102, 0x60, 0x008200, 0x01FB, 6); // RTS
+        assert_eq!(profiler.stack, [0x008000, 0x008100]);
+    }
+}
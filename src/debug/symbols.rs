@@ -0,0 +1,365 @@
+// Symbol Tables
+// Label files from WLA-DX/asar (.sym), ca65/ld65 (.dbg) and Mesen (.mlb)
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::cartridge::MappingMode;
+
+/// Bidirectional map between 24-bit CPU addresses and label names
+///
+/// An address may have several names (e.g. `Reset` and `Start`); all of
+/// them resolve, and the last one inserted is the label shown for it.
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    labels: BTreeMap<u32, String>,
+    addresses: HashMap<String, u32>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a label, making it the one shown for `addr`
+    ///
+    /// Other names at the same address stay resolvable. Redefining a name
+    /// at a new address moves it there.
+    pub fn insert(&mut self, addr: u32, name: &str) {
+        let addr = addr & 0xFFFFFF;
+        match self.addresses.insert(name.to_string(), addr) {
+            Some(old) if old != addr && self.labels.get(&old).is_some_and(|label| label == name) => {
+                // Show another name left at the old address, if any
+                let alias = self.addresses.iter().filter(|&(_, &a)| a == old).map(|(n, _)| n).min().cloned();
+                match alias {
+                    Some(alias) => self.labels.insert(old, alias),
+                    None => self.labels.remove(&old),
+                };
+            }
+            _ => {}
+        }
+        self.labels.insert(addr, name.to_string());
+    }
+
+    /// Label defined at `addr`, if any
+    pub fn label(&self, addr: u32) -> Option<&str> {
+        self.labels.get(&(addr & 0xFFFFFF)).map(String::as_str)
+    }
+
+    /// Address of the label `name`, if defined
+    pub fn address(&self, name: &str) -> Option<u32> {
+        self.addresses.get(name).copied()
+    }
+
+    /// Resolve a label name or a `$`-prefixed hex address
+    pub fn resolve(&self, text: &str) -> Result<u32, String> {
+        let text = text.trim();
+        if let Some(hex) = text.strip_prefix('$') {
+            return u32::from_str_radix(hex, 16)
+                .map(|addr| addr & 0xFFFFFF)
+                .map_err(|_| format!("Invalid address: {}", text));
+        }
+        self.address(text).ok_or_else(|| format!("Unknown label: {}", text))
+    }
+
+    /// Iterate over the label shown for each address, in address order
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.labels.iter().map(|(addr, name)| (*addr, name.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Add all labels from another table
+    ///
+    /// Every name is copied; the other table's shown labels win.
+    pub fn merge(&mut self, other: &SymbolTable) {
+        for (name, &addr) in &other.addresses {
+            self.insert(addr, name);
+        }
+        for (addr, name) in other.iter() {
+            self.insert(addr, name);
+        }
+    }
+
+    /// Parse a WLA-DX or asar `.sym` file
+    ///
+    /// Reads the `[labels]` section (or the whole file when it has no
+    /// sections), accepting both `BB:AAAA name` and `BBAAAAAA name` lines.
+    pub fn parse_sym(text: &str) -> Result<Self, String> {
+        let mut table = Self::new();
+        let mut in_labels = true;
+
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.split(';').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_labels = line.eq_ignore_ascii_case("[labels]");
+                continue;
+            }
+            if !in_labels {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let (Some(addr), Some(name)) = (parts.next(), parts.next()) else {
+                return Err(format!("Line {}: expected address and label", line_no + 1));
+            };
+            let addr = match addr.split_once(':') {
+                Some((bank, offset)) => {
+                    let bank = u32::from_str_radix(bank, 16);
+                    let offset = u32::from_str_radix(offset, 16);
+                    match (bank, offset) {
+                        (Ok(bank), Ok(offset)) => (bank << 16) | (offset & 0xFFFF),
+                        _ => return Err(format!("Line {}: invalid address {}", line_no + 1, addr)),
+                    }
+                }
+                None => u32::from_str_radix(addr, 16)
+                    .map_err(|_| format!("Line {}: invalid address {}", line_no + 1, addr))?,
+            };
+            table.insert(addr, name);
+        }
+
+        Ok(table)
+    }
+
+    /// Parse an ld65 debug info file (`--dbgfile`)
+    ///
+    /// Uses the `sym` records of type `lab`; 16-bit values take their bank
+    /// from the start address of the segment they belong to.
+    pub fn parse_dbg(text: &str) -> Result<Self, String> {
+        let mut segment_banks = HashMap::new();
+        let mut symbols = Vec::new();
+
+        for (line_no, line) in text.lines().enumerate() {
+            let Some((kind, fields)) = line.trim().split_once(char::is_whitespace) else {
+                continue;
+            };
+            if kind != "seg" && kind != "sym" {
+                continue;
+            }
+            let fields = parse_dbg_fields(fields);
+            let number = |key: &str| -> Result<Option<u32>, String> {
+                fields
+                    .get(key)
+                    .map(|v| parse_dbg_number(v).ok_or_else(|| format!("Line {}: invalid {} {}", line_no + 1, key, v)))
+                    .transpose()
+            };
+
+            if kind == "seg" {
+                if let (Some(id), Some(start)) = (number("id")?, number("start")?) {
+                    segment_banks.insert(id, start & 0xFF0000);
+                }
+            } else if fields.get("type").is_some_and(|t| *t == "lab") {
+                let (Some(name), Some(val)) = (fields.get("name"), number("val")?) else {
+                    return Err(format!("Line {}: label without name or value", line_no + 1));
+                };
+                symbols.push((name.trim_matches('"').to_string(), val, number("seg")?));
+            }
+        }
+
+        let mut table = Self::new();
+        for (name, val, segment) in symbols {
+            let bank = match segment {
+                Some(seg) if val <= 0xFFFF => segment_banks.get(&seg).copied().unwrap_or(0),
+                _ => 0,
+            };
+            table.insert(bank | val, &name);
+        }
+        Ok(table)
+    }
+
+    /// Parse a Mesen label file (`.mlb`)
+    ///
+    /// ROM, work RAM and save RAM offsets are converted to CPU addresses
+    /// using the cartridge's mapping mode. Unknown memory types are skipped.
+    pub fn parse_mlb(text: &str, mapping: MappingMode) -> Result<Self, String> {
+        let mut table = Self::new();
+
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut parts = line.splitn(4, ':');
+            let (Some(kind), Some(offset), Some(name)) = (parts.next(), parts.next(), parts.next()) else {
+                return Err(format!("Line {}: expected type:address:label", line_no + 1));
+            };
+            if name.is_empty() {
+                // Comment-only entry
+                continue;
+            }
+            let offset = offset.split('-').next().unwrap_or(offset);
+            let offset = u32::from_str_radix(offset, 16)
+                .map_err(|_| format!("Line {}: invalid address {}", line_no + 1, offset))?;
+
+            let addr = match kind {
+                "SnesPrgRom" | "PRG" => rom_offset_to_address(offset, mapping),
+                "SnesWorkRam" | "WORK" => 0x7E0000 + (offset & 0x1FFFF),
+                "SnesSaveRam" | "SAVE" => sram_offset_to_address(offset, mapping),
+                "SnesRegister" | "REG" => offset,
+                _ => continue,
+            };
+            table.insert(addr, name);
+        }
+
+        Ok(table)
+    }
+
+    /// Parse a symbol file, choosing the format from its file extension
+    pub fn parse_file(path: &str, text: &str, mapping: MappingMode) -> Result<Self, String> {
+        let extension = path.rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+        match extension.as_str() {
+            "sym" => Self::parse_sym(text),
+            "dbg" => Self::parse_dbg(text),
+            "mlb" => Self::parse_mlb(text, mapping),
+            _ => Err(format!("Unsupported symbol file: {}", path)),
+        }
+    }
+}
+
+/// Split `key=value,key="quoted, value"` fields of an ld65 debug record
+fn parse_dbg_fields(text: &str) -> HashMap<&str, &str> {
+    let mut fields = HashMap::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let Some((key, value)) = rest.split_once('=') else {
+            break;
+        };
+        let end = if let Some(quoted) = value.strip_prefix('"') {
+            quoted.find('"').map_or(value.len(), |i| i + 2)
+        } else {
+            value.find(',').unwrap_or(value.len())
+        };
+        fields.insert(key.trim(), &value[..end]);
+        rest = value[end..].trim_start_matches(',');
+    }
+    fields
+}
+
+fn parse_dbg_number(text: &str) -> Option<u32> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
+
+/// Convert a ROM file offset to its primary CPU address
+pub(crate) fn rom_offset_to_address(offset: u32, mapping: MappingMode) -> u32 {
+    match mapping {
+        MappingMode::LoRom => ((offset >> 15) << 16) | 0x8000 | (offset & 0x7FFF),
+        MappingMode::HiRom => 0xC00000 | (offset & 0x3FFFFF),
+        MappingMode::ExHiRom if offset < 0x400000 => 0xC00000 | offset,
+        MappingMode::ExHiRom => 0x400000 | (offset & 0x3FFFFF),
+    }
+}
+
+/// Convert an SRAM offset to its primary CPU address
+fn sram_offset_to_address(offset: u32, mapping: MappingMode) -> u32 {
+    match mapping {
+        MappingMode::LoRom => 0x700000 | ((offset >> 15) << 16) | (offset & 0x7FFF),
+        MappingMode::HiRom | MappingMode::ExHiRom => 0x206000 | ((offset >> 13) << 16) | (offset & 0x1FFF),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_aliases() {
+        let mut table = SymbolTable::new();
+        table.insert(0x1000, "A");
+        table.insert(0x2000, "A");
+        // A moved, so its old address has no label left
+        assert_eq!(table.label(0x1000), None);
+        assert_eq!(table.label(0x2000), Some("A"));
+
+        table.insert(0x1000, "B");
+        table.insert(0x1000, "C");
+        assert_eq!(table.address("A"), Some(0x2000));
+        assert_eq!(table.address("B"), Some(0x1000));
+        assert_eq!(table.label(0x1000), Some("C"));
+
+        // Moving the shown name falls back to a remaining alias
+        table.insert(0x3000, "C");
+        assert_eq!(table.label(0x1000), Some("B"));
+
+        let mut merged = SymbolTable::new();
+        merged.merge(&table);
+        assert_eq!(merged.address("B"), Some(0x1000));
+        assert_eq!(merged.label(0x3000), Some("C"));
+    }
+
+    #[test]
+    fn test_parse_wla_sym() {
+        let text = "; wla symbolic information file\n\
+                    [labels]\n\
+                    00:8000 Reset\n\
+                    01:9234 UpdatePlayer ; comment\n\
+                    \n\
+                    [definitions]\n\
+                    00000010 SOME_CONSTANT\n";
+        let table = SymbolTable::parse_sym(text).unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.label(0x008000), Some("Reset"));
+        assert_eq!(table.address("UpdatePlayer"), Some(0x019234));
+        assert_eq!(table.address("SOME_CONSTANT"), None);
+
+        // asar's sectionless flat format
+        let table = SymbolTable::parse_sym("00808000 main\n").unwrap();
+        assert_eq!(table.label(0x808000), Some("main"));
+
+        assert!(SymbolTable::parse_sym("[labels]\nzz:8000 bad\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_ca65_dbg() {
+        let text = "version\tmajor=2,minor=0\n\
+                    seg\tid=0,name=\"CODE\",start=0x808000,size=0x0100,addrsize=absolute,type=ro\n\
+                    sym\tid=0,name=\"Reset\",addrsize=absolute,scope=0,def=1,val=0x8000,seg=0,type=lab\n\
+                    sym\tid=1,name=\"FarProc\",addrsize=far,scope=0,def=2,val=0xC12345,type=lab\n\
+                    sym\tid=2,name=\"CONST\",addrsize=zeropage,scope=0,def=3,val=0x10,type=equ\n";
+        let table = SymbolTable::parse_dbg(text).unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.address("Reset"), Some(0x808000));
+        assert_eq!(table.address("FarProc"), Some(0xC12345));
+    }
+
+    #[test]
+    fn test_parse_mesen_mlb() {
+        let text = "SnesPrgRom:8010:NmiHandler:Main NMI\n\
+                    SnesWorkRam:0100:PlayerX\n\
+                    SnesRegister:2100:INIDISP\n\
+                    SnesPrgRom:0000::comment only\n\
+                    SpcRam:0200:SpcLabel\n";
+        let table = SymbolTable::parse_mlb(text, MappingMode::LoRom).unwrap();
+        assert_eq!(table.address("NmiHandler"), Some(0x018010));
+        assert_eq!(table.address("PlayerX"), Some(0x7E0100));
+        assert_eq!(table.address("INIDISP"), Some(0x002100));
+        assert_eq!(table.address("SpcLabel"), None);
+
+        let table = SymbolTable::parse_mlb("SnesPrgRom:8010:Hi\n", MappingMode::HiRom).unwrap();
+        assert_eq!(table.address("Hi"), Some(0xC08010));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let mut table = SymbolTable::new();
+        table.insert(0x008000, "Reset");
+        assert_eq!(table.resolve("Reset"), Ok(0x008000));
+        assert_eq!(table.resolve("$7E0010"), Ok(0x7E0010));
+        assert!(table.resolve("Missing").is_err());
+
+        // Both names of an address resolve; the newer one is shown
+        table.insert(0x008000, "Start");
+        assert_eq!(table.resolve("Reset"), Ok(0x008000));
+        assert_eq!(table.label(0x008000), Some("Start"));
+    }
+}
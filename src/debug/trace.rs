@@ -0,0 +1,258 @@
+// Instruction Trace Logging
+// Per-instruction CPU state in Mesen- or bsnes-compatible text formats
+
+use std::io;
+
+use crate::debug::symbols::SymbolTable;
+use crate::disasm::Instruction;
+
+/// CPU state captured just before an instruction executes
+#[derive(Copy, Clone, Debug)]
+pub struct TraceEntry {
+    /// 24-bit address of the instruction
+    pub pc: u32,
+    pub instruction: Instruction,
+    /// Resolved operand address, if the instruction accesses memory
+    pub effective_addr: Option<u32>,
+    pub a: u16,
+    pub x: u16,
+    pub y: u16,
+    pub s: u16,
+    pub d: u16,
+    pub dbr: u8,
+    /// Status register as pushed by PHP
+    pub p: u8,
+    /// Emulation mode flag
+    pub e: bool,
+    /// Scanline (V counter)
+    pub v: u16,
+    /// Dot within the scanline (H counter)
+    pub h: u16,
+    pub master_cycles: u64,
+}
+
+/// Receiver for instruction trace entries
+pub trait TraceSink {
+    /// Called once per instruction, before it executes
+    fn trace(&mut self, entry: &TraceEntry);
+}
+
+/// Line format used by `TraceLogger`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// Mesen default SNES trace layout with opcode bytes (uppercase hex, `P:nvMXdIzc`)
+    Mesen,
+    /// bsnes trace layout with opcode bytes (lowercase, effective address in brackets)
+    Bsnes,
+}
+
+/// Format the status register as `nvmxdizc`, uppercase for set bits
+fn format_flags(p: u8) -> String {
+    "NVMXDIZC"
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if p & (0x80 >> i) != 0 {
+                c
+            } else {
+                c.to_ascii_lowercase()
+            }
+        })
+        .collect()
+}
+
+/// Format instruction bytes as space-separated uppercase hex
+fn format_bytes(instruction: &Instruction) -> String {
+    instruction
+        .bytes()
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl TraceFormat {
+    /// Render one trace entry as a line (without newline)
+    pub fn format(&self, entry: &TraceEntry) -> String {
+        self.format_with_symbols(entry, None)
+    }
+
+    /// Render one trace entry, printing labelled operands by name
+    pub fn format_with_symbols(&self, entry: &TraceEntry, symbols: Option<&SymbolTable>) -> String {
+        let disasm = match symbols {
+            Some(symbols) => entry.instruction.format_with_symbols(symbols),
+            None => entry.instruction.to_string(),
+        };
+        let bytes = format_bytes(&entry.instruction);
+        match self {
+            TraceFormat::Mesen => format!(
+                "{:06X}  {:<11} {:<28} A:{:04X} X:{:04X} Y:{:04X} S:{:04X} D:{:04X} DB:{:02X} P:{}{} V:{:<3} H:{:<3} MC:{}",
+                entry.pc,
+                bytes,
+                disasm,
+                entry.a,
+                entry.x,
+                entry.y,
+                entry.s,
+                entry.d,
+                entry.dbr,
+                format_flags(entry.p),
+                if entry.e { " E" } else { "" },
+                entry.v,
+                entry.h,
+                entry.master_cycles,
+            ),
+            TraceFormat::Bsnes => {
+                // bsnes prints lowercase mnemonics; labels keep their case
+                let mut disasm = match symbols {
+                    Some(_) if disasm != entry.instruction.to_string() => {
+                        let (mnemonic, operand) = disasm.split_once(' ').unwrap_or((&disasm, ""));
+                        format!("{} {}", mnemonic.to_ascii_lowercase(), operand)
+                    }
+                    _ => disasm.to_ascii_lowercase(),
+                };
+                if let Some(addr) = entry.effective_addr {
+                    disasm.push_str(&format!(" [{:06x}]", addr));
+                }
+                format!(
+                    "{:06x} {:<11} {:<22} A:{:04x} X:{:04x} Y:{:04x} S:{:04x} D:{:04x} B:{:02x} {}{} V:{:>3} H:{:>4}",
+                    entry.pc,
+                    bytes.to_ascii_lowercase(),
+                    disasm,
+                    entry.a,
+                    entry.x,
+                    entry.y,
+                    entry.s,
+                    entry.d,
+                    entry.dbr,
+                    if entry.e { 'E' } else { 'e' },
+                    format_flags(entry.p),
+                    entry.v,
+                    entry.h,
+                )
+            }
+        }
+    }
+}
+
+/// Trace sink writing formatted lines to any `io::Write`
+pub struct TraceLogger<W: io::Write> {
+    writer: W,
+    format: TraceFormat,
+    /// Inclusive PC ranges to log; empty logs everything
+    ranges: Vec<(u32, u32)>,
+    symbols: Option<SymbolTable>,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> TraceLogger<W> {
+    pub fn new(writer: W, format: TraceFormat) -> Self {
+        Self {
+            writer,
+            format,
+            ranges: Vec::new(),
+            symbols: None,
+            error: None,
+        }
+    }
+
+    /// Only log instructions with a PC in `start..=end` (may be called repeatedly)
+    pub fn with_range(mut self, start: u32, end: u32) -> Self {
+        self.ranges.push((start.min(end), start.max(end)));
+        self
+    }
+
+    /// Print operands that have a label by name
+    pub fn with_symbols(mut self, symbols: SymbolTable) -> Self {
+        self.symbols = Some(symbols);
+        self
+    }
+
+    /// Check whether an instruction at `pc` passes the range filter
+    pub fn accepts(&self, pc: u32) -> bool {
+        self.ranges.is_empty() || self.ranges.iter().any(|&(start, end)| pc >= start && pc <= end)
+    }
+
+    /// First write error encountered, if any (logging stops after an error)
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: io::Write> TraceSink for TraceLogger<W> {
+    fn trace(&mut self, entry: &TraceEntry) {
+        if self.error.is_some() || !self.accepts(entry.pc) {
+            return;
+        }
+        let line = self.format.format_with_symbols(entry, self.symbols.as_ref());
+        if let Err(e) = writeln!(self.writer, "{}", line) {
+            self.error = Some(e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disasm;
+
+    fn create_entry(pc: u32, code: &[u8]) -> TraceEntry {
+        let instruction = disasm::decode(pc, true, true, |a| code[(a - pc) as usize]);
+        TraceEntry {
+            pc,
+            instruction,
+            effective_addr: Some(0x000010),
+            a: 0x0012,
+            x: 0x0034,
+            y: 0x0056,
+            s: 0x01FF,
+            d: 0x0000,
+            dbr: 0x00,
+            p: 0x34,
+            e: true,
+            v: 5,
+            h: 120,
+            master_cycles: 1234,
+        }
+    }
+
+    #[test]
+    fn test_mesen_format() {
+        let line = TraceFormat::Mesen.format(&create_entry(0x008002, &[0x8D, 0x10, 0x00]));
+        assert!(line.starts_with("008002  8D 10 00    STA $0010 "));
+        assert!(line.contains("A:0012 X:0034 Y:0056 S:01FF D:0000 DB:00 P:nvMXdIzc E V:5   H:120 MC:1234"));
+    }
+
+    #[test]
+    fn test_bsnes_format() {
+        let line = TraceFormat::Bsnes.format(&create_entry(0x008002, &[0x8D, 0x10, 0x00]));
+        assert!(line.starts_with("008002 8d 10 00    sta $0010 [000010]"));
+        assert!(line.ends_with("A:0012 X:0034 Y:0056 S:01ff D:0000 B:00 EnvMXdIzc V:  5 H: 120"));
+    }
+
+    #[test]
+    fn test_symbols() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert(0x000010, "PlayerX");
+        let entry = create_entry(0x008002, &[0x8D, 0x10, 0x00]);
+
+        let line = TraceFormat::Mesen.format_with_symbols(&entry, Some(&symbols));
+        assert!(line.starts_with("008002  8D 10 00    STA PlayerX "));
+        let line = TraceFormat::Bsnes.format_with_symbols(&entry, Some(&symbols));
+        assert!(line.starts_with("008002 8d 10 00    sta PlayerX [000010]"));
+    }
+
+    #[test]
+    fn test_range_filter() {
+        let mut logger = TraceLogger::new(Vec::new(), TraceFormat::Mesen).with_range(0x008000, 0x008001);
+        logger.trace(&create_entry(0x008000, &[0xEA]));
+        logger.trace(&create_entry(0x008002, &[0xEA]));
+        let output = String::from_utf8(logger.into_inner()).unwrap();
+        assert_eq!(output.lines().count(), 1);
+        assert!(output.starts_with("008000"));
+    }
+}
@@ -0,0 +1,507 @@
+//! 65816 Disassembler
+//!
+//! Decodes opcodes into mnemonics and operands, taking the M/X register
+//! widths into account. Output uses the common WDC/ca65 operand syntax
+//! (`LDA $1234,X`, `LDA [$12],Y`, `MVN $01,$02`, ...).
+
+use std::fmt;
+
+use crate::cpu::Cpu65816;
+use crate::debug::symbols::SymbolTable;
+
+/// 65816 addressing modes
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AddrMode {
+    Implied,
+    Accumulator,
+    /// 8-bit immediate regardless of flags (REP/SEP/BRK/COP/WDM)
+    Immediate8,
+    /// Immediate sized by the M flag
+    ImmediateM,
+    /// Immediate sized by the X flag
+    ImmediateX,
+    /// 16-bit immediate (PEA)
+    Immediate16,
+    Direct,
+    DirectX,
+    DirectY,
+    /// (dp)
+    DirectIndirect,
+    /// [dp]
+    DirectIndirectLong,
+    /// (dp,X)
+    DirectIndexedIndirect,
+    /// (dp),Y
+    DirectIndirectIndexed,
+    /// [dp],Y
+    DirectIndirectLongIndexed,
+    Absolute,
+    AbsoluteX,
+    AbsoluteY,
+    AbsoluteLong,
+    AbsoluteLongX,
+    /// (abs) - JMP
+    AbsoluteIndirect,
+    /// (abs,X) - JMP/JSR
+    AbsoluteIndexedIndirect,
+    /// [abs] - JML
+    AbsoluteIndirectLong,
+    /// sr,S
+    StackRelative,
+    /// (sr,S),Y
+    StackRelativeIndirectIndexed,
+    /// 8-bit branch displacement
+    Relative,
+    /// 16-bit branch displacement (BRL/PER)
+    RelativeLong,
+    /// MVN/MVP source and destination banks
+    BlockMove,
+}
+
+use AddrMode::*;
+
+/// Mnemonic and addressing mode for every opcode
+const OPCODES: [(&str, AddrMode); 256] = [
+    // $00-$0F
+    ("BRK", Immediate8), ("ORA", DirectIndexedIndirect), ("COP", Immediate8), ("ORA", StackRelative),
+    ("TSB", Direct), ("ORA", Direct), ("ASL", Direct), ("ORA", DirectIndirectLong),
+    ("PHP", Implied), ("ORA", ImmediateM), ("ASL", Accumulator), ("PHD", Implied),
+    ("TSB", Absolute), ("ORA", Absolute), ("ASL", Absolute), ("ORA", AbsoluteLong),
+    // $10-$1F
+    ("BPL", Relative), ("ORA", DirectIndirectIndexed), ("ORA", DirectIndirect), ("ORA", StackRelativeIndirectIndexed),
+    ("TRB", Direct), ("ORA", DirectX), ("ASL", DirectX), ("ORA", DirectIndirectLongIndexed),
+    ("CLC", Implied), ("ORA", AbsoluteY), ("INC", Accumulator), ("TCS", Implied),
+    ("TRB", Absolute), ("ORA", AbsoluteX), ("ASL", AbsoluteX), ("ORA", AbsoluteLongX),
+    // $20-$2F
+    ("JSR", Absolute), ("AND", DirectIndexedIndirect), ("JSL", AbsoluteLong), ("AND", StackRelative),
+    ("BIT", Direct), ("AND", Direct), ("ROL", Direct), ("AND", DirectIndirectLong),
+    ("PLP", Implied), ("AND", ImmediateM), ("ROL", Accumulator), ("PLD", Implied),
+    ("BIT", Absolute), ("AND", Absolute), ("ROL", Absolute), ("AND", AbsoluteLong),
+    // $30-$3F
+    ("BMI", Relative), ("AND", DirectIndirectIndexed), ("AND", DirectIndirect), ("AND", StackRelativeIndirectIndexed),
+    ("BIT", DirectX), ("AND", DirectX), ("ROL", DirectX), ("AND", DirectIndirectLongIndexed),
+    ("SEC", Implied), ("AND", AbsoluteY), ("DEC", Accumulator), ("TSC", Implied),
+    ("BIT", AbsoluteX), ("AND", AbsoluteX), ("ROL", AbsoluteX), ("AND", AbsoluteLongX),
+    // $40-$4F
+    ("RTI", Implied), ("EOR", DirectIndexedIndirect), ("WDM", Immediate8), ("EOR", StackRelative),
+    ("MVP", BlockMove), ("EOR", Direct), ("LSR", Direct), ("EOR", DirectIndirectLong),
+    ("PHA", Implied), ("EOR", ImmediateM), ("LSR", Accumulator), ("PHK", Implied),
+    ("JMP", Absolute), ("EOR", Absolute), ("LSR", Absolute), ("EOR", AbsoluteLong),
+    // $50-$5F
+    ("BVC", Relative), ("EOR", DirectIndirectIndexed), ("EOR", DirectIndirect), ("EOR", StackRelativeIndirectIndexed),
+    ("MVN", BlockMove), ("EOR", DirectX), ("LSR", DirectX), ("EOR", DirectIndirectLongIndexed),
+    ("CLI", Implied), ("EOR", AbsoluteY), ("PHY", Implied), ("TCD", Implied),
+    ("JML", AbsoluteLong), ("EOR", AbsoluteX), ("LSR", AbsoluteX), ("EOR", AbsoluteLongX),
+    // $60-$6F
+    ("RTS", Implied), ("ADC", DirectIndexedIndirect), ("PER", RelativeLong), ("ADC", StackRelative),
+    ("STZ", Direct), ("ADC", Direct), ("ROR", Direct), ("ADC", DirectIndirectLong),
+    ("PLA", Implied), ("ADC", ImmediateM), ("ROR", Accumulator), ("RTL", Implied),
+    ("JMP", AbsoluteIndirect), ("ADC", Absolute), ("ROR", Absolute), ("ADC", AbsoluteLong),
+    // $70-$7F
+    ("BVS", Relative), ("ADC", DirectIndirectIndexed), ("ADC", DirectIndirect), ("ADC", StackRelativeIndirectIndexed),
+    ("STZ", DirectX), ("ADC", DirectX), ("ROR", DirectX), ("ADC", DirectIndirectLongIndexed),
+    ("SEI", Implied), ("ADC", AbsoluteY), ("PLY", Implied), ("TDC", Implied),
+    ("JMP", AbsoluteIndexedIndirect), ("ADC", AbsoluteX), ("ROR", AbsoluteX), ("ADC", AbsoluteLongX),
+    // $80-$8F
+    ("BRA", Relative), ("STA", DirectIndexedIndirect), ("BRL", RelativeLong), ("STA", StackRelative),
+    ("STY", Direct), ("STA", Direct), ("STX", Direct), ("STA", DirectIndirectLong),
+    ("DEY", Implied), ("BIT", ImmediateM), ("TXA", Implied), ("PHB", Implied),
+    ("STY", Absolute), ("STA", Absolute), ("STX", Absolute), ("STA", AbsoluteLong),
+    // $90-$9F
+    ("BCC", Relative), ("STA", DirectIndirectIndexed), ("STA", DirectIndirect), ("STA", StackRelativeIndirectIndexed),
+    ("STY", DirectX), ("STA", DirectX), ("STX", DirectY), ("STA", DirectIndirectLongIndexed),
+    ("TYA", Implied), ("STA", AbsoluteY), ("TXS", Implied), ("TXY", Implied),
+    ("STZ", Absolute), ("STA", AbsoluteX), ("STZ", AbsoluteX), ("STA", AbsoluteLongX),
+    // $A0-$AF
+    ("LDY", ImmediateX), ("LDA", DirectIndexedIndirect), ("LDX", ImmediateX), ("LDA", StackRelative),
+    ("LDY", Direct), ("LDA", Direct), ("LDX", Direct), ("LDA", DirectIndirectLong),
+    ("TAY", Implied), ("LDA", ImmediateM), ("TAX", Implied), ("PLB", Implied),
+    ("LDY", Absolute), ("LDA", Absolute), ("LDX", Absolute), ("LDA", AbsoluteLong),
+    // $B0-$BF
+    ("BCS", Relative), ("LDA", DirectIndirectIndexed), ("LDA", DirectIndirect), ("LDA", StackRelativeIndirectIndexed),
+    ("LDY", DirectX), ("LDA", DirectX), ("LDX", DirectY), ("LDA", DirectIndirectLongIndexed),
+    ("CLV", Implied), ("LDA", AbsoluteY), ("TSX", Implied), ("TYX", Implied),
+    ("LDY", AbsoluteX), ("LDA", AbsoluteX), ("LDX", AbsoluteY), ("LDA", AbsoluteLongX),
+    // $C0-$CF
+    ("CPY", ImmediateX), ("CMP", DirectIndexedIndirect), ("REP", Immediate8), ("CMP", StackRelative),
+    ("CPY", Direct), ("CMP", Direct), ("DEC", Direct), ("CMP", DirectIndirectLong),
+    ("INY", Implied), ("CMP", ImmediateM), ("DEX", Implied), ("WAI", Implied),
+    ("CPY", Absolute), ("CMP", Absolute), ("DEC", Absolute), ("CMP", AbsoluteLong),
+    // $D0-$DF
+    ("BNE", Relative), ("CMP", DirectIndirectIndexed), ("CMP", DirectIndirect), ("CMP", StackRelativeIndirectIndexed),
+    ("PEI", DirectIndirect), ("CMP", DirectX), ("DEC", DirectX), ("CMP", DirectIndirectLongIndexed),
+    ("CLD", Implied), ("CMP", AbsoluteY), ("PHX", Implied), ("STP", Implied),
+    ("JML", AbsoluteIndirectLong), ("CMP", AbsoluteX), ("DEC", AbsoluteX), ("CMP", AbsoluteLongX),
+    // $E0-$EF
+    ("CPX", ImmediateX), ("SBC", DirectIndexedIndirect), ("SEP", Immediate8), ("SBC", StackRelative),
+    ("CPX", Direct), ("SBC", Direct), ("INC", Direct), ("SBC", DirectIndirectLong),
+    ("INX", Implied), ("SBC", ImmediateM), ("NOP", Implied), ("XBA", Implied),
+    ("CPX", Absolute), ("SBC", Absolute), ("INC", Absolute), ("SBC", AbsoluteLong),
+    // $F0-$FF
+    ("BEQ", Relative), ("SBC", DirectIndirectIndexed), ("SBC", DirectIndirect), ("SBC", StackRelativeIndirectIndexed),
+    ("PEA", Immediate16), ("SBC", DirectX), ("INC", DirectX), ("SBC", DirectIndirectLongIndexed),
+    ("SED", Implied), ("SBC", AbsoluteY), ("PLX", Implied), ("XCE", Implied),
+    ("JSR", AbsoluteIndexedIndirect), ("SBC", AbsoluteX), ("INC", AbsoluteX), ("SBC", AbsoluteLongX),
+];
+
+/// Look up the mnemonic and addressing mode of an opcode
+pub const fn opcode_info(opcode: u8) -> (&'static str, AddrMode) {
+    OPCODES[opcode as usize]
+}
+
+/// Find the opcode for a mnemonic/addressing mode pair (used by the assembler)
+pub fn find_opcode(mnemonic: &str, mode: AddrMode) -> Option<u8> {
+    OPCODES
+        .iter()
+        .position(|&(m, md)| md == mode && m.eq_ignore_ascii_case(mnemonic))
+        .map(|i| i as u8)
+}
+
+/// Instruction length in bytes, including the opcode
+///
+/// # Arguments
+/// * `m8` - Accumulator is 8-bit (M flag set or emulation mode)
+/// * `x8` - Index registers are 8-bit (X flag set or emulation mode)
+pub fn instruction_length(mode: AddrMode, m8: bool, x8: bool) -> u8 {
+    match mode {
+        Implied | Accumulator => 1,
+        ImmediateM => if m8 { 2 } else { 3 },
+        ImmediateX => if x8 { 2 } else { 3 },
+        Immediate8 | Direct | DirectX | DirectY | DirectIndirect | DirectIndirectLong
+        | DirectIndexedIndirect | DirectIndirectIndexed | DirectIndirectLongIndexed
+        | StackRelative | StackRelativeIndirectIndexed | Relative => 2,
+        Immediate16 | Absolute | AbsoluteX | AbsoluteY | AbsoluteIndirect
+        | AbsoluteIndexedIndirect | AbsoluteIndirectLong | RelativeLong | BlockMove => 3,
+        AbsoluteLong | AbsoluteLongX => 4,
+    }
+}
+
+/// A decoded instruction
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Instruction {
+    /// 24-bit address of the opcode
+    pub addr: u32,
+    pub opcode: u8,
+    pub mnemonic: &'static str,
+    pub mode: AddrMode,
+    /// Raw bytes; only the first `len` are valid
+    pub bytes: [u8; 4],
+    pub len: u8,
+}
+
+impl Instruction {
+    /// Raw instruction bytes
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+
+    /// Operand value (little-endian, without the opcode)
+    pub fn operand(&self) -> u32 {
+        self.bytes[1..self.len as usize]
+            .iter()
+            .rev()
+            .fold(0, |acc, &b| (acc << 8) | b as u32)
+    }
+
+    /// Destination of a relative branch, BRL or PER
+    pub fn branch_target(&self) -> Option<u32> {
+        let next = (self.addr as u16).wrapping_add(self.len as u16);
+        let target = match self.mode {
+            Relative => next.wrapping_add(self.bytes[1] as i8 as u16),
+            RelativeLong => next.wrapping_add(self.operand() as u16),
+            _ => return None,
+        };
+        Some((self.addr & 0xFF0000) | target as u32)
+    }
+
+    /// Whether the instruction calls a subroutine (JSR/JSL)
+    pub fn is_call(&self) -> bool {
+        matches!(self.opcode, 0x20 | 0x22 | 0xFC)
+    }
+
+    /// Whether the instruction returns from a subroutine or interrupt
+    pub fn is_return(&self) -> bool {
+        matches!(self.opcode, 0x40 | 0x60 | 0x6B)
+    }
+
+    /// Resolve the 24-bit address the instruction operates on
+    ///
+    /// Indirect modes read their pointer through `read`, so callers should
+    /// pass a side-effect free accessor. Returns None for modes without a
+    /// memory operand (implied, immediate, block move).
+    pub fn effective_address<F: FnMut(u32) -> u8>(&self, cpu: &Cpu65816, mut read: F) -> Option<u32> {
+        let operand = self.operand();
+        let dbr = (cpu.dbr as u32) << 16;
+        let pbr = self.addr & 0xFF0000;
+        let x = cpu.x as u32;
+        let y = cpu.y as u32;
+        let direct = |offset: u32| (cpu.d as u32).wrapping_add(offset) & 0xFFFF;
+        let addr = match self.mode {
+            Implied | Accumulator | Immediate8 | ImmediateM | ImmediateX | Immediate16 | BlockMove => {
+                return None;
+            }
+            Direct => direct(operand),
+            DirectX => direct(operand + x),
+            DirectY => direct(operand + y),
+            StackRelative => (cpu.s as u32 + operand) & 0xFFFF,
+            DirectIndirect => dbr | read_word(&mut read, direct(operand)),
+            DirectIndexedIndirect => dbr | read_word(&mut read, direct(operand + x)),
+            DirectIndirectIndexed => (dbr | read_word(&mut read, direct(operand))).wrapping_add(y),
+            DirectIndirectLong | DirectIndirectLongIndexed => {
+                let ptr = direct(operand);
+                let long = read_word(&mut read, ptr) | ((read((ptr + 2) & 0xFFFF) as u32) << 16);
+                if self.mode == DirectIndirectLongIndexed {
+                    long.wrapping_add(y)
+                } else {
+                    long
+                }
+            }
+            StackRelativeIndirectIndexed => {
+                let ptr = (cpu.s as u32 + operand) & 0xFFFF;
+                (dbr | read_word(&mut read, ptr)).wrapping_add(y)
+            }
+            // JMP/JSR absolute stay in the program bank
+            Absolute if matches!(self.opcode, 0x20 | 0x4C) => pbr | operand,
+            Absolute => dbr | operand,
+            AbsoluteX => (dbr | operand).wrapping_add(x),
+            AbsoluteY => (dbr | operand).wrapping_add(y),
+            AbsoluteLong => operand,
+            AbsoluteLongX => operand.wrapping_add(x),
+            AbsoluteIndirect => pbr | read_word(&mut read, operand),
+            AbsoluteIndexedIndirect => pbr | read_word(&mut read, pbr | ((operand + x) & 0xFFFF)),
+            AbsoluteIndirectLong => read_word(&mut read, operand) | ((read((operand + 2) & 0xFFFF) as u32) << 16),
+            Relative | RelativeLong => return self.branch_target(),
+        };
+        Some(addr & 0xFFFFFF)
+    }
+}
+
+impl Instruction {
+    /// Format like `Display`, replacing address operands that have a label
+    ///
+    /// 16-bit operands are looked up in the instruction's bank, then bank
+    /// $00, then (below $2000) the WRAM bank $7E they mirror.
+    pub fn format_with_symbols(&self, symbols: &SymbolTable) -> String {
+        let text = self.to_string();
+        let op = self.operand();
+        let (hex, candidates) = match self.mode {
+            Relative | RelativeLong => {
+                let target = self.branch_target().unwrap_or(0);
+                (format!("${:04X}", target & 0xFFFF), vec![target])
+            }
+            AbsoluteLong | AbsoluteLongX => (format!("${:06X}", op), vec![op]),
+            Absolute | AbsoluteX | AbsoluteY | AbsoluteIndirect | AbsoluteIndexedIndirect
+            | AbsoluteIndirectLong => {
+                let mut candidates = vec![(self.addr & 0xFF0000) | op, op];
+                if op < 0x2000 {
+                    candidates.push(0x7E0000 | op);
+                }
+                (format!("${:04X}", op), candidates)
+            }
+            _ => return text,
+        };
+
+        match candidates.into_iter().find_map(|addr| symbols.label(addr)) {
+            Some(label) => text.replacen(&hex, label, 1),
+            None => text,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = self.operand();
+        let imm = |f: &mut fmt::Formatter<'_>| {
+            if self.len == 3 {
+                write!(f, "{} #${:04X}", self.mnemonic, op)
+            } else {
+                write!(f, "{} #${:02X}", self.mnemonic, op)
+            }
+        };
+        match self.mode {
+            Implied => write!(f, "{}", self.mnemonic),
+            Accumulator => write!(f, "{} A", self.mnemonic),
+            Immediate8 | ImmediateM | ImmediateX => imm(f),
+            Immediate16 => write!(f, "{} ${:04X}", self.mnemonic, op),
+            Direct => write!(f, "{} ${:02X}", self.mnemonic, op),
+            DirectX => write!(f, "{} ${:02X},X", self.mnemonic, op),
+            DirectY => write!(f, "{} ${:02X},Y", self.mnemonic, op),
+            DirectIndirect => write!(f, "{} (${:02X})", self.mnemonic, op),
+            DirectIndirectLong => write!(f, "{} [${:02X}]", self.mnemonic, op),
+            DirectIndexedIndirect => write!(f, "{} (${:02X},X)", self.mnemonic, op),
+            DirectIndirectIndexed => write!(f, "{} (${:02X}),Y", self.mnemonic, op),
+            DirectIndirectLongIndexed => write!(f, "{} [${:02X}],Y", self.mnemonic, op),
+            Absolute => write!(f, "{} ${:04X}", self.mnemonic, op),
+            AbsoluteX => write!(f, "{} ${:04X},X", self.mnemonic, op),
+            AbsoluteY => write!(f, "{} ${:04X},Y", self.mnemonic, op),
+            AbsoluteLong => write!(f, "{} ${:06X}", self.mnemonic, op),
+            AbsoluteLongX => write!(f, "{} ${:06X},X", self.mnemonic, op),
+            AbsoluteIndirect => write!(f, "{} (${:04X})", self.mnemonic, op),
+            AbsoluteIndexedIndirect => write!(f, "{} (${:04X},X)", self.mnemonic, op),
+            AbsoluteIndirectLong => write!(f, "{} [${:04X}]", self.mnemonic, op),
+            StackRelative => write!(f, "{} ${:02X},S", self.mnemonic, op),
+            StackRelativeIndirectIndexed => write!(f, "{} (${:02X},S),Y", self.mnemonic, op),
+            Relative | RelativeLong => {
+                let target = self.branch_target().unwrap_or(0);
+                write!(f, "{} ${:04X}", self.mnemonic, target & 0xFFFF)
+            }
+            // Operand bytes are destination, source; syntax is source, destination
+            BlockMove => write!(f, "{} ${:02X},${:02X}", self.mnemonic, self.bytes[2], self.bytes[1]),
+        }
+    }
+}
+
+/// Read a little-endian pointer, wrapping within the bank
+fn read_word<F: FnMut(u32) -> u8>(read: &mut F, addr: u32) -> u32 {
+    let next = (addr & 0xFF0000) | ((addr as u16).wrapping_add(1) as u32);
+    read(addr) as u32 | ((read(next) as u32) << 8)
+}
+
+/// Decode the instruction at `addr`
+///
+/// Operand bytes are fetched through `read` and wrap within the bank, as the
+/// program counter does.
+pub fn decode<F: FnMut(u32) -> u8>(addr: u32, m8: bool, x8: bool, mut read: F) -> Instruction {
+    let addr = addr & 0xFFFFFF;
+    let opcode = read(addr);
+    let (mnemonic, mode) = opcode_info(opcode);
+    let len = instruction_length(mode, m8, x8);
+
+    let mut bytes = [opcode, 0, 0, 0];
+    for (i, byte) in bytes.iter_mut().enumerate().take(len as usize).skip(1) {
+        let operand_addr = (addr & 0xFF0000) | ((addr as u16).wrapping_add(i as u16) as u32);
+        *byte = read(operand_addr);
+    }
+
+    Instruction {
+        addr,
+        opcode,
+        mnemonic,
+        mode,
+        bytes,
+        len,
+    }
+}
+
+/// Decode the instruction at the CPU's current PC using its register widths
+pub fn decode_at_pc<F: FnMut(u32) -> u8>(cpu: &Cpu65816, read: F) -> Instruction {
+    let pc = ((cpu.pbr as u32) << 16) | cpu.pc as u32;
+    let m8 = cpu.p.e || cpu.p.m;
+    let x8 = cpu.p.e || cpu.p.x;
+    decode(pc, m8, x8, read)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_bytes(addr: u32, code: &[u8], m8: bool, x8: bool) -> Instruction {
+        decode(addr, m8, x8, |a| code[(a - addr) as usize])
+    }
+
+    #[test]
+    fn test_immediate_widths() {
+        let code = [0xA9, 0x34, 0x12];
+        assert_eq!(decode_bytes(0x8000, &code, true, true).to_string(), "LDA #$34");
+        assert_eq!(decode_bytes(0x8000, &code, false, true).to_string(), "LDA #$1234");
+
+        let code = [0xA2, 0x34, 0x12];
+        assert_eq!(decode_bytes(0x8000, &code, false, true).len, 2);
+        assert_eq!(decode_bytes(0x8000, &code, true, false).to_string(), "LDX #$1234");
+
+        // REP is always 8-bit
+        let code = [0xC2, 0x30, 0x00];
+        assert_eq!(decode_bytes(0x8000, &code, false, false).to_string(), "REP #$30");
+    }
+
+    #[test]
+    fn test_operand_formats() {
+        let cases: &[(&[u8], &str)] = &[
+            (&[0xEA], "NOP"),
+            (&[0x0A], "ASL A"),
+            (&[0xBD, 0x00, 0x20], "LDA $2000,X"),
+            (&[0xBF, 0x56, 0x34, 0x12], "LDA $123456,X"),
+            (&[0xB7, 0x10], "LDA [$10],Y"),
+            (&[0xB3, 0x03], "LDA ($03,S),Y"),
+            (&[0x7C, 0x00, 0x90], "JMP ($9000,X)"),
+            (&[0xDC, 0x00, 0x90], "JML [$9000]"),
+            (&[0x54, 0x7E, 0x01], "MVN $01,$7E"),
+            (&[0xF4, 0xCD, 0xAB], "PEA $ABCD"),
+        ];
+        for (code, text) in cases {
+            assert_eq!(decode_bytes(0x8000, code, true, true).to_string(), *text);
+        }
+    }
+
+    #[test]
+    fn test_branch_targets() {
+        let bra = decode_bytes(0x018000, &[0x80, 0xFE], true, true);
+        assert_eq!(bra.branch_target(), Some(0x018000));
+        assert_eq!(bra.to_string(), "BRA $8000");
+
+        let brl = decode_bytes(0x01FFF0, &[0x82, 0x20, 0x00], true, true);
+        assert_eq!(brl.branch_target(), Some(0x010013));
+    }
+
+    #[test]
+    fn test_effective_addresses() {
+        let mut cpu = Cpu65816::new();
+        cpu.dbr = 0x7E;
+        cpu.d = 0x0100;
+        cpu.x = 0x0004;
+        cpu.y = 0x0010;
+
+        let mut ram = vec![0u8; 0x10000];
+        ram[0x0120] = 0x00;
+        ram[0x0121] = 0x30;
+        ram[0x0122] = 0x05;
+        let read = |a: u32| ram[(a & 0xFFFF) as usize];
+
+        let lda_abs = decode_bytes(0x808000, &[0xAD, 0x00, 0x20], true, true);
+        assert_eq!(lda_abs.effective_address(&cpu, read), Some(0x7E2000));
+
+        let jmp_abs = decode_bytes(0x808000, &[0x4C, 0x00, 0x90], true, true);
+        assert_eq!(jmp_abs.effective_address(&cpu, read), Some(0x809000));
+
+        let lda_dp_x = decode_bytes(0x808000, &[0xB5, 0x1C], true, true);
+        assert_eq!(lda_dp_x.effective_address(&cpu, read), Some(0x000120));
+
+        let lda_ind_y = decode_bytes(0x808000, &[0xB1, 0x20], true, true);
+        assert_eq!(lda_ind_y.effective_address(&cpu, read), Some(0x7E3010));
+
+        let lda_long_y = decode_bytes(0x808000, &[0xB7, 0x20], true, true);
+        assert_eq!(lda_long_y.effective_address(&cpu, read), Some(0x053010));
+
+        let lda_imm = decode_bytes(0x808000, &[0xA9, 0x00], true, true);
+        assert_eq!(lda_imm.effective_address(&cpu, read), None);
+    }
+
+    #[test]
+    fn test_format_with_symbols() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert(0x019234, "UpdatePlayer");
+        symbols.insert(0x008010, "Loop");
+        symbols.insert(0x7E0100, "PlayerX");
+
+        let jsl = decode_bytes(0x008000, &[0x22, 0x34, 0x92, 0x01], true, true);
+        assert_eq!(jsl.format_with_symbols(&symbols), "JSL UpdatePlayer");
+
+        let bne = decode_bytes(0x008020, &[0xD0, 0xEE], true, true);
+        assert_eq!(bne.format_with_symbols(&symbols), "BNE Loop");
+
+        let lda = decode_bytes(0x008000, &[0xBD, 0x00, 0x01], true, true);
+        assert_eq!(lda.format_with_symbols(&symbols), "LDA PlayerX,X");
+
+        let sta = decode_bytes(0x008000, &[0x8D, 0x00, 0x02], true, true);
+        assert_eq!(sta.format_with_symbols(&symbols), "STA $0200");
+    }
+
+    #[test]
+    fn test_all_opcodes_decode() {
+        for opcode in 0..=255u8 {
+            let inst = decode_bytes(0, &[opcode, 0, 0, 0], false, false);
+            assert!((1..=4).contains(&inst.len));
+            assert!(!inst.to_string().is_empty());
+        }
+    }
+}
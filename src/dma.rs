@@ -0,0 +1,154 @@
+//! S-CPU DMA controller ($420B, $4300-$437F)
+//!
+//! Holds the eight channel register sets. General purpose transfers are run
+//! by the emulator's system bus when MDMAEN ($420B) is written, since they
+//! need both the A-bus and the B-bus. HDMA registers are stored but HDMA
+//! transfers are not emulated yet.
+
+/// Master cycles per byte moved by general purpose DMA
+pub const CYCLES_PER_BYTE: u32 = 8;
+/// Master cycles of setup per enabled channel
+pub const CYCLES_PER_CHANNEL: u32 = 8;
+
+/// Registers of one DMA channel ($43x0-$43xF)
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DmaChannel {
+    /// DMAPx: direction (bit 7), HDMA indirect (bit 6), A-bus step (bits 3-4)
+    /// and transfer pattern (bits 0-2)
+    pub control: u8,
+    /// BBADx: B-bus register, $21xx
+    pub b_address: u8,
+    /// A1TxL/H: A-bus address, advanced as the transfer runs
+    pub a_address: u16,
+    /// A1Bx: A-bus bank
+    pub a_bank: u8,
+    /// DASxL/H: byte count (0 means 65536), counts down to 0
+    pub count: u16,
+    /// DASBx: HDMA indirect bank
+    pub indirect_bank: u8,
+    /// A2AxL/H: HDMA table address
+    pub table_address: u16,
+    /// NLTRx: HDMA line counter
+    pub line_counter: u8,
+    /// $43xB/$43xF: unused read/write byte
+    pub unused: u8,
+}
+
+impl DmaChannel {
+    /// Whether the transfer copies from the B-bus to the A-bus
+    pub fn b_to_a(&self) -> bool {
+        self.control & 0x80 != 0
+    }
+
+    /// B-bus register offsets written in turn by the transfer mode
+    pub fn pattern(&self) -> &'static [u8] {
+        match self.control & 7 {
+            0 => &[0],
+            1 => &[0, 1],
+            2 | 6 => &[0, 0],
+            3 | 7 => &[0, 0, 1, 1],
+            4 => &[0, 1, 2, 3],
+            _ => &[0, 1, 0, 1],
+        }
+    }
+
+    /// Amount added to the A-bus address after each byte
+    pub fn a_step(&self) -> i16 {
+        match self.control & 0x18 {
+            0x00 => 1,
+            0x10 => -1,
+            _ => 0,
+        }
+    }
+}
+
+/// The eight DMA channels plus HDMAEN
+#[derive(Clone, Debug, Default)]
+pub struct Dma {
+    pub channels: [DmaChannel; 8],
+    /// HDMAEN ($420C) channel mask
+    pub hdma_enable: u8,
+}
+
+impl Dma {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a channel register ($4300-$437F)
+    pub fn read_register(&self, addr: u16) -> u8 {
+        let channel = &self.channels[(addr >> 4) as usize & 7];
+        match addr & 0xF {
+            0x0 => channel.control,
+            0x1 => channel.b_address,
+            0x2 => channel.a_address as u8,
+            0x3 => (channel.a_address >> 8) as u8,
+            0x4 => channel.a_bank,
+            0x5 => channel.count as u8,
+            0x6 => (channel.count >> 8) as u8,
+            0x7 => channel.indirect_bank,
+            0x8 => channel.table_address as u8,
+            0x9 => (channel.table_address >> 8) as u8,
+            0xA => channel.line_counter,
+            _ => channel.unused,
+        }
+    }
+
+    /// Write a channel register ($4300-$437F)
+    pub fn write_register(&mut self, addr: u16, value: u8) {
+        let channel = &mut self.channels[(addr >> 4) as usize & 7];
+        match addr & 0xF {
+            0x0 => channel.control = value,
+            0x1 => channel.b_address = value,
+            0x2 => channel.a_address = (channel.a_address & 0xFF00) | value as u16,
+            0x3 => channel.a_address = (channel.a_address & 0x00FF) | (value as u16) << 8,
+            0x4 => channel.a_bank = value,
+            0x5 => channel.count = (channel.count & 0xFF00) | value as u16,
+            0x6 => channel.count = (channel.count & 0x00FF) | (value as u16) << 8,
+            0x7 => channel.indirect_bank = value,
+            0x8 => channel.table_address = (channel.table_address & 0xFF00) | value as u16,
+            0x9 => channel.table_address = (channel.table_address & 0x00FF) | (value as u16) << 8,
+            0xA => channel.line_counter = value,
+            0xB | 0xF => channel.unused = value,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_registers() {
+        let mut dma = Dma::new();
+        for (offset, value) in [(0x0, 0x01), (0x1, 0x18), (0x2, 0x00), (0x3, 0x90), (0x4, 0x7E), (0x5, 0x00), (0x6, 0x10)] {
+            dma.write_register(0x4320 | offset, value);
+        }
+        let channel = dma.channels[2];
+        assert_eq!(channel.b_address, 0x18);
+        assert_eq!(channel.a_address, 0x9000);
+        assert_eq!(channel.a_bank, 0x7E);
+        assert_eq!(channel.count, 0x1000);
+        assert_eq!(channel.pattern(), &[0, 1]);
+        assert_eq!(dma.read_register(0x4323), 0x90);
+
+        // $43xF mirrors $43xB
+        dma.write_register(0x432F, 0x5A);
+        assert_eq!(dma.read_register(0x432B), 0x5A);
+    }
+
+    #[test]
+    fn test_a_bus_step() {
+        let mut channel = DmaChannel::default();
+        assert_eq!(channel.a_step(), 1);
+        channel.control = 0x10;
+        assert_eq!(channel.a_step(), -1);
+        channel.control = 0x08;
+        assert_eq!(channel.a_step(), 0);
+        channel.control = 0x18;
+        assert_eq!(channel.a_step(), 0);
+        channel.control = 0x80;
+        assert!(channel.b_to_a());
+    }
+}
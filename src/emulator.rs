@@ -1,12 +1,19 @@
 // Integrated SNES Emulator
 // Combines CPU, PPU, and Memory systems
 
-use crate::cpu::Cpu65816;
+use crate::cpu::{Cpu65816, CpuBus};
 use crate::apu::Apu;
-use crate::ppu::Ppu;
+use crate::ppu::{Ppu, Renderer};
 use crate::memory::Memory;
 use crate::cartridge::{Cartridge, CartridgeType};
 use crate::chips::{ChipType, create_coprocessor};
+use crate::debug::{AccessKind, AddressSpace, BreakpointHit, Breakpoints, CodeDataLog, Event, EventKind, EventLog, MemoryDomain, Profiler};
+use crate::debug::events;
+use crate::disasm::{self, Instruction};
+use crate::dma::{self, Dma};
+
+/// Master clock cycles per CPU cycle
+const MASTER_CYCLES_PER_CPU_CYCLE: u32 = 6;
 
 /// Main SNES Emulator - coordinates CPU, PPU, APU, and memory
 pub struct Emulator {
@@ -17,6 +24,17 @@ pub struct Emulator {
     cartridge: Option<Cartridge>,
     master_cycles: u64,
     paused: bool,
+    /// Previous level of the coprocessor NMI line (NMI is edge triggered)
+    coprocessor_nmi_line: bool,
+    breakpoints: Breakpoints,
+    /// Address of the last execute breakpoint hit, skipped once on resume
+    exec_break_pc: Option<u32>,
+    /// Whether the last CPU step serviced an interrupt
+    interrupt_taken: bool,
+    dma: Dma,
+    profiler: Option<Profiler>,
+    code_data_log: Option<CodeDataLog>,
+    event_log: Option<EventLog>,
 }
 
 impl Emulator {
@@ -30,6 +48,14 @@ impl Emulator {
             cartridge: None,
             master_cycles: 0,
             paused: false,
+            coprocessor_nmi_line: false,
+            breakpoints: Breakpoints::new(),
+            exec_break_pc: None,
+            interrupt_taken: false,
+            dma: Dma::new(),
+            profiler: None,
+            code_data_log: None,
+            event_log: None,
         }
     }
     
@@ -51,8 +77,13 @@ impl Emulator {
         }
         self.ppu.reset();
         self.apu.reset();
+        self.dma = Dma::new();
         self.master_cycles = 0;
         self.paused = false;
+        self.coprocessor_nmi_line = false;
+        self.breakpoints.clear_hit();
+        self.exec_break_pc = None;
+        self.interrupt_taken = false;
     }
     
     /// Create a coprocessor based on cartridge type
@@ -67,41 +98,35 @@ impl Emulator {
     }
     
     /// Run emulator for one frame (returns true when frame completes)
+    ///
+    /// Returns false early when a breakpoint is hit; see `break_reason`.
     pub fn run_frame(&mut self) -> bool {
         if self.paused {
             return false;
         }
         
-        let mut frame_complete = false;
+        self.breakpoints.clear_hit();
         
-        // Run until a frame completes
-        // SNES master clock: ~21.477 MHz
-        // CPU runs at master/6, PPU at master/4 for dots
-        // For simplicity, we run them in lockstep
+        let mut frame_complete = false;
         
+        // Run until a frame completes, letting each instruction's cycle
+        // count drive the PPU, coprocessor and APU
         while !frame_complete {
-            // Step PPU (runs every master cycle)
-            frame_complete = self.ppu.step();
-            
-            // Step CPU (runs every 6-12 master cycles depending on mode)
-            // For now, step once per several PPU cycles
-            if self.master_cycles % 6 == 0 {
-                self.step_cpu();
-                
-                // Step coprocessor if present
-                if let Some(ref mut memory) = self.memory {
-                    memory.step_coprocessor(6);
-                }
+            let cycles = self.step_cpu(true);
+            if cycles == 0 && self.breakpoints.hit().is_some() {
+                return false;
             }
-
-            // Keep the APU running at roughly 1 MHz (coarse approximation)
-            // 1 APU step per master cycle is too slow; batch a few.
-            self.apu.step_spc(2);
             
-            self.master_cycles += 1;
+            // Keep time moving while no cartridge is loaded
+            let start = self.master_cycles;
+            frame_complete = self.advance(cycles.max(MASTER_CYCLES_PER_CPU_CYCLE));
+            
+            if self.breakpoints.hit().is_some() {
+                return false;
+            }
             
             // Safety limit to prevent infinite loops
-            if self.master_cycles % 100000 == 0 {
+            if self.master_cycles / 100000 != start / 100000 {
                 break;
             }
         }
@@ -112,71 +137,148 @@ impl Emulator {
     /// Step the emulator by one instruction
     pub fn step(&mut self) {
         if !self.paused {
-            self.step_cpu();
-            
-            // Step coprocessor if present
-            if let Some(ref mut memory) = self.memory {
-                memory.step_coprocessor(6);
-            }
-            
-            // Step PPU proportionally (approximately 6 dots per CPU cycle)
-            for _ in 0..6 {
-                self.ppu.step();
-            }
-
-            // Run a small batch of APU cycles to keep audio logic alive
-            self.apu.step_spc(32);
-            
-            self.master_cycles += 6;
+            self.breakpoints.clear_hit();
+            let cycles = self.step_cpu(false);
+            self.advance(cycles.max(MASTER_CYCLES_PER_CPU_CYCLE));
         }
     }
     
-    /// Step CPU and handle memory-mapped I/O
-    fn step_cpu(&mut self) {
-        // For now, simplified - just step PPU
-        // Full CPU execution would be integrated here
-        // The CPU module already has complex instruction execution
+    /// Run the rest of the system for `master_cycles` master cycles
+    ///
+    /// The PPU advances one dot and the APU two steps per master cycle.
+    /// Returns true if a frame completed.
+    fn advance(&mut self, master_cycles: u32) -> bool {
+        // Step coprocessor if present
+        if let Some(ref mut memory) = self.memory {
+            memory.step_coprocessor(master_cycles);
+        }
+        
+        let mut frame_complete = false;
+        for _ in 0..master_cycles {
+            if self.ppu.step() {
+                frame_complete = true;
+                self.end_debug_frame();
+            }
+        }
+        
+        self.step_apu(master_cycles * 2);
+        self.master_cycles += master_cycles as u64;
+        frame_complete
     }
     
-    /// Read byte from memory or memory-mapped I/O
-    #[allow(dead_code)]
-    fn read_byte(&mut self, addr: u32) -> u8 {
-        // Check for PPU register reads ($2100-$213F)
-        if (0x2100..=0x213F).contains(&(addr as u16)) {
-            return self.ppu.read_register(addr as u16);
+    /// Step CPU, servicing interrupts raised by the coprocessor first
+    ///
+    /// With `check_execute` set, an execute breakpoint at the current PC stops
+    /// the CPU before the instruction runs. Returns the master cycles taken,
+    /// including any DMA the instruction started, or 0 if nothing ran.
+    fn step_cpu(&mut self, check_execute: bool) -> u32 {
+        let Some(ref mut memory) = self.memory else {
+            return 0;
+        };
+        
+        let pc = ((self.cpu.pbr as u32) << 16) | self.cpu.pc as u32;
+        let resume_pc = self.exec_break_pc.take();
+        self.breakpoints.set_pc(pc);
+        if check_execute && resume_pc != Some(pc) && self.breakpoints.check_execute(pc) {
+            self.exec_break_pc = Some(pc);
+            return 0;
         }
         
-        // Check for APU I/O ports ($2140-$2143)
-        if (0x2140..=0x2143).contains(&(addr as u16)) {
-            return self.apu.cpu_read_port(addr as u16);
+        // NMI fires on the rising edge; IRQ is level triggered
+        let nmi_line = memory.coprocessor_nmi();
+        let nmi_edge = nmi_line && !self.coprocessor_nmi_line;
+        self.coprocessor_nmi_line = nmi_line;
+        let irq_line = memory.coprocessor_irq();
+        
+        if self.cpu.is_tracing() {
+            self.cpu.set_trace_position(self.ppu.get_scanline(), self.ppu.get_dot(), self.master_cycles);
         }
         
-        // Otherwise read from main memory (which may include coprocessor)
-        if let Some(ref mut memory) = self.memory {
-            memory.read(addr)
+        let mut bus = SystemBus {
+            memory,
+            ppu: &mut self.ppu,
+            apu: &mut self.apu,
+            dma: &mut self.dma,
+            breakpoints: &mut self.breakpoints,
+            event_log: self.event_log.as_mut(),
+            code_data_log: self.code_data_log.as_mut(),
+            pc,
+            dma_cycles: 0,
+        };
+        
+        let profile_start = self.profiler.as_ref().map(|_| {
+            (bus.peek(pc), self.cpu.waiting, self.cpu.stopped)
+        });
+        
+        let start_cycles = self.cpu.cycles;
+        self.interrupt_taken = false;
+        let mut logged = None;
+        if nmi_edge {
+            self.interrupt_taken = self.cpu.nmi(&mut bus) > 0;
+        } else if irq_line && self.cpu.irq(&mut bus) > 0 {
+            self.interrupt_taken = true;
         } else {
-            0
+            if let Some(cdl) = bus.code_data_log.as_deref_mut().filter(|_| !self.cpu.waiting && !self.cpu.stopped) {
+                logged = Some(cdl.record_instruction(&self.cpu, bus.memory));
+            }
+            self.cpu.step(&mut bus);
         }
-    }
-    
-    /// Write byte to memory or memory-mapped I/O
-    #[allow(dead_code)]
-    fn write_byte(&mut self, addr: u32, value: u8) {
-        // Check for PPU register writes ($2100-$2133)
-        if (0x2100..=0x2133).contains(&(addr as u16)) {
-            self.ppu.write_register(addr as u16, value);
-            return;
+        let cycles = self.cpu.cycles - start_cycles;
+        let dma_cycles = bus.dma_cycles;
+        
+        if let Some(log) = self.event_log.as_mut().filter(|_| self.interrupt_taken) {
+            log.record(Event {
+                kind: if nmi_edge { EventKind::Nmi } else { EventKind::Irq },
+                scanline: self.ppu.get_scanline(),
+                dot: self.ppu.get_dot(),
+                pc,
+                register: ((self.cpu.pbr as u32) << 16) | self.cpu.pc as u32,
+                value: 0,
+                length: 0,
+            });
         }
         
-        // Check for APU I/O ports ($2140-$2143)
-        if (0x2140..=0x2143).contains(&(addr as u16)) {
-            self.apu.cpu_write_port(addr as u16, value);
-            return;
+        if let Some(cdl) = self.code_data_log.as_mut() {
+            let next_pc = ((self.cpu.pbr as u32) << 16) | self.cpu.pc as u32;
+            if self.interrupt_taken {
+                cdl.record_interrupt(next_pc, memory);
+            } else if let Some(insn) = logged {
+                cdl.record_flow(&insn, next_pc, memory);
+            }
         }
         
-        // Otherwise write to main memory
-        if let Some(ref mut memory) = self.memory {
-            memory.write(addr, value);
+        if let (Some(profiler), Some((opcode, waiting, stopped))) = (self.profiler.as_mut(), profile_start) {
+            let next_pc = ((self.cpu.pbr as u32) << 16) | self.cpu.pc as u32;
+            if self.interrupt_taken {
+                profiler.record_interrupt(next_pc, self.cpu.s, cycles);
+            } else if waiting {
+                profiler.record_wait(cycles);
+            } else if !stopped {
+                profiler.record_instruction(pc, opcode, next_pc, self.cpu.s, cycles);
+            }
+            if dma_cycles > 0 {
+                profiler.record_dma(dma_cycles.div_ceil(MASTER_CYCLES_PER_CPU_CYCLE) as u64);
+            }
+        }
+        cycles as u32 * MASTER_CYCLES_PER_CPU_CYCLE + dma_cycles
+    }
+    
+    /// Close the current frame of the profiler and event log
+    fn end_debug_frame(&mut self) {
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.end_frame();
+        }
+        if let Some(log) = self.event_log.as_mut() {
+            log.end_frame();
+        }
+    }
+    
+    /// Step the SPC700, watching ARAM accesses when breakpoints are set
+    fn step_apu(&mut self, cycles: u32) {
+        if self.breakpoints.is_empty() {
+            self.apu.step_spc(cycles);
+        } else {
+            self.apu.step_spc_with(cycles, Some(&mut self.breakpoints));
         }
     }
     
@@ -215,9 +317,23 @@ impl Emulator {
         &mut self.ppu.framebuffer
     }
     
-    /// Get framebuffer dimensions
+    /// Get the active dimensions of the last frame
+    ///
+    /// 256 or 512 pixels wide (hi-res) and 224 or 239 lines high (overscan),
+    /// doubled for interlace. Rows are always 512 pixels apart.
     pub fn get_framebuffer_size(&self) -> (usize, usize) {
-        (512, 478)
+        self.ppu.get_output_size()
+    }
+    
+    /// Get the PPU renderer in use
+    pub fn ppu_renderer(&self) -> Renderer {
+        self.ppu.renderer()
+    }
+    
+    /// Choose between the fast (per-line) and accurate (per-dot) PPU
+    /// renderer; can be changed while running
+    pub fn set_ppu_renderer(&mut self, renderer: Renderer) {
+        self.ppu.set_renderer(renderer);
     }
     
     /// Write to PPU register
@@ -260,6 +376,153 @@ impl Emulator {
         self.master_cycles
     }
     
+    /// Get the breakpoint list
+    pub fn breakpoints(&self) -> &Breakpoints {
+        &self.breakpoints
+    }
+    
+    /// Get the breakpoint list for adding/removing breakpoints
+    pub fn breakpoints_mut(&mut self) -> &mut Breakpoints {
+        &mut self.breakpoints
+    }
+    
+    /// Whether the last CPU step serviced an interrupt instead of executing
+    /// an instruction
+    pub fn interrupt_taken(&self) -> bool {
+        self.interrupt_taken
+    }
+    
+    /// Install or remove the execution profiler
+    ///
+    /// Returns the previously installed profiler with its results.
+    pub fn set_profiler(&mut self, profiler: Option<Profiler>) -> Option<Profiler> {
+        std::mem::replace(&mut self.profiler, profiler)
+    }
+    
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+    
+    /// Install or remove the code/data logger
+    ///
+    /// The log must be sized for the loaded ROM. Returns the previously
+    /// installed log, e.g. to save it with `CodeDataLog::to_bytes`.
+    pub fn set_code_data_log(&mut self, log: Option<CodeDataLog>) -> Option<CodeDataLog> {
+        std::mem::replace(&mut self.code_data_log, log)
+    }
+    
+    pub fn code_data_log(&self) -> Option<&CodeDataLog> {
+        self.code_data_log.as_ref()
+    }
+    
+    /// Install or remove the event log
+    ///
+    /// Returns the previously installed log with its events.
+    pub fn set_event_log(&mut self, log: Option<EventLog>) -> Option<EventLog> {
+        std::mem::replace(&mut self.event_log, log)
+    }
+    
+    pub fn event_log(&self) -> Option<&EventLog> {
+        self.event_log.as_ref()
+    }
+    
+    /// Breakpoint that stopped the last `run_frame`/`step`, if any
+    pub fn break_reason(&self) -> Option<&BreakpointHit> {
+        self.breakpoints.hit()
+    }
+    
+    /// Disassemble the instruction at `addr` using the CPU's current M/X widths
+    pub fn disassemble(&self, addr: u32) -> Option<Instruction> {
+        let memory = self.memory.as_ref()?;
+        let m8 = self.cpu.p.e || self.cpu.p.m;
+        let x8 = self.cpu.p.e || self.cpu.p.x;
+        Some(disasm::decode(addr, m8, x8, |a| memory.peek(a)))
+    }
+    
+    /// Size of a memory domain in bytes (0 when it does not exist)
+    pub fn domain_size(&self, domain: MemoryDomain) -> usize {
+        match domain {
+            MemoryDomain::Cgram => self.ppu.cgram().len() * 2,
+            _ => self.domain_bytes(domain).map_or(0, <[u8]>::len),
+        }
+    }
+    
+    /// Read a byte of a memory domain without side effects
+    pub fn peek(&self, domain: MemoryDomain, addr: usize) -> Option<u8> {
+        match domain {
+            MemoryDomain::Cgram => {
+                let color = *self.ppu.cgram().get(addr / 2)?;
+                Some((color >> ((addr & 1) * 8)) as u8)
+            }
+            _ => self.domain_bytes(domain)?.get(addr).copied(),
+        }
+    }
+    
+    /// Write a byte of a memory domain without side effects
+    ///
+    /// Returns false when `addr` is outside the domain.
+    pub fn poke(&mut self, domain: MemoryDomain, addr: usize, value: u8) -> bool {
+        match domain {
+            MemoryDomain::Cgram => match self.ppu.cgram_mut().get_mut(addr / 2) {
+                Some(color) => {
+                    let shift = (addr & 1) * 8;
+                    *color = (*color & !(0xFF << shift)) | ((value as u16) << shift);
+                    true
+                }
+                None => false,
+            },
+            _ => match self.domain_bytes_mut(domain).and_then(|bytes| bytes.get_mut(addr)) {
+                Some(byte) => {
+                    *byte = value;
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+    
+    /// Read up to `len` bytes of a memory domain starting at `start`
+    ///
+    /// The result is shorter when the range runs past the end of the domain.
+    pub fn read_domain(&self, domain: MemoryDomain, start: usize, len: usize) -> Vec<u8> {
+        let end = start.saturating_add(len).min(self.domain_size(domain));
+        match domain {
+            MemoryDomain::Cgram => (start..end).filter_map(|addr| self.peek(domain, addr)).collect(),
+            _ => self.domain_bytes(domain).and_then(|bytes| bytes.get(start..end)).unwrap_or(&[]).to_vec(),
+        }
+    }
+    
+    /// Byte-addressed backing store of every domain except CGRAM
+    fn domain_bytes(&self, domain: MemoryDomain) -> Option<&[u8]> {
+        let memory = self.memory.as_ref();
+        match domain {
+            MemoryDomain::Wram => memory.map(Memory::wram),
+            MemoryDomain::Sram => memory.map(Memory::sram),
+            MemoryDomain::Rom => memory.map(Memory::rom),
+            MemoryDomain::CoprocessorRam => memory.map(Memory::coprocessor_ram),
+            MemoryDomain::Vram => Some(self.ppu.vram()),
+            MemoryDomain::Oam => Some(self.ppu.oam()),
+            MemoryDomain::Aram => Some(self.apu.ram()),
+            MemoryDomain::DspRegisters => Some(self.apu.dsp_registers()),
+            MemoryDomain::Cgram => None,
+        }
+    }
+    
+    fn domain_bytes_mut(&mut self, domain: MemoryDomain) -> Option<&mut [u8]> {
+        let memory = self.memory.as_mut();
+        match domain {
+            MemoryDomain::Wram => memory.map(Memory::wram_mut),
+            MemoryDomain::Sram => memory.map(Memory::sram_mut),
+            MemoryDomain::Rom => memory.map(Memory::rom_mut),
+            MemoryDomain::CoprocessorRam => memory.map(Memory::coprocessor_ram_mut),
+            MemoryDomain::Vram => Some(self.ppu.vram_mut()),
+            MemoryDomain::Oam => Some(self.ppu.oam_mut()),
+            MemoryDomain::Aram => Some(self.apu.ram_mut()),
+            MemoryDomain::DspRegisters => Some(self.apu.dsp_registers_mut()),
+            MemoryDomain::Cgram => None,
+        }
+    }
+    
     /// Direct VRAM write for testing/debugging
     pub fn write_vram(&mut self, addr: u16, data: &[u8]) {
         self.ppu.write_vram_wasm(addr, data);
@@ -276,6 +539,181 @@ impl Emulator {
     }
 }
 
+/// S-CPU view of the system bus
+///
+/// Routes B-bus registers to the PPU and APU, DMA registers to the DMA
+/// controller, everything else to `Memory`, and reports each access to the
+/// breakpoint list. Writing MDMAEN runs general purpose DMA on the spot.
+struct SystemBus<'a> {
+    memory: &'a mut Memory,
+    ppu: &'a mut Ppu,
+    apu: &'a mut Apu,
+    dma: &'a mut Dma,
+    breakpoints: &'a mut Breakpoints,
+    event_log: Option<&'a mut EventLog>,
+    /// Marks ROM bytes read by DMA
+    code_data_log: Option<&'a mut CodeDataLog>,
+    /// Address of the instruction being executed
+    pc: u32,
+    /// Master cycles spent in DMA started by the instruction
+    dma_cycles: u32,
+}
+
+impl SystemBus<'_> {
+    /// Offset within a bank that maps the S-CPU I/O area ($00-$3F, $80-$BF)
+    fn system_offset(addr: u32) -> Option<u16> {
+        let bank = (addr >> 16) as u8;
+        (bank < 0x40 || (0x80..0xC0).contains(&bank)).then_some(addr as u16)
+    }
+    
+    /// B-bus register ($21xx) targeted by `addr`, if any
+    fn io_register(addr: u32) -> Option<u16> {
+        Self::system_offset(addr).filter(|offset| (0x2100..=0x217F).contains(offset))
+    }
+    
+    /// Check a PPU data port access against VRAM/CGRAM/OAM breakpoints
+    fn check_data_port(&mut self, reg: u16, kind: AccessKind, value: u8) {
+        if let Some((space, target)) = self.ppu.data_port_target(reg) {
+            self.breakpoints.check(space, target, kind, value);
+        }
+    }
+    
+    /// Read a B-bus register; unconnected registers read as open bus
+    fn read_b_bus(&mut self, reg: u16) -> u8 {
+        match reg {
+            0x2100..=0x213F => {
+                // Resolve the port target before the read auto-increments it
+                let target = if self.breakpoints.is_empty() {
+                    None
+                } else {
+                    self.ppu.data_port_target(reg)
+                };
+                let value = self.ppu.read_register(reg);
+                if let Some((space, target)) = target {
+                    self.breakpoints.check(space, target, AccessKind::Read, value);
+                }
+                value
+            },
+            0x2140..=0x217F => self.apu.cpu_read_port(reg),
+            _ => 0,
+        }
+    }
+    
+    /// Write a B-bus register
+    fn write_b_bus(&mut self, reg: u16, value: u8) {
+        match reg {
+            0x2100..=0x213F => {
+                if !self.breakpoints.is_empty() {
+                    self.check_data_port(reg, AccessKind::Write, value);
+                }
+                self.ppu.write_register(reg, value);
+            },
+            0x2140..=0x217F => self.apu.cpu_write_port(reg, value),
+            _ => {}
+        }
+    }
+    
+    /// Run general purpose DMA on the channels in `mask`, lowest first
+    ///
+    /// The A-bus cannot reach B-bus registers, which read as open bus.
+    fn run_dma(&mut self, mask: u8) {
+        for index in 0..8 {
+            if mask & (1 << index) == 0 {
+                continue;
+            }
+            let mut channel = self.dma.channels[index];
+            let pattern = channel.pattern();
+            let mut unit = 0;
+            self.dma_cycles += dma::CYCLES_PER_CHANNEL;
+            loop {
+                let a = (channel.a_bank as u32) << 16 | channel.a_address as u32;
+                let b = 0x2100 | channel.b_address.wrapping_add(pattern[unit % pattern.len()]) as u16;
+                if channel.b_to_a() {
+                    let value = self.read_b_bus(b);
+                    self.breakpoints.check(AddressSpace::Bus, a, AccessKind::Write, value);
+                    if Self::io_register(a).is_none() {
+                        self.memory.write(a, value);
+                    }
+                } else {
+                    let value = match Self::io_register(a) {
+                        Some(_) => 0,
+                        None => self.memory.read(a),
+                    };
+                    if let (Some(cdl), Some(offset)) = (self.code_data_log.as_deref_mut(), self.memory.rom_offset(a)) {
+                        cdl.mark_dma(offset, 1);
+                    }
+                    self.breakpoints.check(AddressSpace::Bus, a, AccessKind::Read, value);
+                    self.write_b_bus(b, value);
+                }
+                
+                channel.a_address = channel.a_address.wrapping_add_signed(channel.a_step());
+                channel.count = channel.count.wrapping_sub(1);
+                unit += 1;
+                self.dma_cycles += dma::CYCLES_PER_BYTE;
+                if channel.count == 0 {
+                    break;
+                }
+            }
+            self.dma.channels[index] = channel;
+            
+            if let Some(log) = self.event_log.as_mut() {
+                log.record(Event {
+                    kind: EventKind::Dma,
+                    scanline: self.ppu.get_scanline(),
+                    dot: self.ppu.get_dot(),
+                    pc: self.pc,
+                    register: 0x2100 | channel.b_address as u32,
+                    value: index as u8,
+                    length: unit as u32,
+                });
+            }
+        }
+    }
+}
+
+impl CpuBus for SystemBus<'_> {
+    fn peek(&mut self, addr: u32) -> u8 {
+        // I/O registers have read side effects; report open bus instead
+        match Self::system_offset(addr) {
+            Some(0x2100..=0x217F) => 0,
+            Some(reg @ 0x4300..=0x437F) => self.dma.read_register(reg),
+            _ => self.memory.peek(addr),
+        }
+    }
+    
+    fn read(&mut self, addr: u32) -> u8 {
+        let value = match Self::system_offset(addr) {
+            Some(reg @ 0x2100..=0x217F) => self.read_b_bus(reg),
+            Some(reg @ 0x4300..=0x437F) => self.dma.read_register(reg),
+            _ => self.memory.read(addr),
+        };
+        self.breakpoints.check(AddressSpace::Bus, addr, AccessKind::Read, value);
+        value
+    }
+    
+    fn write(&mut self, addr: u32, value: u8) {
+        self.breakpoints.check(AddressSpace::Bus, addr, AccessKind::Write, value);
+        if let (Some(log), Some(kind)) = (self.event_log.as_mut(), events::classify_write(addr)) {
+            log.record(Event {
+                kind,
+                scanline: self.ppu.get_scanline(),
+                dot: self.ppu.get_dot(),
+                pc: self.pc,
+                register: addr & 0xFFFF,
+                value,
+                length: 0,
+            });
+        }
+        match Self::system_offset(addr) {
+            Some(reg @ 0x2100..=0x217F) => self.write_b_bus(reg, value),
+            Some(0x420B) => self.run_dma(value),
+            Some(0x420C) => self.dma.hdma_enable = value,
+            Some(reg @ 0x4300..=0x437F) => self.dma.write_register(reg, value),
+            _ => self.memory.write(addr, value),
+        }
+    }
+}
+
 impl Default for Emulator {
     fn default() -> Self {
         Self::new()
@@ -329,3 +767,331 @@ impl Emulator {
         &self.apu
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debug::{cdl, Breakpoint};
+    use crate::testing::RomBuilder;
+    
+    fn create_test_emulator() -> Emulator {
+        let code = [
+            0xA9, 0x12,         // $8000: LDA #$12
+            0x8D, 0x10, 0x00,   // $8002: STA $0010
+            0xA9, 0x80,         // $8005: LDA #$80
+            0x8D, 0x15, 0x21,   // $8007: STA $2115
+            0x9C, 0x16, 0x21,   // $800A: STZ $2116
+            0x9C, 0x17, 0x21,   // $800D: STZ $2117
+            0xA9, 0xAB,         // $8010: LDA #$AB
+            0x8D, 0x18, 0x21,   // $8012: STA $2118
+            0x80, 0xFE,         // $8015: BRA $8015
+        ];
+        let mut emulator = Emulator::new();
+        emulator.load_rom(&RomBuilder::lorom(0x8000).with_code(0x008000, &code).build()).unwrap();
+        emulator
+    }
+    
+    #[test]
+    fn test_write_breakpoint_stops_frame() {
+        let mut emulator = create_test_emulator();
+        let id = emulator.breakpoints_mut().add(Breakpoint::write(AddressSpace::Bus, 0x000010, 0x000010));
+        
+        assert!(!emulator.run_frame());
+        let hit = *emulator.break_reason().unwrap();
+        assert_eq!(hit.id, id);
+        assert_eq!(hit.kind, AccessKind::Write);
+        assert_eq!(hit.addr, 0x000010);
+        assert_eq!(hit.value, 0x12);
+        assert_eq!(hit.pc, 0x008002);
+        assert_eq!(emulator.cpu().pc, 0x8005);
+    }
+    
+    #[test]
+    fn test_execute_breakpoint_and_resume() {
+        let mut emulator = create_test_emulator();
+        emulator.breakpoints_mut().add(Breakpoint::execute(0x008005));
+        
+        assert!(!emulator.run_frame());
+        let hit = *emulator.break_reason().unwrap();
+        assert_eq!(hit.kind, AccessKind::Execute);
+        assert_eq!(hit.pc, 0x008005);
+        assert_eq!(emulator.cpu().pc, 0x8005);
+        assert_eq!(emulator.cpu().a & 0xFF, 0x12);
+        
+        // Resuming executes the instruction instead of hitting it again
+        emulator.run_frame();
+        assert!(emulator.break_reason().is_none());
+        assert_eq!(emulator.cpu().pc, 0x8015);
+    }
+    
+    #[test]
+    fn test_vram_write_breakpoint() {
+        let mut emulator = create_test_emulator();
+        emulator.breakpoints_mut().add(
+            Breakpoint::write(AddressSpace::Vram, 0x0000, 0x0001)
+                .with_condition(crate::debug::Condition::Equals(0xAB)),
+        );
+        
+        assert!(!emulator.run_frame());
+        let hit = *emulator.break_reason().unwrap();
+        assert_eq!(hit.space, AddressSpace::Vram);
+        assert_eq!(hit.addr, 0x0000);
+        assert_eq!(hit.pc, 0x008012);
+    }
+    
+    #[test]
+    fn test_step_advances_by_instruction_cycles() {
+        let mut emulator = create_test_emulator();
+        let dot = emulator.ppu.get_dot();
+        
+        emulator.step(); // LDA #$12: 2 cycles
+        assert_eq!(emulator.get_master_cycles(), 12);
+        emulator.step(); // STA $0010: 4 cycles
+        assert_eq!(emulator.get_master_cycles(), 36);
+        assert_eq!(emulator.ppu.get_dot(), dot + 36);
+    }
+    
+    /// Set up DMA channel 1 to copy four ROM bytes to VRAM $0000, start the
+    /// channels in `mask`, then spin
+    fn create_dma_emulator(mask: u8) -> Emulator {
+        let program = crate::asm::assemble(&format!(
+            "        org $8000\n\
+                     lda #$80\n\
+                     sta $2115\n\
+                     stz $2116\n\
+                     stz $2117\n\
+                     lda #$01\n\
+                     sta $4310\n\
+                     lda #$18\n\
+                     sta $4311\n\
+                     lda #<data\n\
+                     sta $4312\n\
+                     lda #>data\n\
+                     sta $4313\n\
+                     stz $4314\n\
+                     lda #4\n\
+                     sta $4315\n\
+                     stz $4316\n\
+                     lda #${:02X}\n\
+             start:  sta $420B\n\
+             spin:   bra spin\n\
+             data:   db $11, $22, $33, $44\n",
+            mask
+        ))
+        .unwrap();
+        let mut emulator = Emulator::new();
+        emulator.load_rom(&RomBuilder::lorom(0x8000).with_code(0x008000, &program.bytes()).build()).unwrap();
+        emulator
+    }
+    
+    #[test]
+    fn test_general_purpose_dma() {
+        let mut emulator = create_dma_emulator(0x02);
+        emulator.set_profiler(Some(Profiler::new()));
+        for _ in 0..18 {
+            emulator.step();
+        }
+        assert_eq!(emulator.cpu().pc, 0x802F);
+        
+        assert_eq!(&emulator.ppu().vram()[..4], &[0x11, 0x22, 0x33, 0x44]);
+        let channel = emulator.dma.channels[1];
+        assert_eq!(channel.count, 0);
+        assert_eq!(channel.a_address, 0x8035);
+        
+        // 8 master cycles of setup plus 8 per byte
+        let profiler = emulator.set_profiler(None).unwrap();
+        assert_eq!(profiler.total().dma_cycles, 40u32.div_ceil(MASTER_CYCLES_PER_CPU_CYCLE) as u64);
+    }
+    
+    #[test]
+    fn test_event_log_records_dma() {
+        // Channel 3 also copies two bytes to CGDATA
+        let mut emulator = create_dma_emulator(0x0A);
+        emulator.dma.channels[3] = dma::DmaChannel { b_address: 0x22, a_address: 0x8031, count: 2, ..Default::default() };
+        emulator.set_event_log(Some(EventLog::new()));
+        for _ in 0..18 {
+            emulator.step();
+        }
+        
+        let log = emulator.event_log().unwrap();
+        let dma: Vec<(u32, u8, u32, u32)> = log
+            .events()
+            .iter()
+            .filter(|e| e.kind == EventKind::Dma)
+            .map(|e| (e.register, e.value, e.length, e.pc))
+            .collect();
+        assert_eq!(dma, [(0x2118, 1, 4, 0x00802C), (0x2122, 3, 2, 0x00802C)]);
+        
+        // The MDMAEN write itself is a register write
+        let start = log.events().iter().find(|e| e.register == 0x420B).unwrap();
+        assert_eq!((start.kind, start.value), (EventKind::CpuWrite, 0x0A));
+    }
+    
+    #[test]
+    fn test_code_data_log_marks_dma_reads() {
+        let mut emulator = create_dma_emulator(0x02);
+        emulator.set_code_data_log(Some(CodeDataLog::new(0x8000)));
+        for _ in 0..18 {
+            emulator.step();
+        }
+        
+        let cdl = emulator.set_code_data_log(None).unwrap();
+        assert_eq!(cdl.count(cdl::DMA), 4);
+        assert_eq!(cdl.get(0x0031), cdl::DMA);
+        assert_eq!(cdl.get(0x0034), cdl::DMA);
+        assert_eq!(cdl.get(0x0035) & cdl::DMA, 0);
+    }
+    
+    #[test]
+    fn test_disassemble() {
+        let emulator = create_test_emulator();
+        let inst = emulator.disassemble(0x008002).unwrap();
+        assert_eq!(inst.to_string(), "STA $0010");
+        assert_eq!(inst.len, 3);
+    }
+    
+    /// Trace writer whose output stays readable after the CPU owns the logger
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+    
+    impl std::io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    
+    #[test]
+    fn test_trace_logging() {
+        use crate::debug::{TraceFormat, TraceLogger};
+        
+        let mut emulator = create_test_emulator();
+        let buffer = SharedBuffer::default();
+        let logger = TraceLogger::new(buffer.clone(), TraceFormat::Bsnes).with_range(0x008000, 0x008004);
+        emulator.cpu_mut().set_tracer(Some(Box::new(logger)));
+        
+        for _ in 0..4 {
+            emulator.step();
+        }
+        
+        let output = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("008000 a9 12       lda #$12 "));
+        assert!(lines[1].starts_with("008002 8d 10 00    sta $0010 [000010]"));
+        assert!(lines[1].contains("A:0012"));
+        
+        assert!(emulator.cpu_mut().set_tracer(None).is_some());
+        assert!(!emulator.cpu().is_tracing());
+    }
+    
+    #[test]
+    fn test_profiler() {
+        let mut emulator = create_test_emulator();
+        emulator.set_profiler(Some(Profiler::new()));
+        for _ in 0..20 {
+            emulator.step();
+        }
+        
+        let profiler = emulator.set_profiler(None).unwrap();
+        assert_eq!(profiler.pc_stats(0x008000).unwrap().cycles, 2);
+        let branch = profiler.pc_stats(0x008015).unwrap();
+        assert_eq!(branch.count, 12);
+        assert_eq!(branch.cycles, 12 * 3);
+        assert_eq!(profiler.hotspots()[0].0, 0x008015);
+        assert_eq!(profiler.subroutine_stats(0x008000).unwrap().inclusive_cycles, profiler.total().cycles);
+        assert!(emulator.profiler().is_none());
+    }
+    
+    #[test]
+    fn test_event_log() {
+        let mut emulator = create_test_emulator();
+        emulator.set_event_log(Some(EventLog::new()));
+        for _ in 0..10 {
+            emulator.step();
+        }
+        
+        let log = emulator.event_log().unwrap();
+        let writes: Vec<_> = log.events().iter().map(|e| (e.kind, e.register, e.value, e.pc)).collect();
+        assert_eq!(
+            writes,
+            vec![
+                (EventKind::PpuWrite, 0x2115, 0x80, 0x008007),
+                (EventKind::PpuWrite, 0x2116, 0x00, 0x00800A),
+                (EventKind::PpuWrite, 0x2117, 0x00, 0x00800D),
+                (EventKind::PpuWrite, 0x2118, 0xAB, 0x008012),
+            ]
+        );
+        assert!(log.events().windows(2).all(|w| (w[0].scanline, w[0].dot) < (w[1].scanline, w[1].dot)));
+        
+        emulator.run_frame();
+        let log = emulator.set_event_log(None).unwrap();
+        assert!(log.last_frame().len() <= 4);
+        assert_eq!(log.render_overlay().len(), events::OVERLAY_WIDTH * events::OVERLAY_HEIGHT);
+    }
+    
+    #[test]
+    fn test_memory_domains() {
+        let mut emulator = create_test_emulator();
+        assert_eq!(emulator.domain_size(MemoryDomain::Wram), 0x20000);
+        assert_eq!(emulator.domain_size(MemoryDomain::Vram), 0x10000);
+        assert_eq!(emulator.domain_size(MemoryDomain::Oam), 544);
+        assert_eq!(emulator.domain_size(MemoryDomain::Cgram), 512);
+        assert_eq!(emulator.domain_size(MemoryDomain::Aram), 0x10000);
+        assert_eq!(emulator.domain_size(MemoryDomain::DspRegisters), 0x80);
+        assert_eq!(emulator.domain_size(MemoryDomain::CoprocessorRam), 0);
+        
+        assert_eq!(emulator.peek(MemoryDomain::Rom, 0), Some(0xA9));
+        assert_eq!(emulator.read_domain(MemoryDomain::Rom, 0, 2), vec![0xA9, 0x12]);
+        
+        for domain in MemoryDomain::ALL {
+            if emulator.domain_size(domain) == 0 {
+                assert!(!emulator.poke(domain, 0, 0x5A));
+                continue;
+            }
+            assert!(emulator.poke(domain, 1, 0x5A), "{}", domain.name());
+            assert_eq!(emulator.peek(domain, 1), Some(0x5A), "{}", domain.name());
+            let end = emulator.domain_size(domain);
+            assert_eq!(emulator.peek(domain, end), None);
+            assert_eq!(emulator.read_domain(domain, end - 1, 4).len(), 1);
+        }
+        
+        // CGRAM bytes map onto the 16-bit colors, low byte first
+        emulator.poke(MemoryDomain::Cgram, 2, 0x1F);
+        emulator.poke(MemoryDomain::Cgram, 3, 0x7C);
+        assert_eq!(emulator.ppu().cgram()[1], 0x7C1F);
+        assert_eq!(emulator.read_domain(MemoryDomain::Cgram, 2, 2), vec![0x1F, 0x7C]);
+        
+        // Pokes go straight to the backing store
+        assert_eq!(emulator.ppu().vram()[1], 0x5A);
+        assert_eq!(emulator.memory().unwrap().wram()[1], 0x5A);
+        assert_eq!(MemoryDomain::from_name("vram"), Some(MemoryDomain::Vram));
+    }
+    
+    #[test]
+    fn test_code_data_log() {
+        let mut emulator = create_test_emulator();
+        emulator.set_code_data_log(Some(CodeDataLog::new(0x8000)));
+        for _ in 0..10 {
+            emulator.step();
+        }
+        
+        let cdl = emulator.set_code_data_log(None).unwrap();
+        assert_eq!(cdl.get(0x0000) & cdl::CODE, cdl::CODE);
+        assert_eq!(cdl.get(0x0015), cdl::CODE | cdl::JUMP_TARGET | cdl::MEMORY_8 | cdl::INDEX_8);
+        assert_eq!(cdl.count(cdl::CODE), 0x17);
+        assert_eq!(cdl.count(cdl::DATA), 0);
+    }
+    
+    #[test]
+    fn test_no_breakpoints_runs_frame() {
+        let mut emulator = create_test_emulator();
+        emulator.run_frame();
+        assert!(emulator.break_reason().is_none());
+        assert_eq!(emulator.cpu().pc, 0x8015);
+    }
+}
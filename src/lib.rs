@@ -6,7 +6,13 @@ pub mod cpu;
 pub mod ppu;
 pub mod emulator;
 pub mod apu;
+pub mod asm;
 pub mod chips;
+pub mod debug;
+pub mod disasm;
+pub mod dma;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 #[cfg(test)]
 mod apu_tests;
@@ -351,6 +351,26 @@ impl Memory {
             }
         }
         
+        self.read_mapped(addr)
+    }
+    
+    /// Read a byte without side effects, for debuggers and tracing
+    ///
+    /// Coprocessor registers report their value without acknowledging
+    /// interrupts or advancing FIFOs.
+    pub fn peek(&self, addr: u32) -> u8 {
+        match self.coprocessor {
+            Some(ref chip) if chip.handles_address(addr) => chip.peek(addr),
+            _ => self.read_mapped(addr),
+        }
+    }
+    
+    /// Read WRAM, SRAM or ROM through the memory map
+    fn read_mapped(&self, addr: u32) -> u8 {
+        if let Some(value) = self.vector_override(addr) {
+            return value;
+        }
+        
         let page = ((addr >> 13) & 0x7FF) as usize; // Get 8KB page number
         let offset_in_page = (addr & 0x1FFF) as usize;
         
@@ -407,6 +427,16 @@ impl Memory {
         }
     }
     
+    /// ROM file offset that `addr` reads from, if it maps to cartridge ROM
+    pub fn rom_offset(&self, addr: u32) -> Option<usize> {
+        if self.coprocessor.as_ref().is_some_and(|chip| chip.handles_address(addr)) {
+            return None;
+        }
+        let region = self.read_map[((addr >> 13) & 0x7FF) as usize];
+        let offset = region.offset + (addr & 0x1FFF) as usize;
+        (region.region_type == RegionType::Rom && offset < self.rom.len()).then_some(offset)
+    }
+    
     /// Read a 16-bit word from memory (little-endian)
     pub fn read_word(&mut self, addr: u32) -> u16 {
         let lo = self.read(addr) as u16;
@@ -436,11 +466,68 @@ impl Memory {
         }
     }
     
+    /// Check if the coprocessor (if present) is asserting IRQ to the main CPU
+    pub fn coprocessor_irq(&self) -> bool {
+        self.coprocessor.as_ref().is_some_and(|chip| chip.irq_line())
+    }
+    
+    /// Check if the coprocessor (if present) is asserting NMI to the main CPU
+    pub fn coprocessor_nmi(&self) -> bool {
+        self.coprocessor.as_ref().is_some_and(|chip| chip.nmi_line())
+    }
+    
+    /// Byte of a coprocessor-supplied interrupt vector at `addr`, if any
+    ///
+    /// The SA-1 answers reads of the native-mode NMI ($00FFEA) and IRQ
+    /// ($00FFEE) vectors with SNV/SIV when SCNT selects them, so every
+    /// interrupt entry fetches the replacement whatever raised it.
+    fn vector_override(&self, addr: u32) -> Option<u8> {
+        let chip = self.coprocessor.as_ref()?;
+        let vector = match addr & 0xFFFFFF {
+            0x00FFEA | 0x00FFEB => chip.nmi_vector()?,
+            0x00FFEE | 0x00FFEF => chip.irq_vector()?,
+            _ => return None,
+        };
+        Some((vector >> ((addr & 1) * 8)) as u8)
+    }
+    
     /// Get SRAM data for saving
     pub fn sram(&self) -> &[u8] {
         &self.sram
     }
     
+    pub fn sram_mut(&mut self) -> &mut [u8] {
+        &mut self.sram
+    }
+    
+    pub fn wram(&self) -> &[u8] {
+        &self.wram[..]
+    }
+    
+    pub fn wram_mut(&mut self) -> &mut [u8] {
+        &mut self.wram[..]
+    }
+    
+    pub fn rom(&self) -> &[u8] {
+        &self.rom
+    }
+    
+    pub fn rom_mut(&mut self) -> &mut [u8] {
+        &mut self.rom
+    }
+    
+    /// Internal RAM of the coprocessor (empty without one)
+    pub fn coprocessor_ram(&self) -> &[u8] {
+        self.coprocessor.as_ref().map_or(&[], |chip| chip.ram())
+    }
+    
+    pub fn coprocessor_ram_mut(&mut self) -> &mut [u8] {
+        match self.coprocessor {
+            Some(ref mut chip) => chip.ram_mut(),
+            None => &mut [],
+        }
+    }
+    
     /// Load SRAM data
     pub fn load_sram(&mut self, data: &[u8]) {
         let len = data.len().min(self.sram.len());
@@ -777,4 +864,25 @@ mod tests {
         let sram_data = memory.sram();
         assert_eq!(sram_data.len(), 8192); // 8KB as specified in header
     }
+    
+    #[test]
+    fn test_coprocessor_vector_override() {
+        let mut rom = create_test_rom_lorom();
+        rom[0x7FEA..0x7FF0].copy_from_slice(&[0x00, 0x81, 0x00, 0x00, 0x00, 0x82]);
+        let cartridge = Cartridge::from_rom(rom).unwrap();
+        let sa1 = crate::chips::create_coprocessor(crate::chips::ChipType::Sa1);
+        let mut memory = Memory::new_with_coprocessor(&cartridge, sa1);
+        assert_eq!(memory.read_word(0x00FFEA), 0x8100);
+        assert_eq!(memory.read_word(0x00FFEE), 0x8200);
+        
+        // SCNT bits 4 and 6 substitute SNV and SIV for the ROM vectors
+        for (addr, value) in [(0x220C, 0x34), (0x220D, 0x12), (0x220E, 0x78), (0x220F, 0x56), (0x2209, 0x50)] {
+            memory.write(addr, value);
+        }
+        assert_eq!(memory.read_word(0x00FFEA), 0x1234);
+        assert_eq!(memory.read_word(0x00FFEE), 0x5678);
+        assert_eq!(memory.peek(0x00FFEF), 0x56);
+        // Only bank $00 holds the vectors
+        assert_eq!(memory.read_word(0x80FFEA), 0x8100);
+    }
 }
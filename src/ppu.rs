@@ -1,6 +1,9 @@
 // SNES PPU (Picture Processing Unit) Implementation
 // Reference: https://snes.nesdev.org/wiki/PPU_registers
 
+use crate::debug::AddressSpace;
+use std::ops::Range;
+
 /// SNES PPU - handles all graphics rendering
 #[allow(dead_code)]
 pub struct Ppu {
@@ -21,10 +24,12 @@ pub struct Ppu {
     vram_increment: u16,
     vram_mapping: VramMapping,
     vram_read_buffer: u16,
+    vram_remap: u8,              // VMAIN bits 2-3 - Address translation
     
     // OAM access
     oam_address: u16,
     oam_high_byte: bool,
+    oam_priority_rotation: bool, // $2103 bit 7 - Start sprite evaluation at OAMADD
     oam_latch: u8,
     
     // CGRAM access
@@ -38,12 +43,15 @@ pub struct Ppu {
     oamadd: u16,                 // $2102-2103 - OAM address
     bgmode: u8,                  // $2105 - BG mode and character size
     mosaic: u8,                  // $2106 - Mosaic size and enable
+    mosaic_counter: u16,         // Lines left in the current mosaic block
+    mosaic_y: u16,               // First line of the current mosaic block
     
     // Background control
     bg1_tilemap_addr: u16,       // BG1 tilemap address
     bg2_tilemap_addr: u16,       // BG2 tilemap address
     bg3_tilemap_addr: u16,       // BG3 tilemap address
     bg4_tilemap_addr: u16,       // BG4 tilemap address
+    bg_tilemap_size: [u8; 4],    // BGnSC bits 0-1: 32x32, 64x32, 32x64, 64x64
     bg1_chr_addr: u16,           // BG1 character data address
     bg2_chr_addr: u16,           // BG2 character data address
     bg3_chr_addr: u16,           // BG3 character data address
@@ -81,6 +89,7 @@ pub struct Ppu {
     cgadsub: u8,                 // $2131 - Color math designation
     coldata: u8,                 // $2132 - Fixed color data
     fixed_color: [u8; 3],        // RGB components
+    setini: u8,                  // $2133 - Screen mode/video select
     
     // Mode 7 registers
     m7sel: u8,
@@ -90,10 +99,12 @@ pub struct Ppu {
     m7d: i16,
     m7x: i16,
     m7y: i16,
+    m7hofs: i16,                 // $210D - Mode 7 horizontal scroll
+    m7vofs: i16,                 // $210E - Mode 7 vertical scroll
     m7_latch: u8,
     
     // Status
-    stat77: u8,                  // $2137 - Software latch for H/V counters
+    stat77: u8,                  // $213E - Time Over/Range Over flags
     stat78: u8,                  // $2138 - OAM data read
     ophct: u16,                  // Horizontal counter
     opvct: u16,                  // Vertical counter
@@ -102,6 +113,15 @@ pub struct Ppu {
     vblank: bool,
     hblank: bool,
     frame_complete: bool,
+    field: bool,                 // Interlace field (STAT78 bit 7)
+    frame_hires: bool,           // Current frame is being output 512 wide
+    output_size: (usize, usize), // Active size of the last completed frame
+    
+    // Line renderer state
+    renderer: Renderer,
+    line_active: bool,           // Accurate renderer: current line still being drawn
+    line_x: usize,               // Pixels of the current line drawn so far
+    sprite_line: SpriteLine,     // Sprites evaluated for the current line
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -123,7 +143,66 @@ struct BgLayer {
     tilemap_height: u16,         // In tiles
 }
 
+/// Source layer of a screen pixel: 0-3 are BG1-4, then sprites and backdrop
+/// in CGADSUB bit order
+const LAYER_OBJ: u8 = 4;
+const LAYER_BACKDROP: u8 = 5;
+/// Sprites using palettes 0-3, which are excluded from color math
+const LAYER_OBJ_NO_MATH: u8 = 6;
+
+/// Main or sub screen of one scanline, before color math
+struct Screen {
+    /// BGR555 color per pixel
+    color: [u16; 512],
+    /// Depth of the frontmost layer drawn so far (see `LayerDepths`)
+    depth: [u8; 512],
+    /// Layer each pixel came from
+    layer: [u8; 512],
+}
+
+/// Direct color mode: BGR555 color from an 8bpp pixel (BBGGGRRR) and the
+/// tilemap palette bits, which supply the next lower bit of each component
+fn direct_color(pixel: u8, palette: u8) -> u16 {
+    let pixel = pixel as u16;
+    let palette = palette as u16;
+    let r = ((pixel & 0x07) << 2) | ((palette & 0x01) << 1);
+    let g = (((pixel >> 3) & 0x07) << 2) | (palette & 0x02);
+    let b = (((pixel >> 6) & 0x03) << 3) | (palette & 0x04);
+    r | (g << 5) | (b << 10)
+}
+
+/// Dot at which the first pixel of a line is output
+const FIRST_PIXEL_DOT: u16 = 22;
+
+/// How the PPU turns register state into pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Renderer {
+    /// Render each line in one go when it starts; mid-line register
+    /// changes only show up on the next line
+    #[default]
+    Fast,
+    /// Draw each line up to the current dot before every register write,
+    /// so mid-line raster effects display correctly
+    Accurate,
+}
+
+/// Front-most sprite pixel per screen column: CGRAM index and priority
+type SpriteLine = [Option<(u8, u8)>; 256];
+
+/// Per-pixel window state of one scanline (true = inside the window)
+type WindowMask = [bool; 256];
+
+/// Depth of each layer in the current BG mode (higher is in front, 0 = absent)
+#[derive(Debug, Clone, Copy)]
+struct LayerDepths {
+    /// Per BG: depth for tiles without and with the priority bit
+    bg: [[u8; 2]; 4],
+    /// Per sprite priority 0-3
+    obj: [u8; 4],
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(dead_code)]
 enum TileSize {
     Size8x8,
     Size16x16,
@@ -164,8 +243,10 @@ impl Ppu {
             vram_increment: 1,
             vram_mapping: VramMapping::Increment0,
             vram_read_buffer: 0,
+            vram_remap: 0,
             
             oam_address: 0,
+            oam_priority_rotation: false,
             oam_high_byte: false,
             oam_latch: 0,
             
@@ -178,11 +259,14 @@ impl Ppu {
             oamadd: 0,
             bgmode: 0,
             mosaic: 0,
+            mosaic_counter: 0,
+            mosaic_y: 0,
             
             bg1_tilemap_addr: 0,
             bg2_tilemap_addr: 0,
             bg3_tilemap_addr: 0,
             bg4_tilemap_addr: 0,
+            bg_tilemap_size: [0; 4],
             bg1_chr_addr: 0,
             bg2_chr_addr: 0,
             bg3_chr_addr: 0,
@@ -215,6 +299,7 @@ impl Ppu {
             cgadsub: 0,
             coldata: 0,
             fixed_color: [0, 0, 0],
+            setini: 0,
             
             m7sel: 0,
             m7a: 0,
@@ -223,6 +308,8 @@ impl Ppu {
             m7d: 0,
             m7x: 0,
             m7y: 0,
+            m7hofs: 0,
+            m7vofs: 0,
             m7_latch: 0,
             
             stat77: 0,
@@ -233,6 +320,14 @@ impl Ppu {
             vblank: true,
             hblank: false,
             frame_complete: false,
+            field: false,
+            frame_hires: false,
+            output_size: (256, 224),
+            
+            renderer: Renderer::Fast,
+            line_active: false,
+            line_x: 0,
+            sprite_line: [None; 256],
         };
         
         // Initialize with black color palette
@@ -255,11 +350,15 @@ impl Ppu {
             self.scanline += 1;
             
             // Render scanline if visible
-            if self.scanline < 224 && !self.is_forced_blank() {
-                self.render_scanline();
+            self.line_active = false;
+            if self.scanline <= self.visible_lines() && !self.is_forced_blank() {
+                match self.renderer {
+                    Renderer::Fast => self.render_scanline(),
+                    Renderer::Accurate => self.line_active = self.begin_scanline(),
+                }
             }
             
-            if self.scanline == 225 {
+            if self.scanline == self.visible_lines() + 1 {
                 // Start of VBlank
                 self.vblank = true;
             }
@@ -267,10 +366,22 @@ impl Ppu {
             if self.scanline >= 262 {
                 self.scanline = 0;
                 self.vblank = false;
+                self.stat77 = 0;
                 self.frame_complete = true;
+                
+                let width = if self.frame_hires { 512 } else { 256 };
+                let height = self.visible_lines() as usize * if self.is_interlaced() { 2 } else { 1 };
+                self.output_size = (width, height);
+                self.frame_hires = false;
+                self.field = !self.field;
             }
         }
         
+        if self.line_active && self.dot == FIRST_PIXEL_DOT + 256 {
+            self.catch_up();
+            self.line_active = false;
+        }
+        
         // HBlank occurs after visible portion (dot 274+)
         self.hblank = self.dot >= 274;
         
@@ -287,231 +398,584 @@ impl Ppu {
         self.inidisp & 0x0F
     }
     
-    /// Render the current scanline
+    /// Number of visible lines per field: 224, or 239 in overscan mode
+    fn visible_lines(&self) -> u16 {
+        if self.setini & 0x04 != 0 { 239 } else { 224 }
+    }
+    
+    fn is_interlaced(&self) -> bool {
+        self.setini & 0x01 != 0
+    }
+    
+    /// Render the current scanline in one go
+    ///
+    /// Visible lines start at line 1. Lines are written to the framebuffer
+    /// with a stride of 512 pixels; interlaced frames alternate between even
+    /// and odd rows by field. A frame is output 256 pixels wide until its
+    /// first hi-res line (Modes 5/6 or pseudo-hires), at which point earlier
+    /// rows are widened and the rest of the frame is written 512 wide, with
+    /// the sub screen in the even columns and the main screen in the odd ones.
     fn render_scanline(&mut self) {
-        let y = self.scanline as usize;
-        if y >= 224 {
-            return;
+        if self.begin_scanline() {
+            self.draw_pixels(0, 256);
         }
+    }
+    
+    /// Per-line setup (mosaic counter, sprite evaluation); returns false
+    /// outside the visible area
+    fn begin_scanline(&mut self) -> bool {
+        let y = self.scanline;
+        if y == 0 || y > self.visible_lines() {
+            return false;
+        }
+        self.update_mosaic();
+        self.sprite_line = self.evaluate_sprites();
+        self.line_x = 0;
+        true
+    }
+    
+    /// Render pixels `start..end` of the current line with the current
+    /// register state
+    ///
+    /// Only those pixels are rendered; the rest of the line is left as is.
+    fn draw_pixels(&mut self, start: usize, end: usize) {
+        let y = self.scanline;
+        let sprites = self.sprite_line;
+        let main = self.render_screen(true, &sprites, start..end);
+        let sub = self.render_screen(false, &sprites, start..end);
+        let colors = self.color_math(&main, &sub, start..end);
         
-        // Create scanline buffer
+        let hires = self.is_hires_mode() || self.setini & 0x08 != 0;
+        if hires && !self.frame_hires {
+            self.widen_framebuffer();
+            self.frame_hires = true;
+        }
+        let (from, to) = if self.frame_hires { (start * 2, end * 2) } else { (start, end) };
         let mut scanline_buffer = [0u32; 512];
-        let mut priority_buffer = [0u8; 512];
-        
-        let mode = self.bgmode & 0x07;
-        
-        match mode {
-            0 => self.render_mode0(&mut scanline_buffer, &mut priority_buffer),
-            1 => self.render_mode1(&mut scanline_buffer, &mut priority_buffer),
-            7 => self.render_mode7(&mut scanline_buffer, &mut priority_buffer),
-            _ => {
-                // Other modes not yet implemented - render black
-                for x in 0..256 {
-                    scanline_buffer[x] = self.rgb555_to_rgba8888(0);
-                }
+        for (x, &color) in colors.iter().enumerate().take(end).skip(start) {
+            let main_color = self.rgb555_to_rgba8888(color);
+            if !self.frame_hires {
+                scanline_buffer[x] = main_color;
+            } else if hires {
+                scanline_buffer[x * 2] = self.rgb555_to_rgba8888(sub.color[x]);
+                scanline_buffer[x * 2 + 1] = main_color;
+            } else {
+                scanline_buffer[x * 2] = main_color;
+                scanline_buffer[x * 2 + 1] = main_color;
             }
         }
         
-        // Render sprites on top
-        self.render_sprites(&mut scanline_buffer, &mut priority_buffer);
-        
-        // Apply brightness
-        let brightness = self.get_brightness();
+        // Apply brightness; forced blank mid-line shows black
+        let brightness = if self.is_forced_blank() { 0 } else { self.get_brightness() };
         if brightness < 15 {
-            for pixel in scanline_buffer.iter_mut() {
+            for pixel in scanline_buffer[from..to].iter_mut() {
                 *pixel = self.apply_brightness(*pixel, brightness);
             }
         }
         
         // Copy to framebuffer
-        let fb_offset = y * 512;
-        for x in 0..256 {
-            self.framebuffer[fb_offset + x] = scanline_buffer[x];
-        }
+        let fb_offset = self.framebuffer_row(y) * 512;
+        self.framebuffer[fb_offset + from..fb_offset + to].copy_from_slice(&scanline_buffer[from..to]);
+        self.line_x = end;
     }
     
-    /// Render Mode 0 - 4 layers, 2bpp each
-    fn render_mode0(&mut self, scanline: &mut [u32; 512], priority: &mut [u8; 512]) {
-        // Mode 0: BG1-4 all 2bpp, 8x8 or 16x16 tiles
-        let bg3_priority = (self.bgmode & 0x08) != 0;
-        
-        // Render in priority order (back to front)
-        // Priority: BG4 low, BG3 low, BG2 low, BG1 low, BG4 high, BG3 high, BG2 high, BG1 high
-        
-        if self.tm & 0x08 != 0 {  // BG4 enabled on main screen
-            self.render_bg_layer(3, 0, scanline, priority, 2); // Priority 0
-        }
-        
-        if self.tm & 0x04 != 0 {  // BG3 enabled
-            self.render_bg_layer(2, 0, scanline, priority, if bg3_priority { 8 } else { 4 });
-        }
-        
-        if self.tm & 0x02 != 0 {  // BG2 enabled
-            self.render_bg_layer(1, 0, scanline, priority, 6);
+    /// Accurate renderer: draw the current line up to the beam position
+    ///
+    /// Called before every register write and at the end of the line, so
+    /// each pixel uses the registers as they were when the beam reached it.
+    fn catch_up(&mut self) {
+        if !self.line_active {
+            return;
         }
-        
-        if self.tm & 0x01 != 0 {  // BG1 enabled
-            self.render_bg_layer(0, 0, scanline, priority, 10);
+        let target = (self.dot.saturating_sub(FIRST_PIXEL_DOT) as usize).min(256);
+        if target > self.line_x {
+            self.draw_pixels(self.line_x, target);
         }
-        
-        // High priority versions
-        if self.tm & 0x08 != 0 {
-            self.render_bg_layer(3, 1, scanline, priority, 3);
+    }
+    
+    pub fn renderer(&self) -> Renderer {
+        self.renderer
+    }
+    
+    /// Switch renderers; takes effect from the next line
+    pub fn set_renderer(&mut self, renderer: Renderer) {
+        self.renderer = renderer;
+    }
+    
+    /// Framebuffer row of visible line `y` in the current field
+    fn framebuffer_row(&self, y: u16) -> usize {
+        if self.is_interlaced() {
+            (y as usize - 1) * 2 + self.field as usize
+        } else {
+            y as usize - 1
         }
-        
-        if self.tm & 0x04 != 0 {
-            self.render_bg_layer(2, 1, scanline, priority, if bg3_priority { 9 } else { 5 });
+    }
+    
+    /// Double every pixel of the rows written so far in this frame and
+    /// field, switching the frame to 512-pixel output
+    ///
+    /// Rows of the other interlace field are left as they were output.
+    fn widen_framebuffer(&mut self) {
+        for y in 1..=self.scanline {
+            let offset = self.framebuffer_row(y) * 512;
+            let row = &mut self.framebuffer[offset..offset + 512];
+            for x in (0..256).rev() {
+                row[x * 2] = row[x];
+                row[x * 2 + 1] = row[x];
+            }
         }
-        
-        if self.tm & 0x02 != 0 {
-            self.render_bg_layer(1, 1, scanline, priority, 7);
+    }
+    
+    /// Active size of the last completed frame: 256 or 512 pixels wide, and
+    /// 224 or 239 lines high (doubled when interlaced)
+    pub fn get_output_size(&self) -> (usize, usize) {
+        self.output_size
+    }
+    
+    /// Advance the vertical mosaic counter at the start of a visible line
+    ///
+    /// Blocks start at the first visible line. The counter reloads with the
+    /// current size only when a block ends, so a size change mid-frame takes
+    /// effect from the next block.
+    fn update_mosaic(&mut self) {
+        self.mosaic_counter = self.mosaic_counter.saturating_sub(1);
+        if self.scanline == 1 || self.mosaic_counter == 0 {
+            self.mosaic_counter = self.mosaic_size();
+            self.mosaic_y = self.scanline;
         }
-        
-        if self.tm & 0x01 != 0 {
-            self.render_bg_layer(0, 1, scanline, priority, 11);
+    }
+    
+    /// Mosaic block size in pixels (1-16)
+    fn mosaic_size(&self) -> u16 {
+        (self.mosaic >> 4) as u16 + 1
+    }
+    
+    /// Screen position a layer samples for pixel `x` of the current line,
+    /// snapped to the top-left of its mosaic block when enabled
+    fn mosaic_position(&self, bg_num: usize, x: u16) -> (u16, u16) {
+        if self.mosaic & (1 << bg_num) == 0 {
+            return (x, self.scanline);
         }
+        (x - x % self.mosaic_size(), self.mosaic_y)
     }
     
-    /// Render Mode 1 - BG1/BG2 4bpp, BG3 2bpp
-    fn render_mode1(&mut self, scanline: &mut [u32; 512], priority: &mut [u8; 512]) {
-        // Mode 1: BG1 and BG2 are 4bpp, BG3 is 2bpp (background)
-        let bg3_priority = (self.bgmode & 0x08) != 0;
+    /// Render the main or sub screen: the layers enabled in TM/TS, hiding
+    /// those enabled in TMW/TSW inside their window
+    ///
+    /// The sub screen's backdrop is the fixed color. Only `pixels` are drawn.
+    fn render_screen(&mut self, main: bool, sprites: &SpriteLine, pixels: Range<usize>) -> Screen {
+        let (layers, windowed, backdrop) = if main {
+            (self.tm, self.tmw, self.cgram[0])
+        } else {
+            (self.ts, self.tsw, self.fixed_color_bgr555())
+        };
+        let mut screen = Screen {
+            color: [backdrop; 512],
+            depth: [0; 512],
+            layer: [LAYER_BACKDROP; 512],
+        };
         
-        if self.tm & 0x04 != 0 {  // BG3 enabled (lowest priority)
-            self.render_bg_layer(2, 0, scanline, priority, if bg3_priority { 8 } else { 2 });
-        }
+        let mode = self.bgmode & 0x07;
+        let depths = self.layer_depths();
+        let window = |ppu: &Self, layer: usize| {
+            if windowed & (1 << layer) != 0 {
+                ppu.window_mask(layer)
+            } else {
+                [false; 256]
+            }
+        };
         
-        if self.tm & 0x02 != 0 {  // BG2 low priority
-            self.render_bg_layer(1, 0, scanline, priority, 4);
+        if mode == 7 {
+            for bg in 0..2 {
+                let extbg = self.setini & 0x40 != 0;
+                if layers & (1 << bg) != 0 && (bg == 0 || extbg) {
+                    let window = window(self, bg);
+                    self.render_mode7(&mut screen, bg, depths.bg[bg], &window, pixels.clone());
+                }
+            }
+        } else {
+            for bg in 0..4 {
+                if layers & (1 << bg) != 0 && depths.bg[bg] != [0, 0] {
+                    let window = window(self, bg);
+                    self.render_bg_layer(bg, depths.bg[bg], &mut screen, &window, main, pixels.clone());
+                }
+            }
         }
         
-        if self.tm & 0x01 != 0 {  // BG1 low priority
-            self.render_bg_layer(0, 0, scanline, priority, 6);
+        // Render sprites between the background layers
+        if layers & 0x10 != 0 {
+            let window = window(self, 4);
+            self.render_sprites(&mut screen, sprites, depths.obj, &window, pixels);
         }
         
-        if self.tm & 0x04 != 0 {  // BG3 high priority
-            self.render_bg_layer(2, 1, scanline, priority, if bg3_priority { 9 } else { 3 });
-        }
+        screen
+    }
+    
+    /// Fixed color (COLDATA) as BGR555
+    fn fixed_color_bgr555(&self) -> u16 {
+        let [r, g, b] = self.fixed_color.map(u16::from);
+        r | (g << 5) | (b << 10)
+    }
+    
+    /// Combine main and sub screen into the final BGR555 line
+    ///
+    /// CGWSEL selects where the main screen is clipped to black and where
+    /// color math is prevented (both via the color window), and whether the
+    /// sub screen or the fixed color is the second operand. CGADSUB enables
+    /// math per main screen layer and selects add/subtract and halving.
+    /// Sprites using palettes 0-3 never take part in color math. Only
+    /// `pixels` of the returned line are filled in.
+    fn color_math(&self, main: &Screen, sub: &Screen, pixels: Range<usize>) -> [u16; 256] {
+        let color_window = self.window_mask(5);
+        let region = |mode: u8, x: usize| match mode & 0x03 {
+            0 => false,
+            1 => !color_window[x],
+            2 => color_window[x],
+            _ => true,
+        };
+        let use_sub = self.cgwsel & 0x02 != 0;
+        let subtract = self.cgadsub & 0x80 != 0;
+        let half = self.cgadsub & 0x40 != 0;
+        let fixed = self.fixed_color_bgr555();
         
-        if self.tm & 0x02 != 0 {  // BG2 high priority
-            self.render_bg_layer(1, 1, scanline, priority, 5);
+        let mut line = [0u16; 256];
+        for (x, out) in line.iter_mut().enumerate().take(pixels.end).skip(pixels.start) {
+            let clipped = region(self.cgwsel >> 6, x);
+            let main_color = if clipped { 0 } else { main.color[x] };
+            let layer = main.layer[x];
+            let enabled = layer != LAYER_OBJ_NO_MATH && self.cgadsub & (1 << layer) != 0;
+            if !enabled || region(self.cgwsel >> 4, x) {
+                *out = main_color;
+                continue;
+            }
+            
+            // A transparent sub screen pixel uses the fixed color, unhalved
+            let (operand, half) = if use_sub && sub.layer[x] != LAYER_BACKDROP {
+                (sub.color[x], half && !clipped)
+            } else if use_sub {
+                (fixed, false)
+            } else {
+                (fixed, half && !clipped)
+            };
+            
+            let mut color = 0;
+            for shift in [0, 5, 10] {
+                let a = (main_color >> shift) & 0x1F;
+                let b = (operand >> shift) & 0x1F;
+                let mut c = if subtract { a.saturating_sub(b) } else { a + b };
+                if half {
+                    c >>= 1;
+                }
+                color |= c.min(0x1F) << shift;
+            }
+            *out = color;
         }
+        line
+    }
+    
+    /// Pixels of the current line inside the window area of a layer
+    ///
+    /// `layer` is 0-3 for BG1-4, 4 for sprites and 5 for the color window.
+    /// Each of the two windows can be enabled and inverted per layer; when
+    /// both are enabled they are combined with the layer's mask logic
+    /// (OR/AND/XOR/XNOR). No enabled window means no pixel is inside.
+    fn window_mask(&self, layer: usize) -> WindowMask {
+        let settings = self.window_mask_settings[layer];
+        let window1 = (settings & 0x02 != 0).then_some((self.window1_left, self.window1_right, settings & 0x01 != 0));
+        let window2 = (settings & 0x08 != 0).then_some((self.window2_left, self.window2_right, settings & 0x04 != 0));
+        let inside = |x: u8, (left, right, invert): (u8, u8, bool)| (left..=right).contains(&x) != invert;
         
-        if self.tm & 0x01 != 0 {  // BG1 high priority
-            self.render_bg_layer(0, 1, scanline, priority, 7);
+        let mut mask = [false; 256];
+        for (x, masked) in mask.iter_mut().enumerate() {
+            let x = x as u8;
+            *masked = match (window1, window2) {
+                (None, None) => false,
+                (Some(w), None) | (None, Some(w)) => inside(x, w),
+                (Some(w1), Some(w2)) => {
+                    let (a, b) = (inside(x, w1), inside(x, w2));
+                    match self.window_mask_logic[layer] {
+                        0 => a || b,
+                        1 => a && b,
+                        2 => a != b,
+                        _ => a == b,
+                    }
+                }
+            };
         }
+        mask
+    }
+    
+    /// Front-to-back order of the layers in the current BG mode
+    ///
+    /// Reference: https://snes.nesdev.org/wiki/Backgrounds (layer priority)
+    fn layer_depths(&self) -> LayerDepths {
+        let bg3_priority = (self.bgmode & 0x08) != 0;
+        let (bg, obj) = match self.bgmode & 0x07 {
+            // S3 1H 2H S2 1L 2L S1 3H 4H S0 3L 4L
+            0 => ([[8, 11], [7, 10], [2, 5], [1, 4]], [3, 6, 9, 12]),
+            // 3H S3 1H 2H S2 1L 2L S1 S0 3L
+            1 if bg3_priority => ([[5, 8], [4, 7], [1, 10], [0, 0]], [2, 3, 6, 9]),
+            // S3 1H 2H S2 1L 2L S1 3H S0 3L
+            1 => ([[6, 9], [5, 8], [1, 3], [0, 0]], [2, 4, 7, 10]),
+            // S3 1H S2 2H S1 1L S0 2L
+            2..=5 => ([[3, 7], [1, 5], [0, 0], [0, 0]], [2, 4, 6, 8]),
+            // S3 1H S2 S1 1L S0
+            6 => ([[2, 5], [0, 0], [0, 0], [0, 0]], [1, 3, 4, 6]),
+            // S3 S2 2H S1 1 S0 2L
+            _ => ([[3, 3], [1, 5], [0, 0], [0, 0]], [2, 4, 6, 7]),
+        };
+        LayerDepths { bg, obj }
+    }
+    
+    /// Whether the current mode renders 512 pixels per line (Modes 5 and 6)
+    fn is_hires_mode(&self) -> bool {
+        matches!(self.bgmode & 0x07, 5 | 6)
     }
     
     /// Render Mode 7 - Affine transformation mode
-    fn render_mode7(&mut self, scanline: &mut [u32; 512], priority: &mut [u8; 512]) {
-        // Mode 7 is a special affine transformation mode
-        // For now, just render a simple version
+    ///
+    /// Follows the hardware's fixed-point math: the 13-bit signed center
+    /// (M7X/M7Y) and scroll (M7HOFS/M7VOFS) values and the 16-bit matrix
+    /// give 8.8 map coordinates, with partial products truncated to
+    /// multiples of 64 as on the real PPU. The 1024x1024 map is the low
+    /// byte of the first 16K VRAM words, character data the high byte.
+    ///
+    /// BG1 uses all 8 bits of a pixel as its color. With EXTBG, BG2 shows
+    /// the same plane using the low 7 bits as color and bit 7 as priority.
+    fn render_mode7(&mut self, screen: &mut Screen, bg_num: usize, depths: [u8; 2], window: &WindowMask, pixels: Range<usize>) {
+        let screen_over = self.m7sel >> 6;
+        let flip_h = self.m7sel & 0x01 != 0;
+        let flip_v = self.m7sel & 0x02 != 0;
         
-        let y = self.scanline as i32;
-        let repeat = (self.m7sel & 0xC0) >> 6;
+        let sign13 = |value: i16| ((value << 3) >> 3) as i32;
+        let clip = |value: i32| if value & 0x2000 != 0 { value | !0x3FF } else { value & 0x3FF };
+        let (a, b, c, d) = (self.m7a as i32, self.m7b as i32, self.m7c as i32, self.m7d as i32);
+        let (center_x, center_y) = (sign13(self.m7x), sign13(self.m7y));
+        let offset_x = clip(sign13(self.m7hofs) - center_x);
+        let offset_y = clip(sign13(self.m7vofs) - center_y);
         
-        for x in 0..256 {
-            // Apply affine transformation
-            let screen_x = x as i32 - 128;
-            let screen_y = y - 112;
+        for (x, &masked) in window.iter().enumerate().take(pixels.end).skip(pixels.start) {
+            if masked {
+                continue;
+            }
+            let (mosaic_x, y) = self.mosaic_position(bg_num, x as u16);
+            let y = if flip_v { 255 - (y as i32 & 0xFF) } else { y as i32 & 0xFF };
+            let screen_x = if flip_h { 255 - mosaic_x as i32 } else { mosaic_x as i32 };
             
-            // Transform to tilemap space
-            let tile_x = ((self.m7a as i32 * screen_x + self.m7b as i32 * screen_y) >> 8) + (self.m7x as i32);
-            let tile_y = ((self.m7c as i32 * screen_x + self.m7d as i32 * screen_y) >> 8) + (self.m7y as i32);
+            let start_x = ((a * offset_x) & !63) + ((b * offset_y) & !63) + ((b * y) & !63) + (center_x << 8);
+            let start_y = ((c * offset_x) & !63) + ((d * offset_y) & !63) + ((d * y) & !63) + (center_y << 8);
+            let map_x = (start_x + a * screen_x) >> 8;
+            let map_y = (start_y + c * screen_x) >> 8;
             
-            // Check bounds based on repeat mode
-            let (tx, ty) = match repeat {
-                0 => {
-                    // Repeat in both directions
-                    ((tile_x & 0x3FF) as u16, (tile_y & 0x3FF) as u16)
-                },
-                1 | 2 => {
-                    // Out of bounds = transparent
-                    if tile_x < 0 || tile_x >= 1024 || tile_y < 0 || tile_y >= 1024 {
-                        continue;
-                    }
-                    (tile_x as u16, tile_y as u16)
-                },
-                _ => continue,
+            // Screen over: 0/1 wrap, 2 transparent, 3 fills with tile 0
+            let outside = (map_x | map_y) & !0x3FF != 0;
+            let tile = match screen_over {
+                2 if outside => continue,
+                3 if outside => 0,
+                _ => {
+                    let entry = ((map_y as usize >> 3) & 0x7F) * 128 + ((map_x as usize >> 3) & 0x7F);
+                    self.vram[entry * 2] as usize
+                }
             };
+            let pixel = tile * 64 + (map_y as usize & 7) * 8 + (map_x as usize & 7);
+            let data = self.vram[pixel * 2 + 1];
             
-            // Get tile and pixel from Mode 7 tilemap
-            let tile_num = self.read_vram(((ty >> 3) * 128 + (tx >> 3)) as u16) as u16;
-            let pixel_x = (tx & 7) as usize;
-            let pixel_y = (ty & 7) as usize;
-            
-            let tile_addr = tile_num * 64 + (pixel_y * 8 + pixel_x) as u16;
-            let color_index = self.read_vram(tile_addr);
-            
-            if color_index != 0 {
-                let color = self.cgram[color_index as usize];
-                scanline[x] = self.rgb555_to_rgba8888(color);
-                priority[x] = 8;
+            let (color_index, depth) = if bg_num == 0 {
+                (data, depths[0])
+            } else {
+                (data & 0x7F, depths[(data >> 7) as usize])
+            };
+            if color_index != 0 && depth > screen.depth[x] {
+                screen.color[x] = if bg_num == 0 && self.cgwsel & 0x01 != 0 {
+                    direct_color(color_index, 0)
+                } else {
+                    self.cgram[color_index as usize]
+                };
+                screen.depth[x] = depth;
+                screen.layer[x] = bg_num as u8;
             }
         }
     }
     
     /// Render a background layer
-    fn render_bg_layer(&mut self, bg_num: usize, pri: u8, scanline: &mut [u32; 512], priority: &mut [u8; 512], layer_priority: u8) {
-        let y = self.scanline;
-        
-        let (tilemap_addr, chr_addr, hscroll, vscroll) = match bg_num {
-            0 => (self.bg1_tilemap_addr, self.bg1_chr_addr, self.bg1_hscroll, self.bg1_vscroll),
-            1 => (self.bg2_tilemap_addr, self.bg2_chr_addr, self.bg2_hscroll, self.bg2_vscroll),
-            2 => (self.bg3_tilemap_addr, self.bg3_chr_addr, self.bg3_hscroll, self.bg3_vscroll),
-            3 => (self.bg4_tilemap_addr, self.bg4_chr_addr, self.bg4_hscroll, self.bg4_vscroll),
-            _ => return,
-        };
-        
-        let mode = self.bgmode & 0x07;
-        let bpp = self.get_bg_bpp(bg_num, mode);
-        let _tile_size = if self.is_bg_16x16(bg_num) { TileSize::Size16x16 } else { TileSize::Size8x8 };
+    ///
+    /// `depths` gives the layer's depth for tiles without and with the
+    /// priority bit set; a pixel is drawn over anything with a lower depth.
+    ///
+    /// In Modes 5 and 6 the layer has 512 pixels per line: the main screen
+    /// shows the odd ones and the sub screen the even ones. Interlace also
+    /// doubles the vertical resolution in those modes.
+    fn render_bg_layer(&mut self, bg_num: usize, depths: [u8; 2], screen: &mut Screen, window: &WindowMask, main: bool, pixels: Range<usize>) {
+        let (hscroll, vscroll) = self.bg_scroll(bg_num);
+        let hires = self.is_hires_mode();
+        let interlace = hires && self.is_interlaced();
+        let opt = matches!(self.bgmode & 0x07, 2 | 4 | 6) && bg_num < 2;
         
-        // Adjust Y position with scroll
-        let tile_y = ((y as u16 + vscroll) & 0x1FF) as usize;
-        let fine_y = tile_y & 7;
-        let coarse_y = tile_y >> 3;
-        
-        for x in 0..256 {
-            // Adjust X position with scroll
-            let tile_x = ((x as u16 + hscroll) & 0x1FF) as usize;
-            let fine_x = tile_x & 7;
-            let coarse_x = tile_x >> 3;
-            
-            // Get tilemap entry (32x32 tiles = 1024 entries, 2 bytes each)
-            let tilemap_offset = tilemap_addr + ((coarse_y * 32 + coarse_x) * 2) as u16;
-            let tile_low = self.read_vram(tilemap_offset);
-            let tile_high = self.read_vram(tilemap_offset + 1);
-            
-            let tile_num = (tile_low as u16) | (((tile_high & 0x03) as u16) << 8);
-            let palette = (tile_high >> 2) & 0x07;
-            let tile_pri = (tile_high >> 5) & 0x01;
-            let flip_h = (tile_high & 0x40) != 0;
-            let flip_v = (tile_high & 0x80) != 0;
-            
-            // Skip if wrong priority
-            if tile_pri != pri {
+        for x in pixels.start as u16..pixels.end as u16 {
+            if window[x as usize] {
                 continue;
             }
+            let (mosaic_x, y) = self.mosaic_position(bg_num, x);
+            let (hscroll, vscroll) = if opt {
+                self.offset_per_tile(bg_num, mosaic_x, hscroll, vscroll)
+            } else {
+                (hscroll, vscroll)
+            };
+            let (screen_x, hscroll) = if hires { (mosaic_x * 2 + main as u16, hscroll << 1) } else { (mosaic_x, hscroll) };
+            let y = if interlace { y * 2 + self.field as u16 } else { y };
             
-            // Get pixel from tile
-            let pixel_x = if flip_h { 7 - fine_x } else { fine_x };
-            let pixel_y = if flip_v { 7 - fine_y } else { fine_y };
-            
-            let color_index = self.get_tile_pixel(chr_addr, tile_num, pixel_x, pixel_y, bpp);
-            
-            // Color 0 is transparent
-            if color_index != 0 {
-                let palette_index = (palette << bpp) | color_index;
-                let color = self.cgram[palette_index as usize];
-                
-                // Only draw if higher priority
-                if layer_priority >= priority[x] {
-                    scanline[x] = self.rgb555_to_rgba8888(color);
-                    priority[x] = layer_priority;
+            let Some((color, tile_pri)) = self.bg_pixel(bg_num, screen_x.wrapping_add(hscroll), y.wrapping_add(vscroll)) else {
+                continue;
+            };
+            let depth = depths[tile_pri as usize];
+            let x = x as usize;
+            if depth > screen.depth[x] {
+                screen.color[x] = color;
+                screen.depth[x] = depth;
+                screen.layer[x] = bg_num as u8;
+            }
+        }
+    }
+    
+    /// Scroll registers of a background layer
+    fn bg_scroll(&self, bg_num: usize) -> (u16, u16) {
+        match bg_num {
+            0 => (self.bg1_hscroll, self.bg1_vscroll),
+            1 => (self.bg2_hscroll, self.bg2_vscroll),
+            2 => (self.bg3_hscroll, self.bg3_vscroll),
+            _ => (self.bg4_hscroll, self.bg4_vscroll),
+        }
+    }
+    
+    /// Apply offset-per-tile to BG1/BG2 scroll values at screen column `x`
+    ///
+    /// Modes 2, 4 and 6 read per-column scroll values from BG3's tilemap:
+    /// row 0 holds horizontal offsets and row 1 vertical offsets (Mode 4 uses
+    /// one row, bit 15 selecting vertical). Bit 13 enables an entry for BG1
+    /// and bit 14 for BG2. The leftmost partial column is never affected.
+    ///
+    /// Works in lo-res pixels, also in Mode 6 where the caller doubles the
+    /// resulting scroll value.
+    fn offset_per_tile(&self, bg_num: usize, x: u16, hscroll: u16, vscroll: u16) -> (u16, u16) {
+        let offset_x = x + (hscroll & 7);
+        if offset_x < 8 {
+            return (hscroll, vscroll);
+        }
+        
+        let valid_bit = 0x2000 << bg_num;
+        let mut lookup_x = (offset_x - 8) + (self.bg3_hscroll & !7);
+        if self.is_hires_mode() {
+            // Tilemap lookups take hi-res coordinates in Mode 6
+            lookup_x <<= 1;
+        }
+        let h_entry = self.bg_tile_entry(2, lookup_x, self.bg3_vscroll);
+        let (mut hscroll, mut vscroll) = (hscroll, vscroll);
+        
+        if self.bgmode & 0x07 == 4 {
+            if h_entry & valid_bit != 0 {
+                if h_entry & 0x8000 == 0 {
+                    hscroll = (h_entry & 0x3F8) | (hscroll & 7);
+                } else {
+                    vscroll = h_entry & 0x3FF;
                 }
             }
+        } else {
+            let v_entry = self.bg_tile_entry(2, lookup_x, self.bg3_vscroll.wrapping_add(8));
+            if h_entry & valid_bit != 0 {
+                hscroll = (h_entry & 0x3F8) | (hscroll & 7);
+            }
+            if v_entry & valid_bit != 0 {
+                vscroll = v_entry & 0x3FF;
+            }
+        }
+        (hscroll, vscroll)
+    }
+    
+    /// Width and height of a layer's tiles in pixels
+    ///
+    /// Modes 5 and 6 always use 16-pixel wide tiles.
+    fn bg_tile_dims(&self, bg_num: usize) -> (u16, u16) {
+        let size = if self.is_bg_16x16(bg_num) { 16 } else { 8 };
+        let width = if self.is_hires_mode() { 16 } else { size };
+        (width, size)
+    }
+    
+    /// Tilemap entry covering pixel (x, y) of a background plane
+    fn bg_tile_entry(&self, bg_num: usize, x: u16, y: u16) -> u16 {
+        let tilemap_addr = match bg_num {
+            0 => self.bg1_tilemap_addr,
+            1 => self.bg2_tilemap_addr,
+            2 => self.bg3_tilemap_addr,
+            _ => self.bg4_tilemap_addr,
+        };
+        let (tile_w, tile_h) = self.bg_tile_dims(bg_num);
+        
+        // The map is made of 1-4 screens of 32x32 entries, 2 bytes each,
+        // laid out left to right, then top to bottom
+        let size = self.bg_tilemap_size[bg_num];
+        let (wide, tall) = (size & 0x01 != 0, size & 0x02 != 0);
+        let coarse_x = (x / tile_w) & if wide { 63 } else { 31 };
+        let coarse_y = (y / tile_h) & if tall { 63 } else { 31 };
+        let mut screen = coarse_x >> 5;
+        if coarse_y >= 32 {
+            screen += if wide { 2 } else { 1 };
+        }
+        let entry = ((coarse_y & 31) * 32 + (coarse_x & 31)) * 2;
+        let offset = tilemap_addr.wrapping_add(screen * 0x800).wrapping_add(entry);
+        (self.read_vram(offset) as u16) | ((self.read_vram(offset.wrapping_add(1)) as u16) << 8)
+    }
+    
+    /// BGR555 color and tile priority bit of pixel (x, y) of a background plane
+    ///
+    /// Returns None for transparent pixels.
+    fn bg_pixel(&self, bg_num: usize, x: u16, y: u16) -> Option<(u16, u8)> {
+        let entry = self.bg_tile_entry(bg_num, x, y);
+        let (tile_w, tile_h) = self.bg_tile_dims(bg_num);
+        
+        let mut tile_num = entry & 0x03FF;
+        let palette = ((entry >> 10) & 0x07) as u8;
+        let tile_pri = ((entry >> 13) & 0x01) as u8;
+        let flip_h = (entry & 0x4000) != 0;
+        let flip_v = (entry & 0x8000) != 0;
+        
+        // Position within the (possibly 16x16) tile, then within its 8x8 part
+        let mut fine_x = x % tile_w;
+        let mut fine_y = y % tile_h;
+        if flip_h {
+            fine_x = tile_w - 1 - fine_x;
+        }
+        if flip_v {
+            fine_y = tile_h - 1 - fine_y;
+        }
+        if fine_x >= 8 {
+            tile_num += 1;
+        }
+        if fine_y >= 8 {
+            tile_num += 16;
+        }
+        
+        let (chr_addr, mode) = match bg_num {
+            0 => (self.bg1_chr_addr, self.bgmode & 0x07),
+            1 => (self.bg2_chr_addr, self.bgmode & 0x07),
+            2 => (self.bg3_chr_addr, self.bgmode & 0x07),
+            _ => (self.bg4_chr_addr, self.bgmode & 0x07),
+        };
+        let bpp = self.get_bg_bpp(bg_num, mode);
+        let color_index = self.get_tile_pixel(chr_addr, tile_num & 0x03FF, (fine_x & 7) as usize, (fine_y & 7) as usize, bpp);
+        
+        // Color 0 is transparent
+        if color_index == 0 {
+            return None;
         }
+        
+        let index = match bpp {
+            8 if self.cgwsel & 0x01 != 0 => return Some((direct_color(color_index, palette), tile_pri)),
+            8 => color_index,
+            // Mode 0 gives each layer its own 32 colors
+            2 if mode == 0 => (bg_num as u8) * 32 + (palette << 2) + color_index,
+            _ => (palette << bpp) | color_index,
+        };
+        Some((self.cgram[index as usize], tile_pri))
     }
     
     /// Get bits per pixel for a background layer in a given mode
@@ -553,9 +1017,9 @@ impl Ppu {
     
     /// Decode 2bpp tile pixel
     fn decode_2bpp(&self, chr_addr: u16, tile_num: u16, x: usize, y: usize) -> u8 {
-        let tile_addr = chr_addr + (tile_num * 16) + (y * 2) as u16;
+        let tile_addr = chr_addr.wrapping_add(tile_num.wrapping_mul(16)).wrapping_add((y * 2) as u16);
         let plane0 = self.read_vram(tile_addr);
-        let plane1 = self.read_vram(tile_addr + 1);
+        let plane1 = self.read_vram(tile_addr.wrapping_add(1));
         
         let bit = 7 - x;
         let bit0 = (plane0 >> bit) & 1;
@@ -566,11 +1030,11 @@ impl Ppu {
     
     /// Decode 4bpp tile pixel
     fn decode_4bpp(&self, chr_addr: u16, tile_num: u16, x: usize, y: usize) -> u8 {
-        let tile_addr = chr_addr + (tile_num * 32) + (y * 2) as u16;
+        let tile_addr = chr_addr.wrapping_add(tile_num.wrapping_mul(32)).wrapping_add((y * 2) as u16);
         let plane0 = self.read_vram(tile_addr);
-        let plane1 = self.read_vram(tile_addr + 1);
-        let plane2 = self.read_vram(tile_addr + 16);
-        let plane3 = self.read_vram(tile_addr + 17);
+        let plane1 = self.read_vram(tile_addr.wrapping_add(1));
+        let plane2 = self.read_vram(tile_addr.wrapping_add(16));
+        let plane3 = self.read_vram(tile_addr.wrapping_add(17));
         
         let bit = 7 - x;
         let bit0 = (plane0 >> bit) & 1;
@@ -583,15 +1047,15 @@ impl Ppu {
     
     /// Decode 8bpp tile pixel
     fn decode_8bpp(&self, chr_addr: u16, tile_num: u16, x: usize, y: usize) -> u8 {
-        let tile_addr = chr_addr + (tile_num * 64) + (y * 2) as u16;
+        let tile_addr = chr_addr.wrapping_add(tile_num.wrapping_mul(64)).wrapping_add((y * 2) as u16);
         let plane0 = self.read_vram(tile_addr);
-        let plane1 = self.read_vram(tile_addr + 1);
-        let plane2 = self.read_vram(tile_addr + 16);
-        let plane3 = self.read_vram(tile_addr + 17);
-        let plane4 = self.read_vram(tile_addr + 32);
-        let plane5 = self.read_vram(tile_addr + 33);
-        let plane6 = self.read_vram(tile_addr + 48);
-        let plane7 = self.read_vram(tile_addr + 49);
+        let plane1 = self.read_vram(tile_addr.wrapping_add(1));
+        let plane2 = self.read_vram(tile_addr.wrapping_add(16));
+        let plane3 = self.read_vram(tile_addr.wrapping_add(17));
+        let plane4 = self.read_vram(tile_addr.wrapping_add(32));
+        let plane5 = self.read_vram(tile_addr.wrapping_add(33));
+        let plane6 = self.read_vram(tile_addr.wrapping_add(48));
+        let plane7 = self.read_vram(tile_addr.wrapping_add(49));
         
         let bit = 7 - x;
         let bit0 = (plane0 >> bit) & 1;
@@ -607,89 +1071,122 @@ impl Ppu {
         (bit4 << 4) | (bit5 << 5) | (bit6 << 6) | (bit7 << 7)
     }
     
-    /// Render sprites
-    fn render_sprites(&mut self, scanline: &mut [u32; 512], priority: &mut [u8; 512]) {
-        let y = self.scanline as i16;
-        
-        // Get sprite sizes from OBSEL register
+    /// Evaluate and draw the sprites of the current line into a line buffer
+    ///
+    /// Like the hardware, this first scans OAM for up to 32 sprites on the
+    /// line, starting at the priority rotation index, then fetches up to 34
+    /// 8-pixel tile slivers beginning with the last sprite found, so sprites
+    /// at the front of the list lose their tiles first. Overflowing either
+    /// limit sets the Range Over or Time Over flag in STAT77. Sprites appear
+    /// one line below their Y coordinate; earlier sprites in the list are
+    /// drawn in front of later ones regardless of their priority bits.
+    fn evaluate_sprites(&mut self) -> SpriteLine {
+        let mut line = [None; 256];
         let (small_size, large_size) = self.get_sprite_sizes();
-        let sprite_base = ((self.obsel & 0x07) as u16) << 13;  // Sprite tile base address
-        let sprite_name_offset = (((self.obsel >> 3) & 0x03) as u16) << 12;
+        let first = if self.oam_priority_rotation { (self.oamadd as usize >> 1) & 0x7F } else { 0 };
         
-        // Process sprites in reverse order (lower priority rendered first)
-        for sprite_idx in (0..128).rev() {
-            let oam_offset = sprite_idx * 4;
-            
-            // Read OAM entry
-            let x_low = self.oam[oam_offset] as i16;
-            let sprite_y = self.oam[oam_offset + 1];
-            let tile = self.oam[oam_offset + 2] as u16;
-            let attr = self.oam[oam_offset + 3];
-            
-            // Read high table for X MSB and size
-            let high_byte = self.oam[512 + (sprite_idx >> 2)];
-            let high_shift = (sprite_idx & 3) * 2;
-            let x_msb = ((high_byte >> high_shift) & 0x01) as i16;
-            let size_bit = ((high_byte >> (high_shift + 1)) & 0x01) != 0;
-            
-            let x = x_low | (x_msb << 8);
-            let x = if x >= 256 { x - 512 } else { x };  // Sign extend
-            
-            let palette = ((attr >> 1) & 0x07) + 8;  // Sprite palettes are 128-255
-            let sprite_priority = (attr >> 4) & 0x03;
-            let flip_h = (attr & 0x40) != 0;
-            let flip_v = (attr & 0x80) != 0;
-            
+        // Range evaluation
+        let mut in_range = Vec::with_capacity(32);
+        for sprite_idx in (0..128).map(|i| (first + i) & 0x7F) {
+            let (x, y, size_bit) = self.sprite_position(sprite_idx);
             let (width, height) = if size_bit { large_size } else { small_size };
-            
-            // Check if sprite intersects this scanline
-            let sprite_top = sprite_y as i16;
-            let sprite_bottom = sprite_top + height as i16;
-            
-            if y < sprite_top || y >= sprite_bottom {
+            let row = self.scanline.wrapping_sub(1).wrapping_sub(y as u16) & 0xFF;
+            let on_line = row < height as u16;
+            // X = -256 counts as on screen for the range check
+            let visible_x = x > -(width as i16) || x == -256;
+            if !on_line || !visible_x {
                 continue;
             }
-            
-            let row = (y - sprite_top) as u8;
-            let tile_row = if flip_v { height - 1 - row } else { row };
-            
-            // Render sprite pixels
-            for col in 0..width {
-                let screen_x = x + col as i16;
-                if screen_x < 0 || screen_x >= 256 {
+            if in_range.len() == 32 {
+                self.stat77 |= 0x40;
+                break;
+            }
+            in_range.push((sprite_idx, x, row as u8, width, height));
+        }
+        
+        // Tile fetches, from the last sprite in range to the first
+        let mut tiles = 0;
+        let mut slivers = Vec::new();
+        'fetch: for &(sprite_idx, x, row, width, height) in in_range.iter().rev() {
+            let attr = self.oam[sprite_idx * 4 + 3];
+            let flip_h = attr & 0x40 != 0;
+            let row = if attr & 0x80 != 0 { height - 1 - row } else { row };
+            for column in 0..width / 8 {
+                let tile_x = x + column as i16 * 8;
+                if !(-7..256).contains(&tile_x) {
                     continue;
                 }
-                
-                let tile_col = if flip_h { width - 1 - col } else { col };
-                
-                // Calculate tile address
-                let tile_x = tile_col >> 3;
-                let tile_y = tile_row >> 3;
-                let tiles_per_row = width >> 3;
-                let tile_offset = tile_y * tiles_per_row + tile_x;
-                let current_tile = tile + tile_offset as u16;
-                
-                let pixel_x = (tile_col & 7) as usize;
-                let pixel_y = (tile_row & 7) as usize;
-                
-                // Sprites are always 4bpp
-                let tile_addr = sprite_base + sprite_name_offset + (current_tile * 32);
-                let color_index = self.decode_4bpp(tile_addr, 0, pixel_x, pixel_y);
-                
+                if tiles == 34 {
+                    self.stat77 |= 0x80;
+                    break 'fetch;
+                }
+                tiles += 1;
+                let tile_column = if flip_h { width / 8 - 1 - column } else { column };
+                slivers.push((sprite_idx, tile_x, tile_column, row));
+            }
+        }
+        
+        // Later sprites first so the front of the list ends up on top
+        for (sprite_idx, tile_x, tile_column, row) in slivers {
+            let attr = self.oam[sprite_idx * 4 + 3];
+            let flip_h = attr & 0x40 != 0;
+            let palette = 0x80 | ((attr & 0x0E) << 3);
+            let priority = (attr >> 4) & 0x03;
+            let tile_addr = self.sprite_tile_address(sprite_idx, tile_column, row >> 3);
+            for pixel in 0..8 {
+                let screen_x = tile_x + pixel;
+                if !(0..256).contains(&screen_x) {
+                    continue;
+                }
+                let pixel_x = if flip_h { 7 - pixel } else { pixel } as usize;
+                let color_index = self.decode_4bpp(tile_addr, 0, pixel_x, (row & 7) as usize);
                 if color_index != 0 {
-                    let palette_index = (palette << 4) | color_index;
-                    let color = self.cgram[palette_index as usize];
-                    
-                    // Sprite priorities: 0-3, mapped to layer priorities
-                    let sprite_layer_priority = 12 + sprite_priority;
-                    
-                    if sprite_layer_priority >= priority[screen_x as usize] {
-                        scanline[screen_x as usize] = self.rgb555_to_rgba8888(color);
-                        priority[screen_x as usize] = sprite_layer_priority;
-                    }
+                    line[screen_x as usize] = Some((palette | color_index, priority));
                 }
             }
         }
+        line
+    }
+    
+    /// X coordinate (sign extended), Y coordinate and size bit of a sprite
+    fn sprite_position(&self, sprite_idx: usize) -> (i16, u8, bool) {
+        let high_bits = self.oam[512 + (sprite_idx >> 2)] >> ((sprite_idx & 3) * 2);
+        let x = self.oam[sprite_idx * 4] as i16 | ((high_bits & 0x01) as i16) << 8;
+        let x = if x >= 256 { x - 512 } else { x };
+        (x, self.oam[sprite_idx * 4 + 1], high_bits & 0x02 != 0)
+    }
+    
+    /// VRAM byte address of one 8x8 tile of a sprite
+    ///
+    /// Tiles of larger sprites are laid out in a 16x16 grid, wrapping within
+    /// their row and column. Names $100-$1FF come from the second table,
+    /// which OBSEL's name select places 1-4 8K-word pages after the first.
+    fn sprite_tile_address(&self, sprite_idx: usize, tile_column: u8, tile_row: u8) -> u16 {
+        let name = self.oam[sprite_idx * 4 + 2];
+        let table = self.oam[sprite_idx * 4 + 3] & 0x01;
+        let column = (name as u16 + tile_column as u16) & 0x0F;
+        let row = ((name >> 4) as u16 + tile_row as u16) & 0x0F;
+        
+        let base = ((self.obsel & 0x07) as u16) << 14;
+        let table_offset = if table != 0 { (((self.obsel >> 3) & 0x03) as u16 + 1) << 13 } else { 0 };
+        base.wrapping_add(table_offset).wrapping_add(((row << 4) | column) * 32)
+    }
+    
+    /// Merge the current line's sprites into a screen
+    fn render_sprites(&self, screen: &mut Screen, sprites: &SpriteLine, depths: [u8; 4], window: &WindowMask, pixels: Range<usize>) {
+        for (x, pixel) in sprites.iter().enumerate().take(pixels.end).skip(pixels.start) {
+            let Some((color_index, priority)) = *pixel else {
+                continue;
+            };
+            // Sprite priorities 0-3 sit between the background layers
+            let depth = depths[priority as usize];
+            if !window[x] && depth >= screen.depth[x] {
+                screen.color[x] = self.cgram[color_index as usize];
+                screen.depth[x] = depth;
+                // Palettes 0-3 (colors 128-191) don't take part in color math
+                screen.layer[x] = if color_index < 0xC0 { LAYER_OBJ_NO_MATH } else { LAYER_OBJ };
+            }
+        }
     }
     
     /// Get sprite sizes based on OBSEL register
@@ -745,10 +1242,59 @@ impl Ppu {
         self.vram[addr as usize] = value;
     }
     
+    /// VRAM word address of the data ports after VMAIN address remapping
+    ///
+    /// The remap modes rotate the low 8, 9 or 10 bits left by 3, so that
+    /// consecutive writes fill one row of several 2, 4 or 8bpp tiles.
+    fn vram_word_address(&self) -> u16 {
+        let addr = self.vram_address;
+        let remapped = match self.vram_remap {
+            0 => addr,
+            1 => (addr & 0xFF00) | ((addr & 0x00E0) >> 5) | ((addr & 0x001F) << 3),
+            2 => (addr & 0xFE00) | ((addr & 0x01C0) >> 6) | ((addr & 0x003F) << 3),
+            _ => (addr & 0xFC00) | ((addr & 0x0380) >> 7) | ((addr & 0x007F) << 3),
+        };
+        remapped & 0x7FFF
+    }
+    
+    /// Load the VRAM read buffer from the current (remapped) address
+    fn prefetch_vram(&mut self) {
+        let addr = self.vram_word_address() * 2;
+        self.vram_read_buffer = u16::from_le_bytes([self.vram[addr as usize], self.vram[addr as usize + 1]]);
+    }
+    
+    /// Whether the PPU is drawing (neither VBlank nor forced blank), which
+    /// locks VRAM and OAM against CPU writes
+    fn in_active_display(&self) -> bool {
+        !self.vblank && !self.is_forced_blank()
+    }
+    
+    /// Resolve the VRAM/CGRAM/OAM byte the next access to a data port touches
+    ///
+    /// Used by the debugger to translate $2104/$2118/$2119/$2122 writes and
+    /// $2138-$213B reads into addresses within the PPU memories.
+    pub(crate) fn data_port_target(&self, addr: u16) -> Option<(AddressSpace, u32)> {
+        match addr {
+            0x2104 | 0x2138 if self.oam_address < 544 => {
+                Some((AddressSpace::Oam, self.oam_address as u32))
+            },
+            0x2118 | 0x2139 | 0x2119 | 0x213A => {
+                let high = matches!(addr, 0x2119 | 0x213A) as u32;
+                Some((AddressSpace::Vram, self.vram_word_address() as u32 * 2 + high))
+            },
+            0x2122 | 0x213B => {
+                let cgram_addr = (self.cgram_address as u32) * 2 + self.cgram_high_byte as u32;
+                Some((AddressSpace::Cgram, cgram_addr))
+            },
+            _ => None,
+        }
+    }
+    
     // PPU Register Read/Write Functions
     
     /// Write to PPU register
     pub fn write_register(&mut self, addr: u16, value: u8) {
+        self.catch_up();
         match addr {
             0x2100 => self.inidisp = value,
             0x2101 => self.obsel = value,
@@ -759,32 +1305,39 @@ impl Ppu {
             0x2103 => {
                 self.oamadd = (self.oamadd & 0x00FF) | ((value as u16 & 0x01) << 8);
                 self.oam_address = self.oamadd;
+                self.oam_priority_rotation = value & 0x80 != 0;
                 self.oam_high_byte = false;
             },
             0x2104 => {
-                // OAM data write
+                // OAM data write, ignored during active display
                 if self.oam_address < 544 {
-                    self.oam[self.oam_address as usize] = value;
+                    if !self.in_active_display() {
+                        self.oam[self.oam_address as usize] = value;
+                    }
                     self.oam_address = (self.oam_address + 1) & 0x21F;
                 }
             },
             0x2105 => self.bgmode = value,
             0x2106 => self.mosaic = value,
             0x2107 => {
-                // BG1 tilemap address and size (bits 7-2 → VRAM address bits 15-10)
-                self.bg1_tilemap_addr = (value as u16 & 0xFC) << 8;
+                // BG1 tilemap address (bits 6-2 → VRAM word address bits 14-10) and size
+                self.bg1_tilemap_addr = (value as u16 & 0x7C) << 9;
+                self.bg_tilemap_size[0] = value & 0x03;
             },
             0x2108 => {
-                // BG2 tilemap address and size (bits 7-2 → VRAM address bits 15-10)
-                self.bg2_tilemap_addr = (value as u16 & 0xFC) << 8;
+                // BG2 tilemap address (bits 6-2 → VRAM word address bits 14-10) and size
+                self.bg2_tilemap_addr = (value as u16 & 0x7C) << 9;
+                self.bg_tilemap_size[1] = value & 0x03;
             },
             0x2109 => {
-                // BG3 tilemap address and size (bits 7-2 → VRAM address bits 15-10)
-                self.bg3_tilemap_addr = (value as u16 & 0xFC) << 8;
+                // BG3 tilemap address (bits 6-2 → VRAM word address bits 14-10) and size
+                self.bg3_tilemap_addr = (value as u16 & 0x7C) << 9;
+                self.bg_tilemap_size[2] = value & 0x03;
             },
             0x210A => {
-                // BG4 tilemap address and size (bits 7-2 → VRAM address bits 15-10)
-                self.bg4_tilemap_addr = (value as u16 & 0xFC) << 8;
+                // BG4 tilemap address (bits 6-2 → VRAM word address bits 14-10) and size
+                self.bg4_tilemap_addr = (value as u16 & 0x7C) << 9;
+                self.bg_tilemap_size[3] = value & 0x03;
             },
             0x210B => {
                 // BG1 and BG2 character data address
@@ -801,12 +1354,17 @@ impl Ppu {
                 let prev = self.bg_scroll_latch;
                 self.bg1_hscroll = ((value as u16) << 8) | (prev as u16);
                 self.bg_scroll_latch = value;
+                // Mode 7 scroll shares the address but uses the Mode 7 latch
+                self.m7hofs = ((value as i16) << 8) | (self.m7_latch as i16);
+                self.m7_latch = value;
             },
             0x210E => {
                 // BG1 vertical scroll
                 let prev = self.bg_scroll_latch;
                 self.bg1_vscroll = ((value as u16) << 8) | (prev as u16);
                 self.bg_scroll_latch = value;
+                self.m7vofs = ((value as i16) << 8) | (self.m7_latch as i16);
+                self.m7_latch = value;
             },
             0x210F => {
                 // BG2 horizontal scroll
@@ -852,37 +1410,31 @@ impl Ppu {
                     2 | 3 => 128,
                     _ => 1,
                 };
-                self.vram_mapping = if (value & 0x04) != 0 {
+                self.vram_mapping = if (value & 0x80) != 0 {
                     VramMapping::Increment1
                 } else {
                     VramMapping::Increment0
                 };
+                self.vram_remap = (value >> 2) & 0x03;
             },
             0x2116 => {
-                // VRAM address low
+                // VRAM address low; the read buffer prefetches the new word
                 self.vram_address = (self.vram_address & 0xFF00) | value as u16;
+                self.prefetch_vram();
             },
             0x2117 => {
                 // VRAM address high
                 self.vram_address = (self.vram_address & 0x00FF) | ((value as u16) << 8);
+                self.prefetch_vram();
             },
-            0x2118 => {
-                // VRAM data write low
-                let addr = (self.vram_address as usize) * 2;
-                if addr < 0x10000 {
-                    self.write_vram(addr as u16, value);
+            0x2118 | 0x2119 => {
+                // VRAM data write low/high, ignored during active display
+                let high = addr == 0x2119;
+                if !self.in_active_display() {
+                    let addr = self.vram_word_address() * 2 + high as u16;
+                    self.write_vram(addr, value);
                 }
-                if matches!(self.vram_mapping, VramMapping::Increment0) {
-                    self.vram_address = self.vram_address.wrapping_add(self.vram_increment);
-                }
-            },
-            0x2119 => {
-                // VRAM data write high
-                let addr = (self.vram_address as usize) * 2 + 1;
-                if addr < 0x10000 {
-                    self.write_vram(addr as u16, value);
-                }
-                if matches!(self.vram_mapping, VramMapping::Increment1) {
+                if high == matches!(self.vram_mapping, VramMapping::Increment1) {
                     self.vram_address = self.vram_address.wrapping_add(self.vram_increment);
                 }
             },
@@ -934,8 +1486,12 @@ impl Ppu {
                     self.cgram_latch = value;
                     self.cgram_high_byte = true;
                 } else {
-                    let color = ((value as u16) << 8) | (self.cgram_latch as u16);
-                    self.cgram[self.cgram_address as usize] = color;
+                    // Only writable outside the visible part of a line
+                    let drawing = (FIRST_PIXEL_DOT..FIRST_PIXEL_DOT + 256).contains(&self.dot);
+                    if !(self.in_active_display() && drawing) {
+                        let color = ((value as u16) << 8) | (self.cgram_latch as u16);
+                        self.cgram[self.cgram_address as usize] = color;
+                    }
                     self.cgram_address = self.cgram_address.wrapping_add(1);
                     self.cgram_high_byte = false;
                 }
@@ -983,18 +1539,24 @@ impl Ppu {
                     self.fixed_color[2] = value & 0x1F;  // B
                 }
             },
+            0x2133 => self.setini = value,  // Screen mode/video select
             _ => {
                 // Unimplemented register
             }
         }
     }
     
+    /// Signed product of M7A and the last byte written to M7B
+    fn multiply_result(&self) -> i32 {
+        self.m7a as i32 * (self.m7b >> 8) as i8 as i32
+    }
+    
     /// Read from PPU register
     pub fn read_register(&mut self, addr: u16) -> u8 {
         match addr {
-            0x2134 => 0,  // MPYL - Multiplication result low
-            0x2135 => 0,  // MPYM - Multiplication result middle
-            0x2136 => 0,  // MPYH - Multiplication result high
+            0x2134 => self.multiply_result() as u8,          // MPYL - Multiplication result low
+            0x2135 => (self.multiply_result() >> 8) as u8,   // MPYM - Multiplication result middle
+            0x2136 => (self.multiply_result() >> 16) as u8,  // MPYH - Multiplication result high
             0x2137 => {
                 // SLHV - Software latch for H/V counters
                 self.ophct = self.dot;
@@ -1011,28 +1573,15 @@ impl Ppu {
                 self.oam_address = (self.oam_address + 1) & 0x21F;
                 value
             },
-            0x2139 => {
-                // VMDATALREAD - VRAM data read low
-                let addr = (self.vram_address as usize) * 2;
-                let value = if addr < 0x10000 {
-                    self.read_vram(addr as u16)
-                } else {
-                    0
-                };
-                if matches!(self.vram_mapping, VramMapping::Increment0) {
-                    self.vram_address = self.vram_address.wrapping_add(self.vram_increment);
-                }
-                value
-            },
-            0x213A => {
-                // VMDATAHREAD - VRAM data read high
-                let addr = (self.vram_address as usize) * 2 + 1;
-                let value = if addr < 0x10000 {
-                    self.read_vram(addr as u16)
-                } else {
-                    0
-                };
-                if matches!(self.vram_mapping, VramMapping::Increment1) {
+            0x2139 | 0x213A => {
+                // VMDATALREAD/VMDATAHREAD - VRAM data read
+                //
+                // Reads return the prefetched word; the access that increments
+                // the address refills it from the address before incrementing.
+                let high = addr == 0x213A;
+                let value = if high { (self.vram_read_buffer >> 8) as u8 } else { self.vram_read_buffer as u8 };
+                if high == matches!(self.vram_mapping, VramMapping::Increment1) {
+                    self.prefetch_vram();
                     self.vram_address = self.vram_address.wrapping_add(self.vram_increment);
                 }
                 value
@@ -1064,25 +1613,27 @@ impl Ppu {
                 // STAT77 - PPU status flag and version
                 // Bit 7: Time Over Flag, Bit 6: Range Over Flag
                 // Bits 0-4: PPU1 version (5C77)
-                0x01
+                self.stat77 | 0x01
             },
             0x213F => {
                 // STAT78 - PPU status flag and version
                 // Bit 7: Interlace field, Bit 6: External latch
                 // Bits 0-4: PPU2 version (5C78)
                 let mut value = 0x03;
-                if self.vblank {
+                if self.field {
                     value |= 0x80;
                 }
-                if self.hblank {
-                    value |= 0x40;
-                }
                 value
             },
             _ => 0,
         }
     }
     
+    /// Get current dot within the scanline
+    pub fn get_dot(&self) -> u16 {
+        self.dot
+    }
+    
     /// Check if in VBlank
     pub fn in_vblank(&self) -> bool {
         self.vblank
@@ -1105,6 +1656,7 @@ impl Ppu {
         self.vblank = true;
         self.hblank = false;
         self.frame_complete = false;
+        self.line_active = false;
         self.inidisp = 0x80;  // Force blank
         
         // Clear framebuffer to black
@@ -1116,6 +1668,31 @@ impl Ppu {
 
 // Helper methods for bulk loading (used by emulator)
 impl Ppu {
+    pub fn vram(&self) -> &[u8] {
+        &self.vram
+    }
+    
+    pub fn vram_mut(&mut self) -> &mut [u8] {
+        &mut self.vram
+    }
+    
+    pub fn oam(&self) -> &[u8] {
+        &self.oam
+    }
+    
+    pub fn oam_mut(&mut self) -> &mut [u8] {
+        &mut self.oam
+    }
+    
+    /// Palette entries (BGR555)
+    pub fn cgram(&self) -> &[u16] {
+        &self.cgram
+    }
+    
+    pub fn cgram_mut(&mut self) -> &mut [u16] {
+        &mut self.cgram
+    }
+    
     /// Write data to VRAM (for bulk loading)
     pub fn write_vram_wasm(&mut self, addr: u16, data: &[u8]) {
         for (i, &byte) in data.iter().enumerate() {
@@ -1152,3 +1729,942 @@ impl Default for Ppu {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bitplane data for an 8x8 tile filled with `color`
+    fn solid_tile(bpp: u8, color: u8) -> Vec<u8> {
+        let mut tile = vec![0; bpp as usize * 8];
+        for plane in 0..bpp as usize {
+            let byte = if color & (1 << plane) != 0 { 0xFF } else { 0 };
+            for row in 0..8 {
+                tile[(plane / 2) * 16 + row * 2 + (plane & 1)] = byte;
+            }
+        }
+        tile
+    }
+
+    /// Store tilemap entry `entry` at (col, row) of a layer's 32x32 map
+    fn set_tile(ppu: &mut Ppu, tilemap_addr: u16, col: u16, row: u16, entry: u16) {
+        ppu.write_vram_wasm(tilemap_addr + (row * 32 + col) * 2, &entry.to_le_bytes());
+    }
+
+    /// PPU with display enabled and a distinct color in every CGRAM entry
+    fn create_ppu(bgmode: u8, tm: u8) -> Ppu {
+        let mut ppu = Ppu::new();
+        let colors: Vec<u16> = (0..256).collect();
+        ppu.write_cgram_wasm(0, &colors);
+        ppu.write_register(0x2100, 0x0F);
+        ppu.write_register(0x2105, bgmode);
+        ppu.write_register(0x212C, tm);
+        ppu
+    }
+
+    /// Render line `y` and return its framebuffer row
+    fn render_line(ppu: &mut Ppu, y: u16) -> Vec<u32> {
+        ppu.scanline = y;
+        ppu.render_scanline();
+        let offset = (y as usize - 1) * 512;
+        let width = if ppu.frame_hires { 512 } else { 256 };
+        ppu.framebuffer[offset..offset + width].to_vec()
+    }
+
+    fn color(ppu: &Ppu, index: u16) -> u32 {
+        ppu.rgb555_to_rgba8888(index)
+    }
+
+    #[test]
+    fn test_backdrop() {
+        let mut ppu = create_ppu(1, 0);
+        ppu.write_cgram_wasm(0, &[0x001F]);
+        assert!(render_line(&mut ppu, 10).iter().all(|&p| p == color(&ppu, 0x001F)));
+    }
+
+    #[test]
+    fn test_mode3_8bpp() {
+        let mut ppu = create_ppu(3, 0x01);
+        ppu.write_register(0x210B, 0x01); // BG1 characters at $2000
+        ppu.write_vram_wasm(0x2000 + 64, &solid_tile(8, 0x81));
+        let map = ppu.bg1_tilemap_addr;
+        set_tile(&mut ppu, map, 0, 0, 0x0001);
+
+        let line = render_line(&mut ppu, 3);
+        assert_eq!(line[0], color(&ppu, 0x81));
+        assert_eq!(line[7], color(&ppu, 0x81));
+        assert_eq!(line[8], color(&ppu, 0));
+    }
+
+    #[test]
+    fn test_mode0_palette_per_layer() {
+        let mut ppu = create_ppu(0, 0x04);
+        ppu.write_register(0x2109, 0x60); // BG3 tilemap
+        ppu.write_register(0x210C, 0x01); // BG3 characters at $2000
+        ppu.write_vram_wasm(0x2000 + 16, &solid_tile(2, 3));
+        let map = ppu.bg3_tilemap_addr;
+        set_tile(&mut ppu, map, 0, 0, 0x0001 | (2 << 10));
+
+        // BG3 uses colors 64-95: palette 2, color 3
+        assert_eq!(render_line(&mut ppu, 1)[0], color(&ppu, 64 + 2 * 4 + 3));
+    }
+
+    #[test]
+    fn test_mode1_bg3_priority() {
+        let mut ppu = create_ppu(1, 0x05);
+        ppu.write_register(0x2107, 0x60);
+        ppu.write_register(0x2109, 0x68);
+        ppu.write_register(0x210B, 0x01);
+        ppu.write_register(0x210C, 0x02);
+        ppu.write_vram_wasm(0x2000 + 32, &solid_tile(4, 1));
+        ppu.write_vram_wasm(0x4000 + 16, &solid_tile(2, 2));
+        let (bg1_map, bg3_map) = (ppu.bg1_tilemap_addr, ppu.bg3_tilemap_addr);
+        set_tile(&mut ppu, bg1_map, 0, 0, 0x0001 | 0x2000);
+        set_tile(&mut ppu, bg3_map, 0, 0, 0x0001 | 0x2000);
+
+        // High priority BG1 normally covers BG3
+        assert_eq!(render_line(&mut ppu, 1)[0], color(&ppu, 1));
+        // With the BG3 priority bit, high priority BG3 tiles come to the front
+        ppu.write_register(0x2105, 0x09);
+        assert_eq!(render_line(&mut ppu, 1)[0], color(&ppu, 2));
+    }
+
+    #[test]
+    fn test_mode2_offset_per_tile() {
+        let mut ppu = create_ppu(2, 0x01);
+        ppu.write_register(0x2107, 0x60);
+        ppu.write_register(0x2109, 0x68);
+        ppu.write_register(0x210B, 0x01);
+        ppu.write_vram_wasm(0x2000 + 32, &solid_tile(4, 1));
+        ppu.write_vram_wasm(0x2000 + 64, &solid_tile(4, 2));
+        let (bg1_map, bg3_map) = (ppu.bg1_tilemap_addr, ppu.bg3_tilemap_addr);
+        for col in 0..32 {
+            set_tile(&mut ppu, bg1_map, col, 0, 0x0001);
+        }
+        // Row 4 holds tile 2 in column 6
+        set_tile(&mut ppu, bg1_map, 6, 4, 0x0002);
+
+        // Screen column 3 scrolls BG1 down 32 lines and right by 24 pixels
+        set_tile(&mut ppu, bg3_map, 2, 0, 0x2000 | 24);
+        set_tile(&mut ppu, bg3_map, 2, 1, 0x2000 | 32);
+        // BG2-only entries leave BG1 alone
+        set_tile(&mut ppu, bg3_map, 3, 0, 0x4000 | 0x100);
+
+        let line = render_line(&mut ppu, 1);
+        assert_eq!(line[2 * 8], color(&ppu, 1));
+        assert_eq!(line[3 * 8], color(&ppu, 2));
+        assert_eq!(line[4 * 8], color(&ppu, 1));
+    }
+
+    #[test]
+    fn test_mode6_offset_per_tile() {
+        let mut ppu = create_ppu(6, 0x01);
+        ppu.write_register(0x212D, 0x01);
+        ppu.write_register(0x2107, 0x60);
+        ppu.write_register(0x2109, 0x68);
+        ppu.write_register(0x210B, 0x01);
+        for (tile, color) in [(1, 1), (2, 1), (3, 2), (4, 2)] {
+            ppu.write_vram_wasm(0x2000 + tile * 32, &solid_tile(4, color));
+        }
+        let (bg1_map, bg3_map) = (ppu.bg1_tilemap_addr, ppu.bg3_tilemap_addr);
+        for col in 0..32 {
+            set_tile(&mut ppu, bg1_map, col, 0, 0x0001);
+        }
+        set_tile(&mut ppu, bg1_map, 6, 4, 0x0003);
+
+        // Entries and scroll values are in lo-res pixels: screen column 3
+        // scrolls BG1 right by 24 (48 hi-res pixels) and down 32 lines
+        set_tile(&mut ppu, bg3_map, 2, 0, 0x2000 | 24);
+        set_tile(&mut ppu, bg3_map, 2, 1, 0x2000 | 32);
+
+        let line = render_line(&mut ppu, 1);
+        assert_eq!(line[2 * 16 + 15], color(&ppu, 1));
+        assert_eq!(line[3 * 16], color(&ppu, 2));
+        assert_eq!(line[3 * 16 + 15], color(&ppu, 2));
+        assert_eq!(line[4 * 16], color(&ppu, 1));
+    }
+
+    #[test]
+    fn test_mode5_hires_tiles() {
+        let mut ppu = create_ppu(5, 0x01);
+        ppu.write_register(0x2107, 0x60);
+        ppu.write_register(0x210B, 0x01);
+        ppu.write_vram_wasm(0x2000 + 32, &solid_tile(4, 1));
+        ppu.write_vram_wasm(0x2000 + 64, &solid_tile(4, 2));
+        let map = ppu.bg1_tilemap_addr;
+        set_tile(&mut ppu, map, 0, 0, 0x0001);
+        ppu.write_register(0x212D, 0x01);
+
+        // Tiles are 16 hi-res pixels wide, alternating sub and main screen
+        let line = render_line(&mut ppu, 1);
+        assert_eq!(line.len(), 512);
+        assert_eq!(line[0], color(&ppu, 1));
+        assert_eq!(line[7], color(&ppu, 1));
+        assert_eq!(line[8], color(&ppu, 2));
+        assert_eq!(line[15], color(&ppu, 2));
+        assert_eq!(line[16], color(&ppu, 0));
+
+        // Without BG1 on the sub screen, even columns show the fixed color
+        ppu.write_register(0x212D, 0x00);
+        ppu.write_register(0x2132, 0x20 | 7);
+        let line = render_line(&mut ppu, 1);
+        assert_eq!(line[0], color(&ppu, 7));
+        assert_eq!(line[1], color(&ppu, 1));
+    }
+
+    /// Mode 1 with a solid BG1 of color 1 on the main screen
+    fn create_windowed_ppu() -> Ppu {
+        let mut ppu = create_ppu(1, 0x01);
+        ppu.write_register(0x2107, 0x60);
+        ppu.write_register(0x210B, 0x01);
+        ppu.write_vram_wasm(0x2000 + 32, &solid_tile(4, 1));
+        let map = ppu.bg1_tilemap_addr;
+        for col in 0..32 {
+            set_tile(&mut ppu, map, col, 0, 0x0001);
+        }
+        ppu.write_register(0x2126, 16);
+        ppu.write_register(0x2127, 31);
+        ppu.write_register(0x2128, 24);
+        ppu.write_register(0x2129, 47);
+        ppu
+    }
+
+    /// Which of the pixels 0, 16, 24, 40 and 48 show BG1
+    fn visible(ppu: &mut Ppu) -> [bool; 5] {
+        let line = render_line(ppu, 1);
+        let bg = color(ppu, 1);
+        [0, 16, 24, 40, 48].map(|x| line[x] == bg)
+    }
+
+    #[test]
+    fn test_window_single() {
+        let mut ppu = create_windowed_ppu();
+        ppu.write_register(0x2123, 0x02); // BG1: window 1
+        assert_eq!(visible(&mut ppu), [true; 5]);
+
+        // Masking only applies once enabled in TMW
+        ppu.write_register(0x212E, 0x01);
+        assert_eq!(visible(&mut ppu), [true, false, false, true, true]);
+
+        ppu.write_register(0x2123, 0x03); // inverted
+        assert_eq!(visible(&mut ppu), [false, true, true, false, false]);
+    }
+
+    #[test]
+    fn test_window_logic() {
+        let mut ppu = create_windowed_ppu();
+        ppu.write_register(0x2123, 0x0A); // BG1: windows 1 and 2
+        ppu.write_register(0x212E, 0x01);
+
+        ppu.write_register(0x212A, 0x00); // OR
+        assert_eq!(visible(&mut ppu), [true, false, false, false, true]);
+        ppu.write_register(0x212A, 0x01); // AND
+        assert_eq!(visible(&mut ppu), [true, true, false, true, true]);
+        ppu.write_register(0x212A, 0x02); // XOR
+        assert_eq!(visible(&mut ppu), [true, false, true, false, true]);
+        ppu.write_register(0x212A, 0x03); // XNOR
+        assert_eq!(visible(&mut ppu), [false, true, false, true, false]);
+    }
+
+    #[test]
+    fn test_window_sprites() {
+        let mut ppu = create_windowed_ppu();
+        ppu.write_register(0x212C, 0x10);
+        ppu.write_register(0x2101, 0x02); // sprites at word $4000
+        ppu.write_vram_wasm(0x8000 + 32, &solid_tile(4, 1));
+        park_sprites(&mut ppu);
+        set_sprite(&mut ppu, 0, 16, 0, 1, 0, false);
+        let sprite_color = color(&ppu, 128 + 1);
+
+        assert_eq!(render_line(&mut ppu, 1)[16], sprite_color);
+        ppu.write_register(0x2125, 0x02); // OBJ: window 1
+        ppu.write_register(0x212E, 0x10);
+        assert_ne!(render_line(&mut ppu, 1)[16], sprite_color);
+        assert_ne!(render_line(&mut ppu, 1)[23], sprite_color);
+    }
+
+    #[test]
+    fn test_color_window_clip() {
+        let mut ppu = create_windowed_ppu();
+        ppu.write_register(0x2125, 0x20); // color: window 1
+        ppu.write_register(0x2130, 0x80); // clip inside the color window
+        let line = render_line(&mut ppu, 1);
+        assert_eq!(line[0], color(&ppu, 1));
+        assert_eq!(line[16], 0xFF000000);
+
+        ppu.write_register(0x2130, 0x40); // clip outside
+        let line = render_line(&mut ppu, 1);
+        assert_eq!(line[0], 0xFF000000);
+        assert_eq!(line[16], color(&ppu, 1));
+    }
+
+    /// BG1 on the main screen (color 1 = red 20) and BG2 on the sub screen
+    /// (color 2 = red 6, blue 8) over its left half
+    fn create_math_ppu() -> Ppu {
+        let mut ppu = create_windowed_ppu();
+        ppu.write_register(0x2108, 0x68);
+        ppu.write_register(0x210B, 0x11);
+        ppu.write_register(0x212D, 0x02);
+        ppu.write_vram_wasm(0x2000 + 64, &solid_tile(4, 2));
+        let map = ppu.bg2_tilemap_addr;
+        for col in 0..16 {
+            set_tile(&mut ppu, map, col, 0, 0x0002);
+        }
+        ppu.write_cgram_wasm(0, &[0x0000, 20, 6 | (8 << 10)]);
+        ppu.write_register(0x2132, 0x20 | 3); // fixed color red 3
+        ppu
+    }
+
+    fn pixel(ppu: &mut Ppu, x: usize) -> u32 {
+        render_line(ppu, 1)[x]
+    }
+
+    #[test]
+    fn test_color_math_fixed_color() {
+        let mut ppu = create_math_ppu();
+        assert_eq!(pixel(&mut ppu, 0), color(&ppu, 20));
+
+        ppu.write_register(0x2131, 0x01); // add to BG1
+        assert_eq!(pixel(&mut ppu, 0), color(&ppu, 23));
+        ppu.write_register(0x2131, 0x81); // subtract
+        assert_eq!(pixel(&mut ppu, 0), color(&ppu, 17));
+        ppu.write_register(0x2131, 0xC1); // subtract and halve
+        assert_eq!(pixel(&mut ppu, 0), color(&ppu, 8));
+
+        // Sums saturate
+        ppu.write_register(0x2132, 0x20 | 31);
+        ppu.write_register(0x2131, 0x01);
+        assert_eq!(pixel(&mut ppu, 0), color(&ppu, 31));
+
+        // Layers without their CGADSUB bit are unaffected
+        ppu.write_register(0x2131, 0x02);
+        assert_eq!(pixel(&mut ppu, 0), color(&ppu, 20));
+    }
+
+    #[test]
+    fn test_color_math_sub_screen() {
+        let mut ppu = create_math_ppu();
+        ppu.write_register(0x2130, 0x02); // sub screen operand
+        ppu.write_register(0x2131, 0x41); // add BG1, halved
+
+        assert_eq!(pixel(&mut ppu, 0), color(&ppu, 13 | (4 << 10)));
+        // Over the sub screen backdrop, the fixed color is added unhalved
+        assert_eq!(pixel(&mut ppu, 200), color(&ppu, 23));
+    }
+
+    #[test]
+    fn test_color_math_backdrop_and_regions() {
+        let mut ppu = create_math_ppu();
+        ppu.write_register(0x212C, 0x00);
+        ppu.write_register(0x2131, 0x20); // add to backdrop
+        assert_eq!(pixel(&mut ppu, 0), color(&ppu, 3));
+
+        // Prevent math inside the color window (window 1: pixels 16-31)
+        ppu.write_register(0x2125, 0x20);
+        ppu.write_register(0x2130, 0x20);
+        assert_eq!(pixel(&mut ppu, 0), color(&ppu, 3));
+        assert_eq!(pixel(&mut ppu, 16), color(&ppu, 0));
+
+        // Clip to black everywhere still adds the fixed color
+        ppu.write_register(0x212C, 0x01);
+        ppu.write_register(0x2131, 0x01);
+        ppu.write_register(0x2130, 0xC0);
+        assert_eq!(pixel(&mut ppu, 0), color(&ppu, 3));
+    }
+
+    /// 4bpp tile whose pixels use color (row + 1) or (column + 1)
+    fn gradient_tile(by_row: bool) -> Vec<u8> {
+        let mut tile = vec![0; 32];
+        for row in 0..8 {
+            for col in 0..8 {
+                let color = if by_row { row + 1 } else { col + 1 };
+                for plane in 0..4 {
+                    if color & (1 << plane) != 0 {
+                        tile[(plane / 2) * 16 + row * 2 + (plane & 1)] |= 0x80 >> col;
+                    }
+                }
+            }
+        }
+        tile
+    }
+
+    /// Mode 1 with BG1 filled with `tile`
+    fn create_mosaic_ppu(tile: &[u8]) -> Ppu {
+        let mut ppu = create_ppu(1, 0x01);
+        ppu.write_register(0x2107, 0x60);
+        ppu.write_register(0x210B, 0x01);
+        ppu.write_vram_wasm(0x2000 + 32, tile);
+        let map = ppu.bg1_tilemap_addr;
+        for entry in 0..32 * 32 {
+            set_tile(&mut ppu, map, entry % 32, entry / 32, 0x0001);
+        }
+        ppu
+    }
+
+    #[test]
+    fn test_mosaic_horizontal() {
+        let mut ppu = create_mosaic_ppu(&gradient_tile(false));
+        ppu.write_register(0x2106, 0x31); // 4x4 blocks on BG1
+        let line = render_line(&mut ppu, 1);
+        assert_eq!(line[3], color(&ppu, 1));
+        assert_eq!(line[4], color(&ppu, 5));
+        assert_eq!(line[7], color(&ppu, 5));
+
+        // Other layers' bits leave BG1 alone
+        ppu.write_register(0x2106, 0x32);
+        assert_eq!(render_line(&mut ppu, 1)[3], color(&ppu, 4));
+    }
+
+    #[test]
+    fn test_mosaic_vertical() {
+        let mut ppu = create_mosaic_ppu(&gradient_tile(true));
+        ppu.write_register(0x2106, 0xF1); // 16x16 blocks
+        let mut lines = [0; 23];
+        for (y, pixel) in lines.iter_mut().enumerate().skip(1) {
+            if y == 10 {
+                ppu.write_register(0x2106, 0x31);
+            }
+            *pixel = render_line(&mut ppu, y as u16)[0];
+        }
+        // Lines 1-16 repeat line 1; the new size applies from the next block
+        assert_eq!(lines[9], color(&ppu, 2));
+        assert_eq!(lines[16], color(&ppu, 2));
+        assert_eq!(lines[17], color(&ppu, 2));
+        assert_eq!(lines[20], color(&ppu, 2));
+        assert_eq!(lines[21], color(&ppu, 6));
+
+        // Blocks restart at the first visible line of each frame, even in
+        // the middle of the last frame's block
+        ppu.write_register(0x2106, 0x11);
+        assert_eq!(render_line(&mut ppu, 1)[0], color(&ppu, 2));
+        assert_eq!(render_line(&mut ppu, 2)[0], color(&ppu, 2));
+        assert_eq!(render_line(&mut ppu, 3)[0], color(&ppu, 4));
+    }
+
+    /// Mode 7 with an identity matrix; map entry (1, 0) is tile 1, whose
+    /// pixels are all `pixel`, and tile 0 is all color 9
+    fn create_mode7_ppu(pixel: u8) -> Ppu {
+        let mut ppu = create_ppu(7, 0x01);
+        for (register, value) in [(0x211B, 0x0100u16), (0x211E, 0x0100)] {
+            ppu.write_register(register, value as u8);
+            ppu.write_register(register, (value >> 8) as u8);
+        }
+        ppu.write_vram_wasm(2, &[1]);
+        for i in 0..64 {
+            ppu.write_vram_wasm(i * 2 + 1, &[9]);
+            ppu.write_vram_wasm((64 + i) * 2 + 1, &[pixel]);
+        }
+        ppu
+    }
+
+    fn write_mode7_scroll(ppu: &mut Ppu, register: u16, value: i16) {
+        ppu.write_register(register, value as u8);
+        ppu.write_register(register, (value >> 8) as u8);
+    }
+
+    #[test]
+    fn test_mode7_scroll_and_flip() {
+        let mut ppu = create_mode7_ppu(5);
+        let line = render_line(&mut ppu, 1);
+        assert_eq!(line[7], color(&ppu, 9));
+        assert_eq!(line[8], color(&ppu, 5));
+        assert_eq!(line[16], color(&ppu, 9));
+
+        write_mode7_scroll(&mut ppu, 0x210D, 4);
+        let line = render_line(&mut ppu, 1);
+        assert_eq!(line[4], color(&ppu, 5));
+        assert_eq!(line[12], color(&ppu, 9));
+
+        // Horizontal flip mirrors the screen around its center
+        write_mode7_scroll(&mut ppu, 0x210D, 0);
+        ppu.write_register(0x211A, 0x01);
+        let line = render_line(&mut ppu, 1);
+        assert_eq!(line[255 - 8], color(&ppu, 5));
+        assert_eq!(line[255 - 7], color(&ppu, 9));
+    }
+
+    #[test]
+    fn test_mode7_screen_over() {
+        let mut ppu = create_mode7_ppu(5);
+        write_mode7_scroll(&mut ppu, 0x210D, -8);
+
+        // Wraps around to map column 127 (tile 0)
+        assert_eq!(render_line(&mut ppu, 1)[0], color(&ppu, 9));
+        assert_eq!(render_line(&mut ppu, 1)[16], color(&ppu, 5));
+        // Transparent outside the map
+        ppu.write_register(0x211A, 0x80);
+        assert_eq!(render_line(&mut ppu, 1)[0], color(&ppu, 0));
+        // Tile 0 outside the map
+        ppu.write_vram_wasm(2 * 127, &[1]);
+        ppu.write_register(0x211A, 0x00);
+        assert_eq!(render_line(&mut ppu, 1)[0], color(&ppu, 5));
+        ppu.write_register(0x211A, 0xC0);
+        assert_eq!(render_line(&mut ppu, 1)[0], color(&ppu, 9));
+    }
+
+    #[test]
+    fn test_mode7_extbg() {
+        let mut ppu = create_mode7_ppu(0x85);
+        ppu.write_register(0x212C, 0x02);
+        assert_eq!(render_line(&mut ppu, 1)[8], color(&ppu, 0));
+
+        // BG2 shows the low 7 bits
+        ppu.write_register(0x2133, 0x40);
+        assert_eq!(render_line(&mut ppu, 1)[8], color(&ppu, 5));
+        // ... in front of BG1 when bit 7 is set
+        ppu.write_register(0x212C, 0x03);
+        assert_eq!(render_line(&mut ppu, 1)[8], color(&ppu, 5));
+        assert_eq!(render_line(&mut ppu, 1)[0], color(&ppu, 9));
+    }
+
+    #[test]
+    fn test_multiply() {
+        let mut ppu = Ppu::new();
+        ppu.write_register(0x211B, 0x34);
+        ppu.write_register(0x211B, 0x12);
+        ppu.write_register(0x211C, 0xFE);
+        let result = [0x2134, 0x2135, 0x2136].map(|addr| ppu.read_register(addr));
+        // 0x1234 * -2 = -0x2468
+        assert_eq!(result, [0x98, 0xDB, 0xFF]);
+    }
+
+    /// Move every sprite below the visible area
+    fn park_sprites(ppu: &mut Ppu) {
+        let mut oam = vec![0u8; 544];
+        for sprite in oam.chunks_mut(4).take(128) {
+            sprite[1] = 0xF0;
+        }
+        ppu.write_oam_wasm(0, &oam);
+    }
+
+    fn set_sprite(ppu: &mut Ppu, idx: u16, x: i16, y: u8, name: u8, attr: u8, large: bool) {
+        ppu.write_oam_wasm(idx * 4, &[x as u8, y, name, attr]);
+        let high_addr = 512 + idx / 4;
+        let shift = (idx & 3) * 2;
+        let mut high = ppu.oam()[high_addr as usize] & !(0x03 << shift);
+        high |= (((x as u16 >> 8) & 1) as u8 | (large as u8) << 1) << shift;
+        ppu.write_oam_wasm(high_addr, &[high]);
+    }
+
+    /// Sprites only, 8x8/16x16 at word $4000, every tile a solid color
+    /// matching its name's low nibble
+    fn create_sprite_ppu() -> Ppu {
+        let mut ppu = create_ppu(1, 0x10);
+        ppu.write_register(0x2101, 0x02);
+        for name in 0..512u16 {
+            let addr = 0x8000u16.wrapping_add(name * 32);
+            ppu.write_vram_wasm(addr, &solid_tile(4, (name & 0x0F) as u8));
+        }
+        park_sprites(&mut ppu);
+        ppu
+    }
+
+    /// Color of sprite palette 0 entry `index`
+    fn obj_color(ppu: &Ppu, index: u16) -> u32 {
+        color(ppu, 128 + index)
+    }
+
+    #[test]
+    fn test_sprite_range_over() {
+        let mut ppu = create_sprite_ppu();
+        for idx in 0..33 {
+            set_sprite(&mut ppu, idx, idx as i16 * 7, 10, 1 + (idx == 32) as u8, 0, false);
+        }
+        let line = render_line(&mut ppu, 11);
+        assert_eq!(ppu.read_register(0x213E), 0x41);
+        // The 33rd sprite is dropped
+        assert_eq!(line[31 * 7 + 7], obj_color(&ppu, 1));
+        assert_eq!(line[32 * 7 + 7], color(&ppu, 0));
+
+        // Flags stay set until the next frame starts
+        render_line(&mut ppu, 40);
+        assert_eq!(ppu.read_register(0x213E), 0x41);
+        ppu.scanline = 261;
+        ppu.dot = 340;
+        ppu.step();
+        assert_eq!(ppu.read_register(0x213E), 0x01);
+    }
+
+    #[test]
+    fn test_sprite_time_over() {
+        let mut ppu = create_sprite_ppu();
+        // 18 16x16 sprites need 36 slivers
+        for idx in 0..18 {
+            set_sprite(&mut ppu, idx, idx as i16 * 14, 10, 2, 0, true);
+        }
+        let line = render_line(&mut ppu, 11);
+        assert_eq!(ppu.read_register(0x213E), 0x81);
+        // Fetching starts with the last sprite, so sprite 0 loses both tiles
+        assert_eq!(line[0], color(&ppu, 0));
+        assert_eq!(line[13], color(&ppu, 0));
+        assert_eq!(line[14], obj_color(&ppu, 2));
+        assert_eq!(line[14 + 8], obj_color(&ppu, 3));
+
+        // Slivers off screen don't count
+        set_sprite(&mut ppu, 0, -16, 10, 2, 0, true);
+        ppu.stat77 = 0;
+        render_line(&mut ppu, 11);
+        assert_eq!(ppu.read_register(0x213E), 0x01);
+    }
+
+    #[test]
+    fn test_sprite_priority_rotation() {
+        let mut ppu = create_sprite_ppu();
+        set_sprite(&mut ppu, 0, 0, 10, 1, 0x30, false);
+        set_sprite(&mut ppu, 1, 4, 10, 2, 0x00, false);
+        // Lower OAM index wins, even with different priority bits
+        assert_eq!(render_line(&mut ppu, 11)[4], obj_color(&ppu, 1));
+
+        // Start evaluation at sprite 1
+        ppu.write_register(0x2102, 0x02);
+        ppu.write_register(0x2103, 0x80);
+        assert_eq!(render_line(&mut ppu, 11)[4], obj_color(&ppu, 2));
+        // Without rotation, sprite 0 is first again
+        ppu.write_register(0x2103, 0x00);
+        assert_eq!(render_line(&mut ppu, 11)[4], obj_color(&ppu, 1));
+    }
+
+    #[test]
+    fn test_sprite_sizes_and_name_select() {
+        let mut ppu = create_sprite_ppu();
+        // 16x32 small sprites (undocumented size 6)
+        ppu.write_register(0x2101, 0xC2);
+        set_sprite(&mut ppu, 0, 0, 10, 0x0E, 0x40, false);
+        // Rows 2-3 of the tile grid, columns wrap from $F to $0
+        let line = render_line(&mut ppu, 11 + 31);
+        assert_eq!(line[0], obj_color(&ppu, 0x0F));
+        assert_eq!(line[8], obj_color(&ppu, 0x0E));
+        assert_eq!(render_line(&mut ppu, 11 + 32)[0], color(&ppu, 0));
+
+        // Names $100+ skip the name select gap: 2 pages after the base
+        ppu.write_register(0x2101, 0x0A);
+        ppu.write_vram_wasm(0x8000 + (2 << 13) + 5 * 32, &solid_tile(4, 9));
+        set_sprite(&mut ppu, 0, 0, 10, 5, 0x01, false);
+        assert_eq!(render_line(&mut ppu, 11)[0], obj_color(&ppu, 9));
+    }
+
+    #[test]
+    fn test_pseudo_hires_widens_frame() {
+        let mut ppu = create_windowed_ppu();
+        ppu.write_register(0x2132, 0x20 | 7);
+        let normal = render_line(&mut ppu, 1);
+        assert_eq!(normal.len(), 256);
+
+        ppu.write_register(0x2133, 0x08);
+        let line = render_line(&mut ppu, 2);
+        assert_eq!(line.len(), 512);
+        assert_eq!(line[0], color(&ppu, 7));
+        assert_eq!(line[1], color(&ppu, 1));
+        // The earlier line was widened to match
+        assert_eq!(ppu.framebuffer[0], color(&ppu, 1));
+        assert_eq!(ppu.framebuffer[1], color(&ppu, 1));
+        // Later normal lines are written doubled
+        ppu.write_register(0x2133, 0x00);
+        let line = render_line(&mut ppu, 3);
+        assert_eq!(line[0], color(&ppu, 1));
+        assert_eq!(line[1], color(&ppu, 1));
+    }
+
+    /// Step the PPU through one whole frame
+    fn run_frame(ppu: &mut Ppu) {
+        while !ppu.step() {}
+    }
+
+    #[test]
+    fn test_output_size() {
+        let mut ppu = create_windowed_ppu();
+        run_frame(&mut ppu);
+        assert_eq!(ppu.get_output_size(), (256, 224));
+
+        // Overscan extends the display to 239 lines
+        ppu.write_register(0x2133, 0x04);
+        ppu.write_cgram_wasm(0, &[0x7C00]);
+        run_frame(&mut ppu);
+        assert_eq!(ppu.get_output_size(), (256, 239));
+        assert_eq!(ppu.framebuffer[238 * 512], color(&ppu, 0x7C00));
+
+        ppu.write_register(0x2105, 0x05);
+        ppu.write_register(0x2133, 0x01);
+        run_frame(&mut ppu);
+        assert_eq!(ppu.get_output_size(), (512, 448));
+    }
+
+    #[test]
+    fn test_interlace_fields() {
+        let mut ppu = create_windowed_ppu();
+        ppu.write_register(0x2133, 0x01);
+        let field = ppu.read_register(0x213F) & 0x80;
+        ppu.write_cgram_wasm(1, &[0x001F]);
+        ppu.scanline = 5;
+        ppu.render_scanline();
+        let row = (4 * 2 + (field >> 7) as usize) * 512;
+        assert_eq!(ppu.framebuffer[row], color(&ppu, 0x001F));
+
+        // The field flips every frame
+        run_frame(&mut ppu);
+        assert_eq!(ppu.read_register(0x213F) & 0x80, field ^ 0x80);
+    }
+
+    #[test]
+    fn test_interlace_hires_fields() {
+        let mut ppu = create_ppu(5, 0x01);
+        ppu.write_register(0x212D, 0x01);
+        ppu.write_register(0x2133, 0x01);
+        ppu.write_register(0x2107, 0x60);
+        ppu.write_register(0x210B, 0x01);
+        ppu.write_vram_wasm(0x2000 + 32, &solid_tile(4, 1));
+        ppu.write_vram_wasm(0x2000 + 64, &solid_tile(4, 2));
+        let map = ppu.bg1_tilemap_addr;
+        for row in 0..32 {
+            set_tile(&mut ppu, map, 0, row, 0x0001);
+        }
+
+        // Widening the second field must not touch the first field's rows
+        run_frame(&mut ppu);
+        run_frame(&mut ppu);
+        assert_eq!(ppu.get_output_size(), (512, 448));
+        for row in [0, 1, 100, 101, 447] {
+            let offset = row * 512;
+            assert_eq!(ppu.framebuffer[offset + 7], color(&ppu, 1), "row {}", row);
+            assert_eq!(ppu.framebuffer[offset + 8], color(&ppu, 2), "row {}", row);
+            assert_eq!(ppu.framebuffer[offset + 16], color(&ppu, 0), "row {}", row);
+        }
+    }
+
+    /// Step to `dot` of `scanline`, change the fixed color added to the
+    /// backdrop there and finish the line; returns the line's framebuffer row
+    fn change_backdrop_mid_line(renderer: Renderer, scanline: u16, dot: u16) -> Vec<u32> {
+        let mut ppu = create_ppu(1, 0);
+        ppu.set_renderer(renderer);
+        ppu.write_cgram_wasm(0, &[0x001F]);
+        ppu.write_register(0x2131, 0x20);
+        while !(ppu.scanline == scanline && ppu.dot == dot) {
+            ppu.step();
+        }
+        ppu.write_register(0x2132, 0x80 | 31);
+        while ppu.scanline == scanline {
+            ppu.step();
+        }
+        let offset = (scanline as usize - 1) * 512;
+        ppu.framebuffer[offset..offset + 256].to_vec()
+    }
+
+    #[test]
+    fn test_accurate_renderer_mid_line_write() {
+        let line = change_backdrop_mid_line(Renderer::Accurate, 5, FIRST_PIXEL_DOT + 100);
+        let (red, magenta) = (color(&Ppu::new(), 0x001F), color(&Ppu::new(), 0x7C1F));
+        assert!(line[..100].iter().all(|&p| p == red));
+        assert!(line[100..].iter().all(|&p| p == magenta));
+
+        // The fast renderer draws the whole line before the write
+        let line = change_backdrop_mid_line(Renderer::Fast, 5, FIRST_PIXEL_DOT + 100);
+        assert!(line.iter().all(|&p| p == red));
+    }
+
+    /// Step the accurate renderer to pixel `x` of `scanline`, run `write`
+    /// there and finish the line; returns the line's framebuffer row
+    fn write_mid_line(ppu: &mut Ppu, scanline: u16, x: u16, write: impl FnOnce(&mut Ppu)) -> Vec<u32> {
+        ppu.set_renderer(Renderer::Accurate);
+        while !(ppu.scanline == scanline && ppu.dot == FIRST_PIXEL_DOT + x) {
+            ppu.step();
+        }
+        write(ppu);
+        while ppu.scanline == scanline {
+            ppu.step();
+        }
+        let offset = (scanline as usize - 1) * 512;
+        ppu.framebuffer[offset..offset + 512].to_vec()
+    }
+
+    #[test]
+    fn test_accurate_renderer_mid_line_scroll() {
+        let mut ppu = create_ppu(1, 0x01);
+        ppu.write_register(0x2107, 0x60);
+        ppu.write_register(0x210B, 0x01);
+        ppu.write_vram_wasm(0x2000 + 32, &solid_tile(4, 1));
+        ppu.write_vram_wasm(0x2000 + 64, &solid_tile(4, 2));
+        let map = ppu.bg1_tilemap_addr;
+        for col in 0..32 {
+            set_tile(&mut ppu, map, col, 0, 1 + (col & 1));
+        }
+
+        let line = write_mid_line(&mut ppu, 5, 100, |ppu| {
+            ppu.write_register(0x210D, 8);
+            ppu.write_register(0x210D, 0);
+        });
+        let (first, second) = (color(&ppu, 1), color(&ppu, 2));
+        assert_eq!(line[8], second);
+        assert_eq!(line[99], first);
+        // Pixels from the write on are drawn scrolled by one tile
+        assert_eq!(line[100], second);
+        assert_eq!(line[104], first);
+        assert_eq!(line[255], first);
+    }
+
+    #[test]
+    fn test_accurate_renderer_mid_line_bgmode() {
+        let mut ppu = create_ppu(1, 0x05);
+        ppu.write_register(0x2107, 0x60);
+        ppu.write_register(0x2109, 0x68);
+        ppu.write_register(0x210B, 0x01);
+        ppu.write_register(0x210C, 0x02);
+        ppu.write_vram_wasm(0x2000 + 32, &solid_tile(4, 1));
+        ppu.write_vram_wasm(0x4000 + 16, &solid_tile(2, 2));
+        let (bg1_map, bg3_map) = (ppu.bg1_tilemap_addr, ppu.bg3_tilemap_addr);
+        for col in 0..32 {
+            set_tile(&mut ppu, bg1_map, col, 0, 0x0001 | 0x2000);
+            set_tile(&mut ppu, bg3_map, col, 0, 0x0001 | 0x2000);
+        }
+
+        // BG3 comes to the front from the write on
+        let line = write_mid_line(&mut ppu, 5, 100, |ppu| ppu.write_register(0x2105, 0x09));
+        assert!(line[..100].iter().all(|&p| p == color(&ppu, 1)));
+        assert!(line[100..256].iter().all(|&p| p == color(&ppu, 2)));
+    }
+
+    #[test]
+    fn test_accurate_renderer_mid_line_hires() {
+        let mut ppu = create_windowed_ppu();
+        ppu.write_register(0x2132, 0x20 | 7);
+        let line = write_mid_line(&mut ppu, 5, 100, |ppu| ppu.write_register(0x2133, 0x08));
+        let (main, sub) = (color(&ppu, 1), color(&ppu, 7));
+
+        // The start of the line is widened, the rest is drawn hi-res
+        assert!(line[..200].iter().all(|&p| p == main));
+        assert_eq!(line[200], sub);
+        assert_eq!(line[201], main);
+        assert_eq!(line[510], sub);
+        assert_eq!(line[511], main);
+        // Earlier lines of the frame were widened too
+        assert!(ppu.framebuffer[..512].iter().all(|&p| p == main));
+    }
+
+    #[test]
+    fn test_tilemap_sizes() {
+        let mut ppu = create_ppu(1, 0x01);
+        ppu.write_register(0x210B, 0x01);
+        for tile in 1..4 {
+            ppu.write_vram_wasm(0x2000 + tile * 32, &solid_tile(4, tile as u8));
+            set_tile(&mut ppu, 0xC000 + tile * 0x800, 0, 0, tile);
+        }
+        let scrolled = |ppu: &mut Ppu, size: u8, hscroll: u8, vscroll: u8| {
+            ppu.write_register(0x2107, 0x60 | size);
+            for (register, value) in [(0x210D, hscroll), (0x210E, vscroll)] {
+                ppu.write_register(register, 0x00);
+                ppu.write_register(register, value);
+            }
+            render_line(ppu, 1)[0]
+        };
+
+        // 64x64: screens left to right, then top to bottom
+        assert_eq!(scrolled(&mut ppu, 3, 1, 0), color(&ppu, 1));
+        assert_eq!(scrolled(&mut ppu, 3, 0, 1), color(&ppu, 2));
+        assert_eq!(scrolled(&mut ppu, 3, 1, 1), color(&ppu, 3));
+        // 64x32 wraps vertically after one screen
+        assert_eq!(scrolled(&mut ppu, 1, 1, 1), color(&ppu, 1));
+        // 32x64 stacks its second screen directly below
+        assert_eq!(scrolled(&mut ppu, 2, 0, 1), color(&ppu, 1));
+        assert_eq!(scrolled(&mut ppu, 2, 1, 0), color(&ppu, 0));
+    }
+
+    #[test]
+    fn test_direct_color() {
+        let mut ppu = create_ppu(3, 0x01);
+        ppu.write_register(0x2107, 0x60);
+        ppu.write_register(0x210B, 0x01);
+        ppu.write_vram_wasm(0x2000 + 64, &solid_tile(8, 0x9D));
+        set_tile(&mut ppu, 0xC000, 0, 0, 0x0001 | (5 << 10));
+        assert_eq!(render_line(&mut ppu, 1)[0], color(&ppu, 0x9D));
+
+        // BBGGGRRR 10 011 101 with palette bits BGR 101
+        ppu.write_register(0x2130, 0x01);
+        assert_eq!(render_line(&mut ppu, 1)[0], color(&ppu, 22 | (12 << 5) | (20 << 10)));
+
+        // Mode 7 has no palette bits
+        let mut ppu = create_mode7_ppu(0x9D);
+        ppu.write_register(0x2130, 0x01);
+        assert_eq!(render_line(&mut ppu, 1)[8], color(&ppu, 20 | (12 << 5) | (16 << 10)));
+    }
+
+    fn write_vram_word(ppu: &mut Ppu, word: u16) {
+        ppu.write_register(0x2118, word as u8);
+        ppu.write_register(0x2119, (word >> 8) as u8);
+    }
+
+    #[test]
+    fn test_vram_remapping() {
+        let mut ppu = Ppu::new();
+        // Increment after the high byte, 8-bit rotation
+        ppu.write_register(0x2115, 0x84);
+        ppu.write_register(0x2116, 0x00);
+        ppu.write_register(0x2117, 0x00);
+        for word in 0..16 {
+            write_vram_word(&mut ppu, word);
+        }
+        // aaaaaaaaYYYxxxxx -> aaaaaaaaxxxxxYYY: eight words per tile row
+        assert_eq!(ppu.vram()[0..2], [0, 0]);
+        assert_eq!(ppu.vram()[16..18], [1, 0]);
+        assert_eq!(ppu.vram()[32..34], [2, 0]);
+
+        // 10-bit rotation fills one 8bpp tile row every 32 words
+        ppu.write_register(0x2115, 0x8C);
+        ppu.write_register(0x2116, 0x81);
+        ppu.write_register(0x2117, 0x04);
+        write_vram_word(&mut ppu, 0xBEEF);
+        let addr = ((0x0400 | (0x01 << 3) | 1) * 2) as usize;
+        assert_eq!(ppu.vram()[addr..addr + 2], [0xEF, 0xBE]);
+    }
+
+    #[test]
+    fn test_vram_read_prefetch() {
+        let mut ppu = Ppu::new();
+        ppu.write_vram_wasm(0x20, &[0x11, 0x22, 0x33, 0x44]);
+        ppu.write_register(0x2115, 0x80);
+        ppu.write_register(0x2116, 0x10);
+        ppu.write_register(0x2117, 0x00);
+        // The address write loaded word $10 into the buffer
+        assert_eq!(ppu.read_register(0x2139), 0x11);
+        assert_eq!(ppu.read_register(0x213A), 0x22);
+        // The high read refilled the buffer from word $10 before
+        // incrementing, so the next word shows up one read late
+        assert_eq!(ppu.read_register(0x2139), 0x11);
+        assert_eq!(ppu.read_register(0x213A), 0x22);
+        assert_eq!(ppu.read_register(0x2139), 0x33);
+    }
+
+    #[test]
+    fn test_access_during_active_display() {
+        let mut ppu = create_ppu(1, 0);
+        ppu.vblank = false;
+        ppu.scanline = 20;
+        ppu.dot = 100;
+
+        // VRAM and OAM are locked; addresses still advance
+        ppu.write_register(0x2115, 0x80);
+        write_vram_word(&mut ppu, 0x1234);
+        assert_eq!(ppu.vram()[0..2], [0, 0]);
+        ppu.write_register(0x2102, 0x00);
+        ppu.write_register(0x2104, 0x55);
+        assert_eq!(ppu.oam()[0], 0);
+        // CGRAM is locked while the line is being drawn
+        ppu.write_register(0x2121, 0x10);
+        ppu.write_register(0x2122, 0xFF);
+        ppu.write_register(0x2122, 0x7F);
+        assert_eq!(ppu.cgram()[0x10], 0x10);
+
+        // ... but writable during HBlank
+        ppu.dot = 300;
+        ppu.write_register(0x2121, 0x10);
+        ppu.write_register(0x2122, 0xFF);
+        ppu.write_register(0x2122, 0x7F);
+        assert_eq!(ppu.cgram()[0x10], 0x7FFF);
+
+        // Forced blank unlocks everything
+        ppu.dot = 100;
+        ppu.write_register(0x2100, 0x80);
+        write_vram_word(&mut ppu, 0x1234);
+        assert_eq!(ppu.vram()[2..4], [0x34, 0x12]);
+        ppu.write_register(0x2104, 0x55);
+        assert_eq!(ppu.oam()[1], 0x55);
+    }
+}
@@ -0,0 +1,384 @@
+//! Test ROM Builder
+//!
+//! Builds LoROM, HiROM and ExHiROM cartridge images with a valid header,
+//! checksum and interrupt vectors, for tests that need a `Cartridge`.
+//! Compiled for the crate's own tests and, with the `testing` feature, for
+//! downstream crates.
+//!
+//! Code and data are placed by CPU address, so the same test can target any
+//! mapping:
+//!
+//! ```ignore
+//! let rom = RomBuilder::lorom(0x8000)
+//!     .with_title("MY TEST")
+//!     .with_code(0x008000, &[0xA9, 0x42, 0xDB]) // LDA #$42; STP
+//!     .build();
+//! ```
+
+use crate::cartridge::{Cartridge, MappingMode};
+
+/// Reset vector target used unless overridden
+const DEFAULT_RESET: u16 = 0x8000;
+
+/// Interrupt vectors in the bank $00 vector table
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Vector {
+    Cop,
+    /// Native mode only; emulation mode BRK shares the IRQ vector
+    Brk,
+    Abort,
+    Nmi,
+    /// Emulation mode only
+    Reset,
+    Irq,
+}
+
+impl Vector {
+    /// CPU address of the native mode vector, if there is one
+    pub fn native_addr(&self) -> Option<u16> {
+        match self {
+            Vector::Cop => Some(0xFFE4),
+            Vector::Brk => Some(0xFFE6),
+            Vector::Abort => Some(0xFFE8),
+            Vector::Nmi => Some(0xFFEA),
+            Vector::Reset => None,
+            Vector::Irq => Some(0xFFEE),
+        }
+    }
+
+    /// CPU address of the emulation mode vector, if there is one
+    pub fn emulation_addr(&self) -> Option<u16> {
+        match self {
+            Vector::Cop => Some(0xFFF4),
+            Vector::Brk => None,
+            Vector::Abort => Some(0xFFF8),
+            Vector::Nmi => Some(0xFFFA),
+            Vector::Reset => Some(0xFFFC),
+            Vector::Irq => Some(0xFFFE),
+        }
+    }
+}
+
+/// Builder for SNES cartridge images
+pub struct RomBuilder {
+    mapping: MappingMode,
+    rom: Vec<u8>,
+    title: String,
+    fast_rom: bool,
+    chipset: u8,
+    sram_size: usize,
+    region: u8,
+    version: u8,
+    /// (CPU address in bank $00, target) pairs
+    vectors: Vec<(u16, u16)>,
+}
+
+impl RomBuilder {
+    /// Create an image of `size` bytes filled with $FF
+    ///
+    /// The size is raised to the smallest image that contains the header
+    /// (32KB LoROM, 64KB HiROM, 4MB + 64KB ExHiROM).
+    pub fn new(mapping: MappingMode, size: usize) -> Self {
+        let size = size.max(header_offset(mapping) + 0x40);
+        let mut builder = Self {
+            mapping,
+            rom: vec![0xFF; size],
+            title: String::new(),
+            fast_rom: false,
+            chipset: 0x00,
+            sram_size: 0,
+            region: 0x01,
+            version: 0x00,
+            vectors: Vec::new(),
+        };
+        for vector in [Vector::Cop, Vector::Brk, Vector::Abort, Vector::Nmi, Vector::Irq] {
+            builder.set_vector(vector, 0x0000);
+        }
+        builder.set_vector(Vector::Reset, DEFAULT_RESET);
+        builder
+    }
+
+    pub fn lorom(size: usize) -> Self {
+        Self::new(MappingMode::LoRom, size)
+    }
+
+    pub fn hirom(size: usize) -> Self {
+        Self::new(MappingMode::HiRom, size)
+    }
+
+    pub fn exhirom(size: usize) -> Self {
+        Self::new(MappingMode::ExHiRom, size)
+    }
+
+    /// Game title (truncated or space padded to 21 characters)
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Set the FastROM bit of the map mode byte
+    pub fn with_fast_rom(mut self, fast_rom: bool) -> Self {
+        self.fast_rom = fast_rom;
+        self
+    }
+
+    /// Raw chipset byte ($FFD6), e.g. $02 for ROM+RAM+battery or $03 for DSP
+    pub fn with_chipset(mut self, chipset: u8) -> Self {
+        self.chipset = chipset;
+        self
+    }
+
+    /// SRAM size in bytes (rounded up to a power of two, at least 2KB)
+    pub fn with_sram(mut self, size: usize) -> Self {
+        self.sram_size = size;
+        self
+    }
+
+    /// Raw region byte ($FFD9); defaults to North America
+    pub fn with_region(mut self, region: u8) -> Self {
+        self.region = region;
+        self
+    }
+
+    pub fn with_version(mut self, version: u8) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Point an interrupt vector at `target` in bank $00
+    ///
+    /// Sets both the native and emulation mode entries where they exist.
+    pub fn with_vector(mut self, vector: Vector, target: u16) -> Self {
+        self.set_vector(vector, target);
+        self
+    }
+
+    /// Write `bytes` starting at a CPU address
+    pub fn with_code(mut self, addr: u32, bytes: &[u8]) -> Self {
+        self.write(addr, bytes);
+        self
+    }
+
+    /// Write `bytes` starting at a CPU address
+    ///
+    /// Panics if any byte lands outside ROM.
+    pub fn write(&mut self, addr: u32, bytes: &[u8]) {
+        for (i, &byte) in bytes.iter().enumerate() {
+            let cpu_addr = (addr + i as u32) & 0xFFFFFF;
+            let offset = rom_offset(self.mapping, cpu_addr)
+                .filter(|&offset| offset < self.rom.len())
+                .unwrap_or_else(|| panic!("${:06X} is not mapped to ROM", cpu_addr));
+            self.rom[offset] = byte;
+        }
+    }
+
+    /// Write `bytes` starting at a file offset
+    pub fn write_offset(&mut self, offset: usize, bytes: &[u8]) {
+        self.rom[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// Finish the header and checksum and return the image
+    pub fn build(mut self) -> Vec<u8> {
+        let header = header_offset(self.mapping);
+
+        let mut title = [b' '; 21];
+        for (dst, src) in title.iter_mut().zip(self.title.bytes()) {
+            *dst = src;
+        }
+        let map_mode = match self.mapping {
+            MappingMode::LoRom => 0x20,
+            MappingMode::HiRom => 0x21,
+            MappingMode::ExHiRom => 0x25,
+        } | if self.fast_rom { 0x10 } else { 0x00 };
+
+        self.rom[header..header + 21].copy_from_slice(&title);
+        self.rom[header + 0x15] = map_mode;
+        self.rom[header + 0x16] = self.chipset;
+        self.rom[header + 0x17] = size_code(self.rom.len());
+        self.rom[header + 0x18] = if self.sram_size == 0 { 0 } else { size_code(self.sram_size).max(1) };
+        self.rom[header + 0x19] = self.region;
+        self.rom[header + 0x1A] = 0x00;
+        self.rom[header + 0x1B] = self.version;
+
+        for (addr, target) in std::mem::take(&mut self.vectors) {
+            self.write(addr as u32, &target.to_le_bytes());
+        }
+
+        // Checksum is computed with complement $FFFF / checksum $0000 in place
+        self.rom[header + 0x1C..header + 0x20].copy_from_slice(&[0xFF, 0xFF, 0x00, 0x00]);
+        let sum = checksum(&self.rom);
+        self.rom[header + 0x1C..header + 0x1E].copy_from_slice(&(!sum).to_le_bytes());
+        self.rom[header + 0x1E..header + 0x20].copy_from_slice(&sum.to_le_bytes());
+
+        self.rom
+    }
+
+    /// Build the image and load it with `Cartridge::from_rom`
+    pub fn build_cartridge(self) -> Result<Cartridge, String> {
+        Cartridge::from_rom(self.build())
+    }
+
+    fn set_vector(&mut self, vector: Vector, target: u16) {
+        for addr in [vector.native_addr(), vector.emulation_addr()].into_iter().flatten() {
+            self.vectors.retain(|&(a, _)| a != addr);
+            self.vectors.push((addr, target));
+        }
+    }
+}
+
+/// File offset of the internal header for a mapping
+pub fn header_offset(mapping: MappingMode) -> usize {
+    match mapping {
+        MappingMode::LoRom => 0x7FC0,
+        MappingMode::HiRom => 0xFFC0,
+        MappingMode::ExHiRom => 0x40FFC0,
+    }
+}
+
+/// File offset a CPU address reads from, if it maps to ROM
+pub fn rom_offset(mapping: MappingMode, addr: u32) -> Option<usize> {
+    let bank = ((addr >> 16) & 0xFF) as usize;
+    let offset = (addr & 0xFFFF) as usize;
+    let upper_half = offset >= 0x8000;
+
+    match mapping {
+        MappingMode::LoRom => match bank {
+            0x7E | 0x7F => None,
+            _ if upper_half => Some(((bank & 0x7F) << 15) | (offset & 0x7FFF)),
+            _ => None,
+        },
+        MappingMode::HiRom => match bank {
+            0x40..=0x7D | 0xC0..=0xFF => Some(((bank & 0x3F) << 16) | offset),
+            0x00..=0x3F | 0x80..=0xBF if upper_half => Some(((bank & 0x3F) << 16) | offset),
+            _ => None,
+        },
+        MappingMode::ExHiRom => match bank {
+            0xC0..=0xFF => Some(((bank & 0x3F) << 16) | offset),
+            0x40..=0x7D => Some(0x400000 | ((bank & 0x3F) << 16) | offset),
+            0x80..=0xBF if upper_half => Some(((bank & 0x3F) << 16) | offset),
+            0x00..=0x3F if upper_half => Some(0x400000 | ((bank & 0x3F) << 16) | offset),
+            _ => None,
+        },
+    }
+}
+
+/// Header checksum: 16-bit sum of all bytes
+///
+/// Images that are not a power of two in size are summed as the hardware
+/// sees them, with the remainder mirrored up to the next power of two.
+pub fn checksum(rom: &[u8]) -> u16 {
+    if rom.is_empty() {
+        return 0;
+    }
+    mirrored_sum(rom, rom.len().next_power_of_two()) as u16
+}
+
+fn mirrored_sum(data: &[u8], len: usize) -> u32 {
+    let base = if data.len().is_power_of_two() {
+        data.len()
+    } else {
+        data.len().next_power_of_two() / 2
+    };
+    let head = data[..base].iter().fold(0u32, |sum, &b| sum.wrapping_add(b as u32));
+    if base == data.len() {
+        head.wrapping_mul((len / base) as u32)
+    } else {
+        head.wrapping_add(mirrored_sum(&data[base..], len - base))
+    }
+}
+
+/// Header size code: smallest n with 1KB << n >= size
+fn size_code(size: usize) -> u8 {
+    let kb = size.div_ceil(1024).next_power_of_two();
+    kb.trailing_zeros() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::CartridgeType;
+    use crate::emulator::Emulator;
+
+    #[test]
+    fn test_lorom_header() {
+        let rom = RomBuilder::lorom(512 * 1024)
+            .with_title("CPU TEST")
+            .with_vector(Vector::Nmi, 0x8100)
+            .build();
+
+        assert_eq!(rom.len(), 512 * 1024);
+        assert_eq!(&rom[0x7FC0..0x7FD5], b"CPU TEST             ");
+        assert_eq!(rom[0x7FD5], 0x20);
+        assert_eq!(rom[0x7FD7], 0x09);
+        assert_eq!(&rom[0x7FEA..0x7FEC], &[0x00, 0x81]);
+        assert_eq!(&rom[0x7FFA..0x7FFC], &[0x00, 0x81]);
+        assert_eq!(&rom[0x7FFC..0x7FFE], &[0x00, 0x80]);
+
+        let sum = u16::from_le_bytes([rom[0x7FDE], rom[0x7FDF]]);
+        let complement = u16::from_le_bytes([rom[0x7FDC], rom[0x7FDD]]);
+        assert_eq!(sum, !complement);
+        assert_eq!(sum, checksum(&rom));
+    }
+
+    #[test]
+    fn test_cartridge_detection() {
+        let cartridge = RomBuilder::lorom(0x8000)
+            .with_title("LO")
+            .with_sram(8 * 1024)
+            .with_chipset(0x02)
+            .build_cartridge()
+            .unwrap();
+        assert_eq!(cartridge.mapping_mode(), MappingMode::LoRom);
+        assert_eq!(cartridge.title(), "LO");
+        assert_eq!(cartridge.sram_size(), 8 * 1024);
+        assert_eq!(cartridge.cartridge_type(), CartridgeType::RomRamBattery);
+
+        let cartridge = RomBuilder::hirom(0x20000).with_title("HI").build_cartridge().unwrap();
+        assert_eq!(cartridge.mapping_mode(), MappingMode::HiRom);
+        assert_eq!(cartridge.title(), "HI");
+
+        let cartridge = RomBuilder::exhirom(0x600000).with_title("EXHI").build_cartridge().unwrap();
+        assert_eq!(cartridge.mapping_mode(), MappingMode::ExHiRom);
+        assert_eq!(cartridge.title(), "EXHI");
+        assert_eq!(cartridge.rom_data().len(), 0x600000);
+    }
+
+    #[test]
+    fn test_rom_offsets() {
+        assert_eq!(rom_offset(MappingMode::LoRom, 0x018000), Some(0x8000));
+        assert_eq!(rom_offset(MappingMode::LoRom, 0x808000), Some(0x0000));
+        assert_eq!(rom_offset(MappingMode::LoRom, 0x002000), None);
+        assert_eq!(rom_offset(MappingMode::HiRom, 0x00FFC0), Some(0xFFC0));
+        assert_eq!(rom_offset(MappingMode::HiRom, 0xC12345), Some(0x12345));
+        assert_eq!(rom_offset(MappingMode::ExHiRom, 0x00FFC0), Some(0x40FFC0));
+        assert_eq!(rom_offset(MappingMode::ExHiRom, 0xC00000), Some(0x000000));
+        assert_eq!(rom_offset(MappingMode::ExHiRom, 0x7E0000), None);
+    }
+
+    #[test]
+    fn test_mirrored_checksum() {
+        // 3 x 32KB: the last 32KB is counted twice
+        let mut rom = vec![0; 0x18000];
+        rom[0x00000] = 1;
+        rom[0x10000] = 2;
+        assert_eq!(checksum(&rom), 1 + 2 * 2);
+    }
+
+    #[test]
+    fn test_lorom_boots() {
+        let rom = RomBuilder::lorom(0x8000)
+            .with_code(0x008000, &[
+                0xA9, 0x42,       // LDA #$42
+                0x8D, 0x10, 0x00, // STA $0010
+                0x80, 0xFE,       // BRA *
+            ])
+            .build();
+
+        let mut emulator = Emulator::new();
+        emulator.load_rom(&rom).unwrap();
+        for _ in 0..3 {
+            emulator.step();
+        }
+        assert_eq!(emulator.memory_mut().unwrap().read(0x000010), 0x42);
+    }
+}
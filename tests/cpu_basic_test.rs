@@ -9,6 +9,7 @@ use znes_wasm::cpu::Cpu65816;
 use znes_wasm::memory::Memory;
 use znes_wasm::cartridge::Cartridge;
 use znes_wasm::emulator::Emulator;
+use znes_wasm::testing::RomBuilder;
 
 // ============================================================================
 // OPCODE CONSTANTS
@@ -379,151 +380,13 @@ pub fn format_instruction_bytes(bytes: &[u8]) -> String {
 // ROM BUILDER
 // ============================================================================
 
-/// ROM Builder for SNES LoROM format
-pub struct RomBuilder {
-    rom: Vec<u8>,
-}
-
-impl RomBuilder {
-    /// Create a new ROM builder with specified size
-    pub fn new(size: usize) -> Self {
-        Self {
-            rom: vec![0xFF; size],
-        }
-    }
-    
-    /// Write a byte at the specified address
-    pub fn write_byte(&mut self, addr: usize, value: u8) {
-        if addr < self.rom.len() {
-            self.rom[addr] = value;
-        }
-    }
-    
-    /// Write a 16-bit value (little-endian)
-    pub fn write_word(&mut self, addr: usize, value: u16) {
-        self.write_byte(addr, (value & 0xFF) as u8);
-        self.write_byte(addr + 1, (value >> 8) as u8);
-    }
-    
-    /// Write a sequence of bytes
-    pub fn write_bytes(&mut self, addr: usize, bytes: &[u8]) {
-        for (i, &byte) in bytes.iter().enumerate() {
-            self.write_byte(addr + i, byte);
-        }
-    }
-    
-    /// Write a null-terminated string
-    pub fn write_string(&mut self, addr: usize, s: &str, max_len: usize) {
-        let bytes = s.as_bytes();
-        let len = bytes.len().min(max_len);
-        for i in 0..len {
-            self.write_byte(addr + i, bytes[i]);
-        }
-        // Pad with spaces
-        for i in len..max_len {
-            self.write_byte(addr + i, 0x20);
-        }
-    }
-    
-    /// Build and return the ROM
-    pub fn build(self) -> Vec<u8> {
-        self.rom
-    }
-}
-
-/// Write LoROM header at $7FC0
-fn write_lorom_header(builder: &mut RomBuilder, title: &str) {
-    // Header starts at $7FC0 in LoROM
-    let header_base = 0x7FC0;
-    
-    // Write game title (21 bytes, padded with spaces)
-    builder.write_string(header_base, title, 21);
-    
-    // ROM makeup byte ($7FD5)
-    // Bit 0: ROM speed (0=slow, 1=fast)
-    // Bits 4-7: Map mode (0=LoROM, 1=HiROM, etc.)
-    builder.write_byte(header_base + 0x15, 0x20); // LoROM, slow
-    
-    // Chipset type ($7FD6) - 0 = ROM only
-    builder.write_byte(header_base + 0x16, 0x00);
-    
-    // ROM size ($7FD7) - $09 = 512KB (2^9 * 1024)
-    builder.write_byte(header_base + 0x17, 0x09);
-    
-    // SRAM size ($7FD8) - 0 = no SRAM
-    builder.write_byte(header_base + 0x18, 0x00);
-    
-    // Country code ($7FD9) - 1 = USA
-    builder.write_byte(header_base + 0x19, 0x01);
-    
-    // Developer ID ($7FDA)
-    builder.write_byte(header_base + 0x1A, 0x00);
-    
-    // Version ($7FDB)
-    builder.write_byte(header_base + 0x1B, 0x00);
-    
-    // Checksum complement will be calculated later ($7FDC-$7FDD)
-    // Checksum will be calculated later ($7FDE-$7FDF)
-    
-    // Interrupt vectors (in LoROM, bank $00 at $FFE4-$FFFF maps to ROM $7FE4-$7FFF)
-    // Native mode vectors
-    builder.write_word(0x7FE4, 0x0000); // COP
-    builder.write_word(0x7FE6, 0x0000); // BRK
-    builder.write_word(0x7FE8, 0x0000); // ABORT
-    builder.write_word(0x7FEA, 0x0000); // NMI
-    builder.write_word(0x7FEC, 0x0000); // Unused
-    builder.write_word(0x7FEE, 0x0000); // IRQ
-    
-    // Emulation mode vectors
-    builder.write_word(0x7FF4, 0x0000); // COP
-    builder.write_word(0x7FF6, 0x0000); // Unused
-    builder.write_word(0x7FF8, 0x0000); // ABORT
-    builder.write_word(0x7FFA, 0x0000); // NMI
-    builder.write_word(0x7FFC, 0x8000); // RESET - points to $8000
-    builder.write_word(0x7FFE, 0x0000); // IRQ/BRK
-}
-
-/// Calculate and write checksum
-fn calculate_checksum(builder: &mut RomBuilder) {
-    let mut sum: u32 = 0;
-    
-    // Sum all bytes in the ROM
-    for &byte in builder.rom.iter() {
-        sum = sum.wrapping_add(byte as u32);
-    }
-    
-    // The checksum is the lower 16 bits
-    let checksum = (sum & 0xFFFF) as u16;
-    let checksum_complement = !checksum;
-    
-    // Write checksum complement at $7FDC
-    builder.write_word(0x7FDC, checksum_complement);
-    
-    // Write checksum at $7FDE
-    builder.write_word(0x7FDE, checksum);
-}
-
-/// Assemble a test sequence at the specified address
-fn assemble_test_sequence(builder: &mut RomBuilder, start_addr: usize, sequence: &[u8]) {
-    builder.write_bytes(start_addr, sequence);
-}
-
 /// Build a complete test ROM with the given test code
 pub fn build_test_rom(test_code: &[u8]) -> Vec<u8> {
-    // Create a 512KB ROM (standard LoROM size)
-    let mut builder = RomBuilder::new(512 * 1024);
-    
-    // Write the LoROM header
-    write_lorom_header(&mut builder, "CPU BASIC TEST");
-    
-    // Write test code at ROM offset $0000
-    // In LoROM, bank $00 at $8000 maps to ROM offset $0000
-    assemble_test_sequence(&mut builder, 0x0000, test_code);
-    
-    // Calculate and write checksum
-    calculate_checksum(&mut builder);
-    
-    builder.build()
+    // 512KB LoROM; bank $00 at $8000 maps to ROM offset $0000
+    RomBuilder::lorom(512 * 1024)
+        .with_title("CPU BASIC TEST")
+        .with_code(0x008000, test_code)
+        .build()
 }
 
 /// Build a simple test ROM with basic instructions
@@ -0,0 +1,404 @@
+//! 65816 Opcode Conformance Tests
+//!
+//! Runs the community SingleStepTests 65816 vectors (one JSON file per
+//! opcode and mode, e.g. `a9.e.json` / `a9.n.json`) through `Cpu65816` on a
+//! flat 24-bit test bus and reports mismatches per opcode and mode.
+//!
+//! The vectors are not checked in, so the test is ignored by default. Point
+//! `SINGLE_STEP_TESTS_DIR` at a local copy (default `tests/65816/v1`) and run
+//! `cargo test --test single_step_test -- --ignored --nocapture` to see the
+//! report; `SINGLE_STEP_OPCODES=a9,eb` restricts the run to some opcodes.
+//! Register, RAM and bus access (address, value and direction of each read
+//! and write, in order) mismatches fail the test; cycle count mismatches are
+//! reported but do not, since internal cycles carry no bus activity.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use znes_wasm::cpu::{Cpu65816, CpuBus};
+
+/// Directory searched when `SINGLE_STEP_TESTS_DIR` is not set
+const DEFAULT_TESTS_DIR: &str = "tests/65816/v1";
+
+/// Mismatch details printed per file
+const MAX_REPORTED_FAILURES: usize = 3;
+
+// ============================================================================
+// TEST BUS
+// ============================================================================
+
+/// One read or write cycle on the bus
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Access {
+    addr: u32,
+    value: u8,
+    write: bool,
+}
+
+/// Flat 16MB address space holding only the bytes a vector touches
+///
+/// Every read and write is logged in order; peeks are not.
+#[derive(Default)]
+struct TestBus {
+    ram: HashMap<u32, u8>,
+    accesses: Vec<Access>,
+}
+
+impl CpuBus for TestBus {
+    fn read(&mut self, addr: u32) -> u8 {
+        let value = self.peek(addr);
+        self.accesses.push(Access { addr: addr & 0xFFFFFF, value, write: false });
+        value
+    }
+
+    fn write(&mut self, addr: u32, value: u8) {
+        self.ram.insert(addr & 0xFFFFFF, value);
+        self.accesses.push(Access { addr: addr & 0xFFFFFF, value, write: true });
+    }
+
+    fn peek(&mut self, addr: u32) -> u8 {
+        self.ram.get(&(addr & 0xFFFFFF)).copied().unwrap_or(0)
+    }
+}
+
+// ============================================================================
+// VECTORS
+// ============================================================================
+
+/// CPU registers and memory from a vector's `initial` or `final` object
+#[derive(Debug, PartialEq)]
+struct State {
+    pc: u16,
+    s: u16,
+    p: u8,
+    a: u16,
+    x: u16,
+    y: u16,
+    dbr: u8,
+    d: u16,
+    pbr: u8,
+    e: bool,
+    ram: Vec<(u32, u8)>,
+}
+
+struct Vector {
+    name: String,
+    initial: State,
+    expected: State,
+    /// Number of bus cycles (including internal operations) the real CPU takes
+    cycles: usize,
+    /// Reads and writes among those cycles, in order
+    accesses: Vec<Access>,
+}
+
+fn field(object: &Value, name: &str) -> Result<u64, String> {
+    object
+        .get(name)
+        .and_then(Value::as_u64)
+        .ok_or_else(|| format!("Missing or invalid field: {}", name))
+}
+
+fn parse_state(object: &Value) -> Result<State, String> {
+    let mut ram = Vec::new();
+    for entry in object.get("ram").and_then(Value::as_array).ok_or("Missing field: ram")? {
+        let pair = entry.as_array().filter(|p| p.len() == 2).ok_or("Invalid ram entry")?;
+        let addr = pair[0].as_u64().ok_or("Invalid ram address")?;
+        let value = pair[1].as_u64().ok_or("Invalid ram value")?;
+        ram.push((addr as u32, value as u8));
+    }
+    ram.sort_unstable();
+
+    Ok(State {
+        pc: field(object, "pc")? as u16,
+        s: field(object, "s")? as u16,
+        p: field(object, "p")? as u8,
+        a: field(object, "a")? as u16,
+        x: field(object, "x")? as u16,
+        y: field(object, "y")? as u16,
+        dbr: field(object, "dbr")? as u8,
+        d: field(object, "d")? as u16,
+        pbr: field(object, "pbr")? as u8,
+        e: field(object, "e")? != 0,
+        ram,
+    })
+}
+
+/// Data cycles of a `cycles` array; entries without a value are internal
+fn parse_accesses(cycles: &[Value]) -> Result<Vec<Access>, String> {
+    let mut accesses = Vec::new();
+    for cycle in cycles {
+        let cycle = cycle.as_array().filter(|c| c.len() == 3).ok_or("Invalid cycle entry")?;
+        let Some(value) = cycle[1].as_u64() else {
+            continue;
+        };
+        let addr = cycle[0].as_u64().ok_or("Invalid cycle address")?;
+        let flags = cycle[2].as_str().ok_or("Invalid cycle flags")?;
+        accesses.push(Access { addr: addr as u32, value: value as u8, write: flags.contains('w') });
+    }
+    Ok(accesses)
+}
+
+fn parse_vectors(json: &str) -> Result<Vec<Vector>, String> {
+    let root: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let mut vectors = Vec::new();
+    for test in root.as_array().ok_or("Expected an array of tests")? {
+        let cycles = test.get("cycles").and_then(Value::as_array).ok_or("Missing field: cycles")?;
+        vectors.push(Vector {
+            name: test.get("name").and_then(Value::as_str).unwrap_or("?").to_string(),
+            initial: parse_state(test.get("initial").ok_or("Missing field: initial")?)?,
+            expected: parse_state(test.get("final").ok_or("Missing field: final")?)?,
+            cycles: cycles.len(),
+            accesses: parse_accesses(cycles)?,
+        });
+    }
+    Ok(vectors)
+}
+
+// ============================================================================
+// RUNNER
+// ============================================================================
+
+/// Outcome of one vector
+struct Outcome {
+    state: State,
+    /// Cycle count returned by `Cpu65816::step`
+    cycles: usize,
+    accesses: Vec<Access>,
+}
+
+fn run_vector(vector: &Vector) -> Outcome {
+    let initial = &vector.initial;
+    let mut bus = TestBus::default();
+    for &(addr, value) in &initial.ram {
+        bus.ram.insert(addr, value);
+    }
+
+    let mut cpu = Cpu65816::new();
+    cpu.pc = initial.pc;
+    cpu.s = initial.s;
+    cpu.p.from_byte(initial.p);
+    cpu.p.e = initial.e;
+    cpu.a = initial.a;
+    cpu.x = initial.x;
+    cpu.y = initial.y;
+    cpu.dbr = initial.dbr;
+    cpu.d = initial.d;
+    cpu.pbr = initial.pbr;
+
+    let cycles = cpu.step(&mut bus) as usize;
+    let accesses = std::mem::take(&mut bus.accesses);
+
+    let ram = vector
+        .expected
+        .ram
+        .iter()
+        .map(|&(addr, _)| (addr, bus.read(addr)))
+        .collect();
+    Outcome {
+        state: State {
+            pc: cpu.pc,
+            s: cpu.s,
+            p: cpu.p.to_byte(),
+            a: cpu.a,
+            x: cpu.x,
+            y: cpu.y,
+            dbr: cpu.dbr,
+            d: cpu.d,
+            pbr: cpu.pbr,
+            e: cpu.p.e,
+            ram,
+        },
+        cycles,
+        accesses,
+    }
+}
+
+/// Describe every field that differs from the expected state
+fn diff_states(expected: &State, actual: &State) -> Vec<String> {
+    let mut diffs = Vec::new();
+    let mut check = |name: &str, expected: u32, actual: u32, width: usize| {
+        if expected != actual {
+            diffs.push(format!("{}: expected {:0w$X}, got {:0w$X}", name, expected, actual, w = width));
+        }
+    };
+    check("PC", expected.pc as u32, actual.pc as u32, 4);
+    check("S", expected.s as u32, actual.s as u32, 4);
+    check("P", expected.p as u32, actual.p as u32, 2);
+    check("A", expected.a as u32, actual.a as u32, 4);
+    check("X", expected.x as u32, actual.x as u32, 4);
+    check("Y", expected.y as u32, actual.y as u32, 4);
+    check("DBR", expected.dbr as u32, actual.dbr as u32, 2);
+    check("D", expected.d as u32, actual.d as u32, 4);
+    check("PBR", expected.pbr as u32, actual.pbr as u32, 2);
+    check("E", expected.e as u32, actual.e as u32, 1);
+    for (&(addr, want), &(_, got)) in expected.ram.iter().zip(&actual.ram) {
+        if want != got {
+            diffs.push(format!("${:06X}: expected {:02X}, got {:02X}", addr, want, got));
+        }
+    }
+    diffs
+}
+
+/// Describe the first bus access that differs from the expected sequence
+fn diff_accesses(expected: &[Access], actual: &[Access]) -> Option<String> {
+    let describe = |access: Option<&Access>| match access {
+        Some(a) => format!("{} ${:06X}={:02X}", if a.write { "write" } else { "read" }, a.addr, a.value),
+        None => "nothing".to_string(),
+    };
+    let index = (0..expected.len().max(actual.len())).find(|&i| expected.get(i) != actual.get(i))?;
+    Some(format!(
+        "bus access {}: expected {}, got {}",
+        index,
+        describe(expected.get(index)),
+        describe(actual.get(index))
+    ))
+}
+
+/// Results for one opcode/mode file
+#[derive(Default)]
+struct FileReport {
+    name: String,
+    total: usize,
+    failed: usize,
+    cycle_mismatches: usize,
+    failures: Vec<String>,
+}
+
+fn run_vectors(name: &str, vectors: &[Vector]) -> FileReport {
+    let mut report = FileReport {
+        name: name.to_string(),
+        total: vectors.len(),
+        ..Default::default()
+    };
+    for vector in vectors {
+        let outcome = run_vector(vector);
+        if outcome.cycles != vector.cycles {
+            report.cycle_mismatches += 1;
+        }
+        let mut diffs = diff_states(&vector.expected, &outcome.state);
+        diffs.extend(diff_accesses(&vector.accesses, &outcome.accesses));
+        if !diffs.is_empty() {
+            report.failed += 1;
+            if report.failures.len() < MAX_REPORTED_FAILURES {
+                report.failures.push(format!("{}: {}", vector.name, diffs.join(", ")));
+            }
+        }
+    }
+    report
+}
+
+fn vector_files(dir: &Path) -> Vec<PathBuf> {
+    let filter: Option<Vec<String>> = std::env::var("SINGLE_STEP_OPCODES")
+        .ok()
+        .map(|list| list.split(',').map(|op| op.trim().to_ascii_lowercase()).collect());
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter(|path| {
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            let opcode = stem.split('.').next().unwrap_or("").to_ascii_lowercase();
+            filter.as_ref().is_none_or(|ops| ops.contains(&opcode))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[test]
+#[ignore = "needs the SingleStepTests vectors; see the module docs"]
+fn test_single_step_vectors() {
+    let dir = std::env::var("SINGLE_STEP_TESTS_DIR").unwrap_or_else(|_| DEFAULT_TESTS_DIR.to_string());
+    let files = vector_files(Path::new(&dir));
+    assert!(!files.is_empty(), "No SingleStepTests vectors in {}", dir);
+
+    let mut failed_files = Vec::new();
+    for path in &files {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("?");
+        let json = fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        let vectors = parse_vectors(&json).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        let report = run_vectors(name, &vectors);
+
+        println!(
+            "{:<12} {:>6}/{:<6} passed  {:>6} cycle count mismatches",
+            report.name,
+            report.total - report.failed,
+            report.total,
+            report.cycle_mismatches
+        );
+        for failure in &report.failures {
+            println!("    {}", failure);
+        }
+        if report.failed > 0 {
+            failed_files.push(report.name);
+        }
+    }
+
+    assert!(
+        failed_files.is_empty(),
+        "{} of {} opcode files have mismatches: {}",
+        failed_files.len(),
+        files.len(),
+        failed_files.join(" ")
+    );
+}
+
+/// Two hand-written vectors in the SingleStepTests format
+const SAMPLE_VECTORS: &str = r#"[
+    {
+        "name": "a9 e 1",
+        "initial": {"pc": 32768, "s": 511, "p": 52, "a": 4660, "x": 0, "y": 0,
+                    "dbr": 0, "d": 0, "pbr": 0, "e": 1,
+                    "ram": [[32768, 169], [32769, 0]]},
+        "final": {"pc": 32770, "s": 511, "p": 54, "a": 4608, "x": 0, "y": 0,
+                  "dbr": 0, "d": 0, "pbr": 0, "e": 1,
+                  "ram": [[32768, 169], [32769, 0]]},
+        "cycles": [[32768, 169, "dp-remx-"], [32769, 0, "-p-remx-"]]
+    },
+    {
+        "name": "8d n 1",
+        "initial": {"pc": 4096, "s": 8191, "p": 0, "a": 48879, "x": 0, "y": 0,
+                    "dbr": 126, "d": 0, "pbr": 18, "e": 0,
+                    "ram": [[1183744, 141], [1183745, 16], [1183746, 32], [8265744, 0], [8265745, 0]]},
+        "final": {"pc": 4099, "s": 8191, "p": 0, "a": 48879, "x": 0, "y": 0,
+                  "dbr": 126, "d": 0, "pbr": 18, "e": 0,
+                  "ram": [[1183744, 141], [1183745, 16], [1183746, 32], [8265744, 239], [8265745, 190]]},
+        "cycles": [[1183744, 141, "dp-remx-"], [1183745, 16, "-p-remx-"], [1183746, 32, "-p-remx-"],
+                   [8265744, 239, "d---mx-w"], [8265745, 190, "d---mx-w"]]
+    }
+]"#;
+
+#[test]
+fn test_harness_on_sample_vectors() {
+    let vectors = parse_vectors(SAMPLE_VECTORS).expect("sample vectors parse");
+    assert_eq!(vectors.len(), 2);
+    assert_eq!(vectors[1].expected.ram[3], (0x7E2010, 0xEF));
+
+    let report = run_vectors("sample", &vectors);
+    assert_eq!(report.failed, 0, "{:?}", report.failures);
+
+    // A wrong expectation is reported with the differing fields
+    let mut broken = parse_vectors(SAMPLE_VECTORS).unwrap();
+    broken[0].expected.a = 0x1242;
+    broken[1].expected.ram[4].1 = 0x00;
+    let report = run_vectors("broken", &broken);
+    assert_eq!(report.failed, 2);
+    assert!(report.failures[0].contains("A: expected 1242, got 1200"));
+    assert!(report.failures[1].contains("$7E2011: expected 00, got BE"));
+
+    // So is a bus access to the wrong address
+    let mut broken = parse_vectors(SAMPLE_VECTORS).unwrap();
+    broken[1].accesses[3].addr = 0x002010;
+    let report = run_vectors("broken", &broken);
+    assert_eq!(report.failed, 1);
+    assert!(report.failures[0].contains("bus access 3: expected write $002010=EF, got write $7E2010=EF"));
+}